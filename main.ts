@@ -1,4 +1,4 @@
-import init, { GameState, FurnaceData, ChestData, DrillData, Item, DroppedItem } from './pkg/wasm_exploration.js';
+import init, { GameState, FurnaceData, ChestData, DrillData, UndergroundBeltData, UndergroundRole, SplitterData, SplitterSide, Item, PlaceableType, DroppedItem, MachineStatus, item_name, machine_status_name } from './pkg/wasm_exploration.js';
 
 // Type definitions for our game state
 let gameState: GameState | null = null;
@@ -7,6 +7,8 @@ let ctx: CanvasRenderingContext2D | null = null;
 const keys: Record<string, boolean> = {};
 let cursorTileX: number | null = null;
 let cursorTileY: number | null = null;
+let dragStartX: number | null = null;
+let dragStartY: number | null = null;
 let furnaceImage: HTMLImageElement | null = null;
 
 // Constants
@@ -15,6 +17,8 @@ let VIEWPORT_HEIGHT = 400;
 let CONSOLE_WIDTH = 250;
 let HELP_BOX_HEIGHT = 120; // Height for help box below viewport
 const TILE_SIZE = 20; // 1 game tile = 20 pixels
+const SAVE_KEY = 'factory-save';
+const AUTOSAVE_INTERVAL_MS = 10000;
 
 // Function to update viewport dimensions based on window size
 function updateViewportDimensions(): void {
@@ -45,7 +49,30 @@ async function run(): Promise<void> {
     });
     
     // Create game state (this will generate a new map)
-    gameState = new GameState();
+    // A ?seed=... URL parameter reproduces a specific world, otherwise resume the saved factory
+    const seedParam = new URLSearchParams(window.location.search).get('seed');
+    const savedGame = localStorage.getItem(SAVE_KEY);
+    if (seedParam !== null) {
+        gameState = GameState.from_seed_str(seedParam);
+    } else if (savedGame !== null) {
+        try {
+            gameState = GameState.load_json(savedGame);
+        } catch (e) {
+            console.warn('Could not load saved game, starting a new one:', e);
+            gameState = new GameState();
+        }
+    } else {
+        gameState = new GameState();
+    }
+
+    // Persist the factory so a page refresh doesn't lose it
+    const saveGame = (): void => {
+        if (gameState) {
+            localStorage.setItem(SAVE_KEY, gameState.save_json());
+        }
+    };
+    setInterval(saveGame, AUTOSAVE_INTERVAL_MS);
+    window.addEventListener('beforeunload', saveGame);
     
     // Setup canvas
     canvas = document.getElementById('game-canvas') as HTMLCanvasElement;
@@ -109,6 +136,26 @@ async function run(): Promise<void> {
         }
     });
     
+    // Drag with the mouse to build a line of the selected item (an L for diagonal drags)
+    canvas.addEventListener('mousedown', () => {
+        if (cursorTileX !== null && cursorTileY !== null && cursorTileX >= 0 && cursorTileY >= 0) {
+            dragStartX = cursorTileX;
+            dragStartY = cursorTileY;
+        }
+    });
+
+    canvas.addEventListener('mouseup', () => {
+        const selectedItem = gameState?.get_selected_item();
+        if (gameState && selectedItem !== undefined && dragStartX !== null && dragStartY !== null
+            && cursorTileX !== null && cursorTileY !== null && cursorTileX >= 0 && cursorTileY >= 0
+            && (cursorTileX !== dragStartX || cursorTileY !== dragStartY)) {
+            // Queued for the next tick so the line goes through step and ends up in recordings
+            gameState.queue_place_line(selectedItem, dragStartX, dragStartY, cursorTileX, cursorTileY);
+        }
+        dragStartX = null;
+        dragStartY = null;
+    });
+
     canvas.addEventListener('mouseleave', () => {
         cursorTileX = null;
         cursorTileY = null;
@@ -117,7 +164,7 @@ async function run(): Promise<void> {
     // Setup keyboard listeners
     window.addEventListener('keydown', (e: KeyboardEvent) => {
         const key = e.key.toLowerCase();
-        if (['w', 'a', 's', 'd', 'm', 'f', 'h', '[', ']', 'b', 'j', 'r', 'p', 'c', 't'].includes(key)) {
+        if (['w', 'a', 's', 'd', 'm', 'f', 'h', '[', ']', 'b', 'j', 'r', 'p', 'c', 't', 'x', 'u', 'l', 'i', 'o', 'g'].includes(key)) {
             keys[key] = true;
             e.preventDefault();
         }
@@ -168,6 +215,12 @@ async function run(): Promise<void> {
             'p': keys['p'] || false,
             'c': keys['c'] || false,
             't': keys['t'] || false,
+            'x': keys['x'] || false,
+            'u': keys['u'] || false,
+            'l': keys['l'] || false,
+            'i': keys['i'] || false,
+            'o': keys['o'] || false,
+            'g': keys['g'] || false,
             'delete': keys['delete'] || false,
         };
         
@@ -190,6 +243,12 @@ async function run(): Promise<void> {
         keys['p'] = false;
         keys['c'] = false;
         keys['t'] = false;
+        keys['x'] = false;
+        keys['u'] = false;
+        keys['l'] = false;
+        keys['i'] = false;
+        keys['o'] = false;
+        keys['g'] = false;
         keys['delete'] = false;
     }
     
@@ -333,24 +392,19 @@ function render(): void {
                 const furnaceData: FurnaceData | undefined = gameState.get_furnace_data(obj.x, obj.y);
                 if (furnaceData && isHovering) {
                     // TypeScript knows these are properties, not methods!
-                    const coalCount = furnaceData.coal_count;
-                    const ironOreCount = furnaceData.iron_ore_count;
-                    const ironPlateCount = furnaceData.iron_plate_count;
-                    const copperCount = furnaceData.copper_count;
-                    const copperPlateCount = furnaceData.copper_plate_count;
+                    const fuel = furnaceData.fuel;
                     const processingTicks = furnaceData.processing_ticks_remaining;
+                    const smeltTicks = furnaceData.smelt_ticks;
                     
                     // Only show items with count > 0
                     const lines: string[] = [];
-                    if (coalCount > 0) lines.push(`Coal: ${coalCount}`);
-                    if (ironOreCount > 0) lines.push(`Iron: ${ironOreCount}`);
-                    if (ironPlateCount > 0) lines.push(`IronP: ${ironPlateCount}`);
-                    if (copperCount > 0) lines.push(`Copper: ${copperCount}`);
-                    if (copperPlateCount > 0) lines.push(`CopperP: ${copperPlateCount}`);
+                    if (fuel.item !== undefined && fuel.count > 0) lines.push(`${getItemName(fuel.item)}: ${fuel.count}`);
+                    for (const item of furnaceData.input_items()) lines.push(`${getItemName(item)}: ${furnaceData.input_count(item)}`);
+                    for (const item of furnaceData.output_items()) lines.push(`${getItemName(item)}: ${furnaceData.output_count(item)}`);
                     
                     // Show processing progress
-                    if (processingTicks > 0) {
-                        const progress = ((60 - processingTicks) / 60 * 100).toFixed(0);
+                    if (processingTicks > 0 && smeltTicks > 0) {
+                        const progress = ((smeltTicks - processingTicks) / smeltTicks * 100).toFixed(0);
                         lines.push(`Processing: ${progress}%`);
                     }
                     
@@ -358,6 +412,11 @@ function render(): void {
                         lines.push('Empty');
                     }
                     
+                    // Say why the furnace is idle
+                    if (furnaceData.status !== MachineStatus.Working) {
+                        lines.push(machine_status_name(furnaceData.status));
+                    }
+                    
                     const textBoxX = objX;
                     const textBoxY = objY - (lines.length * 12 + 8); // Above the furnace, dynamic height
                     const textBoxWidth = 80;
@@ -390,13 +449,20 @@ function render(): void {
                 // Get belt direction
                 const direction = obj.direction_value();
                 
-                // Draw belt pattern (lines perpendicular to direction)
-                ctx.fillStyle = '#6b7280'; // Lighter gray
-                if (direction === 0 || direction === 1) { // North or South - vertical lines
-                    ctx.fillRect(objX + TILE_SIZE / 2 - 1, objY, 2, TILE_SIZE);
-                } else { // East or West - horizontal lines
-                    ctx.fillRect(objX, objY + TILE_SIZE / 2 - 1, TILE_SIZE, 2);
-                }
+                // Draw the centre line from the input edge to the exit edge (round the corner on curves)
+                const inputDirection = gameState.belt_input_direction(obj.x, obj.y) ?? direction;
+                const steps = [[0, -1], [0, 1], [1, 0], [-1, 0]];
+                const [inDx, inDy] = steps[inputDirection];
+                const [outDx, outDy] = steps[direction];
+                const middleX = objX + TILE_SIZE / 2;
+                const middleY = objY + TILE_SIZE / 2;
+                ctx.strokeStyle = '#6b7280'; // Lighter gray
+                ctx.lineWidth = 2;
+                ctx.beginPath();
+                ctx.moveTo(middleX - inDx * TILE_SIZE / 2, middleY - inDy * TILE_SIZE / 2);
+                ctx.lineTo(middleX, middleY);
+                ctx.lineTo(middleX + outDx * TILE_SIZE / 2, middleY + outDy * TILE_SIZE / 2);
+                ctx.stroke();
                 
                 // Draw arrow in belt direction
                 ctx.fillStyle = '#9ca3af'; // Light gray for arrow
@@ -552,15 +618,15 @@ function render(): void {
                 ctx.closePath();
                 ctx.fill();
                 
-                // Draw floating text box above drill showing coal (only when hovering)
+                // Draw floating text box above drill showing fuel (only when hovering)
                 const isHovering = cursorTileX === obj.x && cursorTileY === obj.y;
                 const drillData: DrillData | undefined = gameState.get_drill_data(obj.x, obj.y);
                 if (drillData && isHovering) {
-                    const coalCount = drillData.coal_count;
+                    const fuel = drillData.fuel;
                     const processingTicks = drillData.processing_ticks_remaining;
                     
                     const lines: string[] = [];
-                    if (coalCount > 0) lines.push(`Coal: ${coalCount}`);
+                    if (fuel.item !== undefined && fuel.count > 0) lines.push(`${getItemName(fuel.item)}: ${fuel.count}`);
                     if (processingTicks > 0) {
                         const progress = ((60 - processingTicks) / 60 * 100).toFixed(0);
                         lines.push(`Processing: ${progress}%`);
@@ -568,6 +634,9 @@ function render(): void {
                     if (lines.length === 0) {
                         lines.push('Empty');
                     }
+                    if (drillData.status !== MachineStatus.Working) {
+                        lines.push(machine_status_name(drillData.status));
+                    }
                     
                     const textBoxX = objX;
                     const textBoxY = objY - (lines.length * 12 + 8);
@@ -590,6 +659,112 @@ function render(): void {
                         textY += 12;
                     }
                 }
+            } else if (objType === 5) { // Underground belt
+                // Draw as a belt with a dark tunnel mouth on the tunnel side:
+                // ahead of an entry, behind an exit
+                ctx.fillStyle = '#4b5563'; // Gray belt
+                ctx.fillRect(objX, objY, TILE_SIZE, TILE_SIZE);
+                
+                const direction = obj.direction_value();
+                const data: UndergroundBeltData | undefined = gameState.get_underground_belt_data(obj.x, obj.y);
+                const isEntry = !data || data.role === UndergroundRole.Entry;
+                const [dx, dy] = [[0, -1], [0, 1], [1, 0], [-1, 0]][direction];
+                const mouthSide = isEntry ? 1 : -1;
+                const half = TILE_SIZE / 2;
+                ctx.fillStyle = '#111827'; // Near black tunnel mouth
+                ctx.fillRect(
+                    objX + (dx * mouthSide > 0 ? half : 0),
+                    objY + (dy * mouthSide > 0 ? half : 0),
+                    dx === 0 ? TILE_SIZE : half,
+                    dy === 0 ? TILE_SIZE : half,
+                );
+                
+                // Arrow in the belt direction
+                ctx.fillStyle = '#9ca3af';
+                ctx.beginPath();
+                const tipX = objX + half + dx * (half - 4);
+                const tipY = objY + half + dy * (half - 4);
+                ctx.moveTo(tipX, tipY);
+                ctx.lineTo(tipX - dx * 4 - dy * 3, tipY - dy * 4 - dx * 3);
+                ctx.lineTo(tipX - dx * 4 + dy * 3, tipY - dy * 4 + dx * 3);
+                ctx.closePath();
+                ctx.fill();
+                
+                // Outline ends that have nothing to connect to
+                if (data && !data.is_paired) {
+                    ctx.strokeStyle = '#ef4444';
+                    ctx.lineWidth = 1;
+                    ctx.strokeRect(objX + 0.5, objY + 0.5, TILE_SIZE - 1, TILE_SIZE - 1);
+                }
+            } else if (objType === 6) { // Splitter
+                // Two tiles wide: the placed tile is the left half, the tile to its right the right half
+                const direction = obj.direction_value();
+                const [dx, dy] = [[0, -1], [0, 1], [1, 0], [-1, 0]][direction];
+                const [rightX, rightY] = [obj.x - dy, obj.y + dx];
+                const half = TILE_SIZE / 2;
+                for (const [tileX, tileY] of [[obj.x, obj.y], [rightX, rightY]]) {
+                    const px = tileX * TILE_SIZE;
+                    const py = tileY * TILE_SIZE;
+                    ctx.fillStyle = '#4b5563'; // Gray belt
+                    ctx.fillRect(px, py, TILE_SIZE, TILE_SIZE);
+                    
+                    // Arrow in the belt direction on each half
+                    ctx.fillStyle = '#9ca3af';
+                    ctx.beginPath();
+                    const tipX = px + half + dx * (half - 4);
+                    const tipY = py + half + dy * (half - 4);
+                    ctx.moveTo(tipX, tipY);
+                    ctx.lineTo(tipX - dx * 4 - dy * 3, tipY - dy * 4 - dx * 3);
+                    ctx.lineTo(tipX - dx * 4 + dy * 3, tipY - dy * 4 + dx * 3);
+                    ctx.closePath();
+                    ctx.fill();
+                }
+                
+                // Splitter body as a bar across both halves
+                const minX = Math.min(obj.x, rightX) * TILE_SIZE;
+                const minY = Math.min(obj.y, rightY) * TILE_SIZE;
+                const width = dx === 0 ? TILE_SIZE * 2 : TILE_SIZE;
+                const height = dy === 0 ? TILE_SIZE * 2 : TILE_SIZE;
+                ctx.fillStyle = '#7c3aed'; // Purple
+                if (dx === 0) {
+                    ctx.fillRect(minX + 2, minY + half - 3, width - 4, 6);
+                } else {
+                    ctx.fillRect(minX + half - 3, minY + 2, 6, height - 4);
+                }
+                
+                // Draw floating text box above splitter showing its settings (only when hovering either half)
+                const isHovering = (cursorTileX === obj.x && cursorTileY === obj.y) || (cursorTileX === rightX && cursorTileY === rightY);
+                const splitterData: SplitterData | undefined = gameState.get_splitter_data(obj.x, obj.y);
+                if (splitterData && isHovering) {
+                    const sideName = (side: SplitterSide | undefined) => side === undefined ? 'None' : (side === SplitterSide.Left ? 'Left' : 'Right');
+                    const filter = splitterData.filter;
+                    const lines = [
+                        `Input: ${sideName(splitterData.input_priority)}`,
+                        `Output: ${sideName(splitterData.output_priority)}`,
+                        `Filter: ${filter === undefined ? 'None' : getItemName(filter)}`,
+                    ];
+                    
+                    const textBoxWidth = 110;
+                    const textBoxHeight = lines.length * 12 + 6;
+                    const textBoxX = minX + (width - textBoxWidth) / 2;
+                    const textBoxY = minY - (lines.length * 12 + 8);
+                    
+                    ctx.fillStyle = 'rgba(0, 0, 0, 0.6)';
+                    ctx.fillRect(textBoxX, textBoxY, textBoxWidth, textBoxHeight);
+                    
+                    ctx.strokeStyle = 'rgba(255, 255, 255, 0.5)';
+                    ctx.lineWidth = 1;
+                    ctx.strokeRect(textBoxX, textBoxY, textBoxWidth, textBoxHeight);
+                    
+                    ctx.fillStyle = 'rgba(255, 255, 255, 0.8)';
+                    ctx.font = '10px "Fusion Pixel"';
+                    
+                    let textY = textBoxY + 12;
+                    for (const line of lines) {
+                        ctx.fillText(line, textBoxX + 5, textY);
+                        textY += 12;
+                    }
+                }
             }
         }
     }
@@ -604,29 +779,21 @@ function render(): void {
         if (item === Item.IronPlate) return '#9ca3af'; // Light gray
         if (item === Item.Belt) return '#4b5563'; // Gray belt
         if (item === Item.CopperPlate) return '#f59e0b'; // Amber/orange
+        if (item === Item.IronGear) return '#d1d5db'; // Pale gray
         if (item === Item.Arm) return '#3b82f6'; // Blue
         if (item === Item.Chest) return '#8b4513'; // Brown
         if (item === Item.Drill) return '#374151'; // Dark gray
+        if (item === Item.UndergroundBelt) return '#111827'; // Near black
+        if (item === Item.Splitter) return '#7c3aed'; // Purple
         return '#ffffff'; // Default white
     }
     
     // Helper function to get item name
     function getItemName(item: Item): string {
-        if (item === Item.IronOre) return 'Iron Ore';
-        if (item === Item.Copper) return 'Copper';
-        if (item === Item.Stone) return 'Stone';
-        if (item === Item.Coal) return 'Coal';
-        if (item === Item.Furnace) return 'Furnace';
-        if (item === Item.IronPlate) return 'Iron Plate';
-        if (item === Item.Belt) return 'Belt';
-        if (item === Item.CopperPlate) return 'Copper Plate';
-        if (item === Item.Arm) return 'Arm';
-        if (item === Item.Chest) return 'Chest';
-        if (item === Item.Drill) return 'Drill';
-        return 'Unknown';
+        return item_name(item);
     }
     
-    // Draw items on belts (smaller than 1 tile, with label)
+    // Draw items on belts at their position along the belt (smaller than 1 tile, labelled when hovered)
     const beltItems = gameState.belt_items();
     if (beltItems && beltItems.length > 0) {
         for (let i = 0; i < beltItems.length; i++) {
@@ -636,17 +803,23 @@ function render(): void {
             const item = beltItem.item;
             const quantity = beltItem.quantity;
             
-            // Draw item as a smaller square (12x12 pixels, centered in tile)
-            const itemSize = 12;
-            const offsetX = (TILE_SIZE - itemSize) / 2;
-            const offsetY = (TILE_SIZE - itemSize) / 2;
+            // Draw item as a smaller square (8x8 pixels) centred on its offset within the tile
+            const itemSize = 8;
+            const centerX = itemX + beltItem.offset_x * TILE_SIZE;
+            const centerY = itemY + beltItem.offset_y * TILE_SIZE;
             
             ctx.fillStyle = getItemColor(item);
-            ctx.fillRect(itemX + offsetX, itemY + offsetY, itemSize, itemSize);
+            ctx.fillRect(centerX - itemSize / 2, centerY - itemSize / 2, itemSize, itemSize);
             
             // Draw a small highlight
             ctx.fillStyle = 'rgba(255, 255, 255, 0.3)';
-            ctx.fillRect(itemX + offsetX + 1, itemY + offsetY + 1, itemSize - 2, itemSize - 2);
+            ctx.fillRect(centerX - itemSize / 2 + 1, centerY - itemSize / 2 + 1, itemSize - 2, itemSize - 2);
+            
+            // Only label items on the hovered tile, a busy belt would be unreadable otherwise
+            const isHovering = cursorTileX === beltItem.x && cursorTileY === beltItem.y;
+            if (!isHovering) {
+                continue;
+            }
             
             // Draw label above item with name and quantity
             const itemName = getItemName(item);
@@ -659,7 +832,7 @@ function render(): void {
             const textHeight = 12;
             const padding = 4;
             
-            const labelX = itemX + TILE_SIZE / 2 - textWidth / 2 - padding;
+            const labelX = centerX - textWidth / 2 - padding;
             const labelY = itemY - textHeight - padding - 2;
             
             // Draw semi-transparent background for label
@@ -711,6 +884,28 @@ function render(): void {
         ctx.lineWidth = 2;
         ctx.strokeRect(cursorWorldX, cursorWorldY, TILE_SIZE, TILE_SIZE);
     }
+
+    // Draw the placement ghost (green if the selected item can go there, red if not) with an arrow for its direction
+    const preview = gameState.placement_preview();
+    if (preview) {
+        const ghostX = preview.x * TILE_SIZE;
+        const ghostY = preview.y * TILE_SIZE;
+        ctx.fillStyle = preview.error === undefined ? 'rgba(34, 197, 94, 0.35)' : 'rgba(239, 68, 68, 0.35)';
+        ctx.fillRect(ghostX, ghostY, TILE_SIZE, TILE_SIZE);
+        const [dx, dy] = [[0, -1], [0, 1], [1, 0], [-1, 0]][preview.direction];
+        if (preview.placeable_type === PlaceableType.Splitter) { // Splitters cover the tile to the right as well
+            ctx.fillRect((preview.x - dy) * TILE_SIZE, (preview.y + dx) * TILE_SIZE, TILE_SIZE, TILE_SIZE);
+        }
+
+        const centerX = ghostX + TILE_SIZE / 2;
+        const centerY = ghostY + TILE_SIZE / 2;
+        ctx.strokeStyle = 'rgba(255, 255, 255, 0.8)';
+        ctx.lineWidth = 2;
+        ctx.beginPath();
+        ctx.moveTo(centerX - dx * TILE_SIZE / 4, centerY - dy * TILE_SIZE / 4);
+        ctx.lineTo(centerX + dx * TILE_SIZE / 4, centerY + dy * TILE_SIZE / 4);
+        ctx.stroke();
+    }
     
     // Draw player as 1x1 tile with direction indicator
     // Draw player body (1x1 red square)
@@ -769,39 +964,11 @@ function drawInventory(): void {
     // Helper to get item count
     const getItemCount = (item: Item): number => {
         if (!gameState) return 0;
-        switch (item) {
-            case Item.IronOre: return gameState.iron_ore_count;
-            case Item.Copper: return gameState.copper_count();
-            case Item.Stone: return gameState.stone_count();
-            case Item.Coal: return gameState.coal_count();
-            case Item.Furnace: return gameState.furnace_count();
-            case Item.IronPlate: return gameState.iron_plate_count();
-            case Item.Belt: return gameState.belt_count();
-            case Item.CopperPlate: return gameState.copper_plate_count();
-            case Item.Arm: return gameState.arm_count();
-            case Item.Chest: return gameState.chest_count();
-            case Item.Drill: return gameState.drill_count();
-            default: return 0;
-        }
+        return gameState.item_count(item);
     };
     
     // Helper to get item name
-    const getItemDisplayName = (item: Item): string => {
-        switch (item) {
-            case Item.IronOre: return 'Iron Ore';
-            case Item.Copper: return 'Copper';
-            case Item.Stone: return 'Stone';
-            case Item.Coal: return 'Coal';
-            case Item.Furnace: return 'Furnace';
-            case Item.IronPlate: return 'Iron Plate';
-            case Item.Belt: return 'Belt';
-            case Item.CopperPlate: return 'Copper Plate';
-            case Item.Arm: return 'Arm';
-            case Item.Chest: return 'Chest';
-            case Item.Drill: return 'Drill';
-            default: return 'Unknown';
-        }
-    };
+    const getItemDisplayName = (item: Item): string => item_name(item);
     
     // Count items with count > 0
     let itemCount = 0;
@@ -834,7 +1001,7 @@ function drawInventory(): void {
     // Draw inventory text
     ctx.fillStyle = '#ffffff';
     ctx.font = 'bold 16px "Fusion Pixel"';
-    ctx.fillText('Inventory', inventoryX + 10, inventoryY + 25);
+    ctx.fillText(`Inventory ${gameState.inventory_used_slots}/${gameState.inventory_capacity}`, inventoryX + 10, inventoryY + 25);
     
     // Draw items in the same order as availableItems (which is now sorted consistently)
     ctx.font = '14px "Fusion Pixel"';
@@ -855,6 +1022,30 @@ function drawInventory(): void {
             yOffset += 20;
         }
     }
+
+    // Draw the craft in progress under the inventory
+    const queue = gameState.crafting_queue();
+    if (queue.length > 0) {
+        const job = queue[0];
+        const queueY = inventoryY + inventoryHeight + 10;
+
+        ctx.fillStyle = 'rgba(0, 0, 0, 0.7)';
+        ctx.fillRect(inventoryX, queueY, inventoryWidth, 50);
+        ctx.strokeStyle = '#ffffff';
+        ctx.lineWidth = 2;
+        ctx.strokeRect(inventoryX, queueY, inventoryWidth, 50);
+
+        ctx.fillStyle = '#ffffff';
+        ctx.font = '14px "Fusion Pixel"';
+        const queued = queue.reduce((total, entry) => total + entry.count, 0);
+        ctx.fillText(`Crafting ${job.recipe_id} (${queued})`, inventoryX + 10, queueY + 20);
+
+        // Progress bar
+        ctx.fillStyle = '#444444';
+        ctx.fillRect(inventoryX + 10, queueY + 30, inventoryWidth - 20, 10);
+        ctx.fillStyle = '#00ff00';
+        ctx.fillRect(inventoryX + 10, queueY + 30, (inventoryWidth - 20) * gameState.crafting_progress, 10);
+    }
 }
 
 function drawConsole(): void {
@@ -942,6 +1133,7 @@ function drawHelpBox(): void {
         // Movement
         ['W/A/S/D', 'Move'],
         ['Space', 'Place item / Add to container'],
+        ['Drag', 'Place a line of the selected item'],
         ['Delete', 'Pick up placeable'],
         // Mining & Resources
         ['M', 'Mine resources'],
@@ -953,10 +1145,15 @@ function drawHelpBox(): void {
         ['P', 'Craft Arm'],
         ['C', 'Craft Chest'],
         ['T', 'Craft Drill'],
+        ['U', 'Craft Underground Belt'],
+        ['L', 'Craft Splitter'],
+        ['X', 'Cancel Craft'],
         // Inventory
         ['[ / ]', 'Cycle inventory selection'],
         // Interactions
-        ['R', 'Rotate belt/arm'],
+        ['R', 'Rotate belt/arm (or next placement)'],
+        ['I / O', 'Cycle splitter input/output priority'],
+        ['G', 'Set splitter filter to selected item'],
     ];
     
     const columnWidth = helpBoxWidth / 3;
@@ -0,0 +1,51 @@
+use serde::{Serialize, Deserialize};
+use crate::types::{Item, Direction};
+use crate::splitter::SplitterSide;
+
+// A single player command, applied by GameState::step
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Action {
+    // Walk one tile (several Move actions in one tick combine into a diagonal step)
+    Move(Direction),
+    // Mine the first resource next to the player
+    Mine,
+    // Queue a recipe (by id) up to count times
+    Craft { recipe: String, count: u32 },
+    // Cancel the crafting queue entry at index and refund its ingredients
+    CancelCraft { index: usize },
+    // Place a placeable item from the inventory at (x, y)
+    Place { item: Item, x: u32, y: u32, direction: Direction },
+    // Place a line of placeables from (from_x, from_y) to (to_x, to_y), along x first then y
+    PlaceLine { item: Item, from_x: u32, from_y: u32, to_x: u32, to_y: u32 },
+    // Put one item from the inventory into the furnace, chest, or drill at (x, y)
+    Insert { item: Item, x: u32, y: u32 },
+    // Rotate the belt, arm, or drill at (x, y)
+    Rotate { x: u32, y: u32 },
+    // Rotate the direction the next placed object will face
+    RotatePlacement,
+    // Pick up the belt item or dropped item at (x, y), or harvest a furnace there
+    Pickup { x: u32, y: u32 },
+    // Drop one item from the inventory at (x, y)
+    Drop { item: Item, x: u32, y: u32 },
+    // Insert the selected item into the container at (x, y), or place it there if there is none
+    UseSelected { x: u32, y: u32 },
+    // Drop one of the selected item at (x, y)
+    DropSelected { x: u32, y: u32 },
+    // Pick up the placeable at (x, y) together with its contents
+    Deconstruct { x: u32, y: u32 },
+    // Change the priorities and filter of the splitter covering (x, y)
+    ConfigureSplitter { x: u32, y: u32, input_priority: Option<SplitterSide>, output_priority: Option<SplitterSide>, filter: Option<Item> },
+    // Move the inventory selection forward or backward
+    SelectNext,
+    SelectPrevious,
+    // Change how far belt items move each tick (capped at one tile per tick)
+    SetBeltSpeed(u32),
+}
+
+// Everything the player did during one tick
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct InputFrame {
+    pub cursor_x: Option<u32>,
+    pub cursor_y: Option<u32>,
+    pub actions: Vec<Action>,
+}
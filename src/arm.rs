@@ -1,26 +1,55 @@
+#[cfg(feature = "wasm")]
 use wasm_bindgen::prelude::*;
+use serde::{Serialize, Deserialize};
+use crate::types::Item;
+use crate::entity::{Entity, MachineStatus, TickContext};
+use crate::map::DroppedItem;
+use crate::belt::{BeltLane, BELT_INSERT_PROGRESS};
 
-// Arm data - tracks tick counter for duty cycle
-#[wasm_bindgen]
-#[derive(Clone)]
+// Arm data - tracks tick counter for duty cycle and what the arm is holding
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct ArmData {
     tick_counter: u32, // Current tick in the duty cycle (0-119)
+    #[serde(default)]
+    status: MachineStatus, // Outcome of the last move attempt
+    #[serde(default)]
+    held: Option<(Item, u32)>, // Picked up but not delivered yet (the target refused it)
 }
 
-#[wasm_bindgen]
+impl Default for ArmData {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
 impl ArmData {
-    #[wasm_bindgen(constructor)]
+    #[cfg_attr(feature = "wasm", wasm_bindgen(constructor))]
     pub fn new() -> ArmData {
         ArmData {
             tick_counter: 0,
+            status: MachineStatus::default(),
+            held: None,
         }
     }
 
-    #[wasm_bindgen(getter)]
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
     pub fn tick_counter(&self) -> u32 {
         self.tick_counter
     }
 
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn status(&self) -> MachineStatus {
+        self.status
+    }
+
+    // Item the arm is holding until the target takes it
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn held_item(&self) -> Option<Item> {
+        self.held.map(|(item, _)| item)
+    }
+
     // Increment tick counter and return true if it's time to act (every 120 ticks)
     pub fn process_tick(&mut self) -> bool {
         self.tick_counter += 1;
@@ -33,3 +62,141 @@ impl ArmData {
     }
 }
 
+
+impl ArmData {
+    // Pick up one item (or a whole stack off a belt or the ground) from the source tile
+    fn pick_up(ctx: &mut TickContext, source_x: u32, source_y: u32) -> Option<(Item, u32)> {
+        if let Some(item) = ctx.take_item_at(source_x, source_y) {
+            return Some((item, 1));
+        }
+        if let Some(belt_item) = ctx.map.take_belt_item(source_x, source_y) {
+            return Some((belt_item.item(), belt_item.quantity()));
+        }
+        let index = ctx.map.get_dropped_item_index_at(source_x, source_y)?;
+        let dropped_item = ctx.map.dropped_items_ref()[index].clone();
+        ctx.map.remove_dropped_item(index);
+        Some((dropped_item.item(), dropped_item.quantity()))
+    }
+
+    // Hand what the arm holds to the target tile. Returns what's still held:
+    // machines and chests take one item per move, belts and empty ground the whole stack,
+    // anything else (water, a full lane, a placeable refusing the item) takes nothing.
+    fn deliver(ctx: &mut TickContext, target_x: u32, target_y: u32, belt_lane: Option<BeltLane>, (item, quantity): (Item, u32)) -> Option<(Item, u32)> {
+        if let Some(lane) = belt_lane {
+            let belt_item = DroppedItem::new(target_x, target_y, item, quantity);
+            if ctx.map.insert_onto_belt(target_x, target_y, belt_item, lane, BELT_INSERT_PROGRESS) {
+                return None;
+            }
+        } else if ctx.map.get_placeable_at(target_x, target_y).is_some() {
+            if ctx.insert_item_at(target_x, target_y, item) {
+                return (quantity > 1).then_some((item, quantity - 1));
+            }
+        } else if !ctx.map.would_collide_with_water(target_x, target_y)
+            && ctx.map.get_dropped_item_index_at(target_x, target_y).is_none()
+        {
+            ctx.map.add_dropped_item(DroppedItem::new(target_x, target_y, item, quantity));
+            return None;
+        }
+        Some((item, quantity))
+    }
+}
+
+// Arms move items from the tile behind them ("below") to the tile in front ("above") every 120 ticks.
+// Whatever the target won't take stays in the arm until it does.
+impl Entity for ArmData {
+    fn tick(&mut self, ctx: &mut TickContext) {
+        if !self.process_tick() {
+            return;
+        }
+        let Some((target_x, target_y)) = ctx.neighbour(ctx.direction) else {
+            self.status = MachineStatus::TargetFull;
+            return;
+        };
+
+        // Arms reach across a belt and put items on its far lane
+        let belt_lane = ctx.map.belt_direction_at(target_x, target_y)
+            .map(|belt_direction| BeltLane::far_side(belt_direction, ctx.direction));
+
+        let held = match self.held {
+            Some(held) => held,
+            None => {
+                // Check the target before taking anything from the source.
+                // Belts are occupied while there's no room in the middle of that lane, empty ground while something lies there;
+                // other placeables are checked once the item is in hand.
+                let target_occupied = match belt_lane {
+                    Some(lane) => !ctx.map.belt_has_room(target_x, target_y, lane, BELT_INSERT_PROGRESS),
+                    None if ctx.map.get_placeable_at(target_x, target_y).is_some() => false,
+                    None => ctx.map.get_dropped_item_index_at(target_x, target_y).is_some(),
+                };
+                if target_occupied {
+                    self.status = MachineStatus::TargetFull;
+                    return;
+                }
+                let picked = ctx.neighbour(ctx.direction.opposite())
+                    .and_then(|(source_x, source_y)| Self::pick_up(ctx, source_x, source_y));
+                let Some(picked) = picked else {
+                    self.status = MachineStatus::SourceEmpty;
+                    return;
+                };
+                picked
+            }
+        };
+
+        self.held = Self::deliver(ctx, target_x, target_y, belt_lane, held);
+        self.status = if self.held == Some(held) { MachineStatus::TargetFull } else { MachineStatus::Working };
+    }
+
+    // Arms only get items by picking them up
+    fn accepts_item(&self, _item: Item) -> bool {
+        false
+    }
+
+    fn insert_item(&mut self, _item: Item) {}
+
+    fn take_item(&mut self) -> Option<Item> {
+        None
+    }
+
+    // What the arm is holding (given back when it's picked up)
+    fn contents(&self) -> Vec<(Item, u32)> {
+        self.held.into_iter().collect()
+    }
+
+    fn status(&self) -> Option<MachineStatus> {
+        Some(self.status)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::action::Action;
+    use crate::game_state::GameState;
+    use crate::types::{Direction, Item};
+    use crate::entity::MachineStatus;
+
+    #[test]
+    fn items_the_target_refuses_stay_in_the_arm() {
+        let mut game_state = GameState::new();
+        let (x, y) = (game_state.player_x() + 2, game_state.player_y() + 2);
+        let stone = game_state.item_count(Item::Stone);
+        game_state.step(&[
+            Action::Place { item: Item::Furnace, x, y, direction: Direction::North },
+            Action::Place { item: Item::Arm, x, y: y + 1, direction: Direction::North },
+            Action::Place { item: Item::Chest, x, y: y + 2, direction: Direction::North },
+            Action::Insert { item: Item::Stone, x, y: y + 2 },
+        ]);
+        for _ in 0..130 {
+            game_state.step(&[]);
+        }
+
+        let arm = game_state.entity_at(x, y + 1).unwrap();
+        assert_eq!(arm.status(), Some(MachineStatus::TargetFull));
+        assert_eq!(arm.content_items(), vec![Item::Stone]);
+        assert!(game_state.entity_at(x, y).unwrap().content_items().is_empty());
+        assert!(game_state.dropped_items().is_empty());
+
+        // Picking the arm up gives back what it held
+        game_state.step(&[Action::Deconstruct { x, y: y + 1 }]);
+        assert_eq!(game_state.item_count(Item::Stone), stone);
+    }
+}
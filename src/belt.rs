@@ -0,0 +1,163 @@
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+use serde::{Serialize, Deserialize};
+use crate::types::{Item, Direction};
+
+// Belt positions are fixed-point so movement stays deterministic:
+// an item's progress runs from 0 (entry edge) to BELT_LENGTH (exit edge) of its tile
+pub const BELT_LENGTH: u32 = 120;
+// Closest two items on a belt can be, so a tile holds up to BELT_LENGTH / BELT_ITEM_SPACING items
+pub const BELT_ITEM_SPACING: u32 = 30;
+// Progress per tick - one tile every 30 ticks
+pub const DEFAULT_BELT_SPEED: u32 = 4;
+// Where items put onto a belt from the side (drops, arms, drills) land
+pub const BELT_INSERT_PROGRESS: u32 = BELT_LENGTH / 2;
+
+// Which side of a belt an item rides on, looking along the belt
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BeltLane {
+    #[default]
+    Left,
+    Right,
+}
+
+impl BeltLane {
+    pub const ALL: [BeltLane; 2] = [BeltLane::Left, BeltLane::Right];
+
+    pub fn other(&self) -> BeltLane {
+        match self {
+            BeltLane::Left => BeltLane::Right,
+            BeltLane::Right => BeltLane::Left,
+        }
+    }
+
+    // The lane nearest to something moving in `incoming` onto the side of a belt
+    // (None if it comes from behind or ahead instead)
+    pub fn near_side(belt_direction: Direction, incoming: Direction) -> Option<BeltLane> {
+        if incoming == belt_direction.rotated_clockwise() {
+            Some(BeltLane::Left)
+        } else if incoming == belt_direction.rotated_clockwise().opposite() {
+            Some(BeltLane::Right)
+        } else {
+            None
+        }
+    }
+
+    // The lane furthest from something reaching in moving `incoming` - where arms put items.
+    // From behind or ahead both lanes are equally far, the right one is used.
+    pub fn far_side(belt_direction: Direction, incoming: Direction) -> BeltLane {
+        BeltLane::near_side(belt_direction, incoming).map_or(BeltLane::Right, |near| near.other())
+    }
+}
+
+// How a belt runs through its tile. Belts fed only from one side turn the corner
+// instead of being side-loaded; anything fed from behind (or from both sides) stays straight.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum BeltShape {
+    #[default]
+    Straight,
+    TurnLeft,  // Fed from its left-hand side
+    TurnRight, // Fed from its right-hand side
+}
+
+impl BeltShape {
+    // Which way items are moving when they come in
+    pub fn input_direction(&self, belt_direction: Direction) -> Direction {
+        match self {
+            BeltShape::Straight => belt_direction,
+            BeltShape::TurnLeft => belt_direction.rotated_clockwise(),
+            BeltShape::TurnRight => belt_direction.rotated_clockwise().opposite(),
+        }
+    }
+}
+
+// Unit vectors along a direction and to its right-hand side
+fn axes(direction: Direction) -> ((f64, f64), (f64, f64)) {
+    match direction {
+        Direction::North => ((0.0, -1.0), (1.0, 0.0)),
+        Direction::South => ((0.0, 1.0), (-1.0, 0.0)),
+        Direction::East => ((1.0, 0.0), (0.0, 1.0)),
+        Direction::West => ((-1.0, 0.0), (0.0, -1.0)),
+    }
+}
+
+// Offset of an item's centre from the tile's top-left corner, in tiles.
+// Lanes run a quarter tile either side of the belt's centre line; on a curve
+// each lane runs in from the input edge to its corner, then out to the exit edge.
+pub fn progress_offset(direction: Direction, shape: BeltShape, lane: BeltLane, progress: u32) -> (f64, f64) {
+    let along = progress.min(BELT_LENGTH) as f64 / BELT_LENGTH as f64;
+    let side = match lane {
+        BeltLane::Left => -0.25,
+        BeltLane::Right => 0.25,
+    };
+    let ((out_x, out_y), (right_x, right_y)) = axes(direction);
+    if shape == BeltShape::Straight {
+        return (0.5 + out_x * (along - 0.5) + right_x * side, 0.5 + out_y * (along - 0.5) + right_y * side);
+    }
+    let ((in_x, in_y), (in_right_x, in_right_y)) = axes(shape.input_direction(direction));
+    let entry = (0.5 - in_x * 0.5 + in_right_x * side, 0.5 - in_y * 0.5 + in_right_y * side);
+    let corner = (0.5 + (in_right_x + right_x) * side, 0.5 + (in_right_y + right_y) * side);
+    let exit = (0.5 + out_x * 0.5 + right_x * side, 0.5 + out_y * 0.5 + right_y * side);
+    let (from, to, t) = if along < 0.5 { (entry, corner, along * 2.0) } else { (corner, exit, along * 2.0 - 1.0) };
+    (from.0 + (to.0 - from.0) * t, from.1 + (to.1 - from.1) * t)
+}
+
+// An item on a belt as the renderer sees it
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy, Debug)]
+pub struct BeltItem {
+    x: u32,
+    y: u32,
+    item: Item,
+    quantity: u32,
+    lane: BeltLane,
+    offset_x: f64,
+    offset_y: f64,
+}
+
+impl BeltItem {
+    pub(crate) fn new(x: u32, y: u32, item: Item, quantity: u32, lane: BeltLane, (offset_x, offset_y): (f64, f64)) -> BeltItem {
+        BeltItem { x, y, item, quantity, lane, offset_x, offset_y }
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl BeltItem {
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn x(&self) -> u32 {
+        self.x
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn y(&self) -> u32 {
+        self.y
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn item(&self) -> Item {
+        self.item
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn quantity(&self) -> u32 {
+        self.quantity
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn lane(&self) -> BeltLane {
+        self.lane
+    }
+
+    // Centre of the item within its tile (0.0 - 1.0 on each axis)
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn offset_x(&self) -> f64 {
+        self.offset_x
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn offset_y(&self) -> f64 {
+        self.offset_y
+    }
+}
@@ -1,17 +1,26 @@
+#[cfg(feature = "wasm")]
 use wasm_bindgen::prelude::*;
+use serde::{Serialize, Deserialize};
 use crate::types::Item;
+use crate::entity::{Entity, TickContext};
 
 // Chest inventory data - can hold multiple items and stacks
-#[wasm_bindgen]
-#[derive(Clone)]
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct ChestData {
     item_types: Vec<Item>, // Vector of item types
     quantities: Vec<u32>, // Vector of quantities (parallel to item_types)
 }
 
-#[wasm_bindgen]
+impl Default for ChestData {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
 impl ChestData {
-    #[wasm_bindgen(constructor)]
+    #[cfg_attr(feature = "wasm", wasm_bindgen(constructor))]
     pub fn new() -> ChestData {
         ChestData {
             item_types: Vec::new(),
@@ -23,7 +32,7 @@ impl ChestData {
     pub fn add_item(&mut self, item: Item, quantity: u32) {
         // Try to find existing stack of same item
         for i in 0..self.item_types.len() {
-            if std::mem::discriminant(&self.item_types[i]) == std::mem::discriminant(&item) {
+            if self.item_types[i] == item {
                 self.quantities[i] += quantity;
                 return;
             }
@@ -36,7 +45,7 @@ impl ChestData {
     // Remove items from chest
     pub fn remove_item(&mut self, item: Item, quantity: u32) -> bool {
         for i in 0..self.item_types.len() {
-            if std::mem::discriminant(&self.item_types[i]) == std::mem::discriminant(&item) {
+            if self.item_types[i] == item {
                 if self.quantities[i] >= quantity {
                     self.quantities[i] -= quantity;
                     // Remove entry if count is now 0
@@ -73,3 +82,27 @@ impl ChestData {
     }
 }
 
+
+impl Entity for ChestData {
+    // Chests just store things
+    fn tick(&mut self, _ctx: &mut TickContext) {}
+
+    fn accepts_item(&self, _item: Item) -> bool {
+        self.has_space()
+    }
+
+    fn insert_item(&mut self, item: Item) {
+        self.add_item(item, 1);
+    }
+
+    // Items come out oldest stack first
+    fn take_item(&mut self) -> Option<Item> {
+        let item = *self.item_types.first()?;
+        self.remove_item(item, 1);
+        Some(item)
+    }
+
+    fn contents(&self) -> Vec<(Item, u32)> {
+        self.item_types.iter().copied().zip(self.quantities.iter().copied()).collect()
+    }
+}
@@ -1,245 +1,837 @@
-use crate::types::Item;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+use std::collections::{HashMap, VecDeque};
+use std::ops::Range;
+use std::fmt;
+use serde::{Serialize, Deserialize};
+use crate::types::{Item, ITEM_DEFS};
+use crate::inventory::Inventory;
 use crate::game_state::GameState;
+use crate::events::{GameEvent, ActionError};
 
-// Crafting recipe struct
+// Crafting recipe - consumes every ingredient and yields every result, each with a count
+#[derive(Debug)]
 pub struct CraftingRecipe {
-    pub result: Item,
-    pub ingredients: Vec<(Item, u32)>,
-}
-
-// Get crafting recipes - scalable system for future recipes
-pub fn get_crafting_recipes() -> Vec<CraftingRecipe> {
-    vec![
-        CraftingRecipe {
-            result: Item::Furnace,
-            ingredients: vec![(Item::Stone, 5)],
-        },
-        CraftingRecipe {
-            result: Item::Belt,
-            ingredients: vec![(Item::IronPlate, 1)],
-        },
-        CraftingRecipe {
-            result: Item::Arm,
-            ingredients: vec![(Item::CopperPlate, 1)],
-        },
-        CraftingRecipe {
-            result: Item::Chest,
-            ingredients: vec![(Item::IronPlate, 1)],
-        },
-        CraftingRecipe {
-            result: Item::Drill,
-            ingredients: vec![(Item::IronOre, 5)],
-        },
-    ]
-}
-
-// Helper function to get item count from inventory
-fn get_item_count(game_state: &GameState, item_type: &Item) -> u32 {
-    match item_type {
-        Item::IronOre => game_state.player().inventory().count_iron_ore(),
-        Item::Copper => game_state.player().inventory().count_copper(),
-        Item::Stone => game_state.player().inventory().count_stone(),
-        Item::Coal => game_state.player().inventory().count_coal(),
-        Item::Furnace => game_state.player().inventory().count_furnace(),
-        Item::IronPlate => game_state.player().inventory().count_iron_plate(),
-        Item::Belt => game_state.player().inventory().count_belt(),
-        Item::CopperPlate => game_state.player().inventory().count_copper_plate(),
-        Item::Arm => game_state.player().inventory().count_arm(),
-        Item::Chest => game_state.player().inventory().count_chest(),
-        Item::Drill => game_state.player().inventory().count_drill(),
-    }
-}
-
-// Helper function to get item name
-fn get_item_name(item_type: &Item) -> &'static str {
-    match item_type {
-        Item::IronOre => "Iron Ore",
-        Item::Copper => "Copper",
-        Item::Stone => "Stone",
-        Item::Coal => "Coal",
-        Item::Furnace => "Furnace",
-        Item::IronPlate => "Iron Plate",
-        Item::Belt => "Belt",
-        Item::CopperPlate => "Copper Plate",
-        Item::Arm => "Arm",
-        Item::Chest => "Chest",
-        Item::Drill => "Drill",
-    }
-}
-
-// Handle belt crafting
-pub fn handle_belt_crafting(game_state: &mut GameState) {
-    let recipes = get_crafting_recipes();
-    
-    // Find belt recipe
-    if let Some(recipe) = recipes.iter().find(|r| matches!(r.result, Item::Belt)) {
-        // Check if player has all required ingredients
-        let mut has_all = true;
-        for (item_type, required_count) in &recipe.ingredients {
-            let count = get_item_count(game_state, item_type);
-            if count < *required_count {
-                has_all = false;
-                let message = format!("Not enough resources! Need {} {} (have {})", required_count, get_item_name(item_type), count);
-                game_state.add_console_message(message);
-                break;
+    pub id: &'static str, // Stable identifier used by Action::Craft, saves and the UI
+    pub name: &'static str,
+    pub craft_ticks: u32, // Crafting time for one craft (60 ticks = 1 second)
+    pub hand_craftable: bool, // False for recipes only machines can run (smelting)
+    pub ingredients: &'static [(Item, u32)],
+    pub results: &'static [(Item, u32)],
+}
+
+// Every recipe in the game, in UI display order - add new recipes here
+pub const RECIPES: &[CraftingRecipe] = &[
+    CraftingRecipe {
+        id: "furnace",
+        name: "Furnace",
+        hand_craftable: true,
+        craft_ticks: 60,
+        ingredients: &[(Item::Stone, 5)],
+        results: &[(Item::Furnace, 1)],
+    },
+    CraftingRecipe {
+        id: "belt",
+        name: "Belt",
+        hand_craftable: true,
+        craft_ticks: 30,
+        ingredients: &[(Item::IronPlate, 1)],
+        results: &[(Item::Belt, 1)],
+    },
+    CraftingRecipe {
+        id: "arm",
+        name: "Arm",
+        hand_craftable: true,
+        craft_ticks: 30,
+        ingredients: &[(Item::CopperPlate, 1)],
+        results: &[(Item::Arm, 1)],
+    },
+    CraftingRecipe {
+        id: "chest",
+        name: "Chest",
+        hand_craftable: true,
+        craft_ticks: 30,
+        ingredients: &[(Item::IronPlate, 1)],
+        results: &[(Item::Chest, 1)],
+    },
+    CraftingRecipe {
+        id: "drill",
+        name: "Drill",
+        hand_craftable: true,
+        craft_ticks: 90,
+        ingredients: &[(Item::IronOre, 5)],
+        results: &[(Item::Drill, 1)],
+    },
+    CraftingRecipe {
+        id: "underground_belt",
+        name: "Underground Belt",
+        hand_craftable: true,
+        craft_ticks: 60,
+        ingredients: &[(Item::IronPlate, 5)],
+        results: &[(Item::UndergroundBelt, 2)],
+    },
+    CraftingRecipe {
+        id: "splitter",
+        name: "Splitter",
+        hand_craftable: true,
+        craft_ticks: 60,
+        ingredients: &[(Item::IronGear, 2), (Item::IronPlate, 1), (Item::CopperPlate, 5)],
+        results: &[(Item::Splitter, 1)],
+    },
+    CraftingRecipe {
+        id: "iron_gear",
+        name: "Iron Gear",
+        hand_craftable: true,
+        craft_ticks: 30,
+        ingredients: &[(Item::IronPlate, 2)],
+        results: &[(Item::IronGear, 1)],
+    },
+    CraftingRecipe {
+        id: "iron_plate",
+        name: "Iron Plate",
+        hand_craftable: false,
+        craft_ticks: 60,
+        ingredients: &[(Item::IronOre, 1)],
+        results: &[(Item::IronPlate, 1)],
+    },
+    CraftingRecipe {
+        id: "copper_plate",
+        name: "Copper Plate",
+        hand_craftable: false,
+        craft_ticks: 60,
+        ingredients: &[(Item::Copper, 1)],
+        results: &[(Item::CopperPlate, 1)],
+    },
+];
+
+impl CraftingRecipe {
+    pub fn find(recipe_id: &str) -> Option<&'static CraftingRecipe> {
+        RECIPES.iter().find(|recipe| recipe.id == recipe_id)
+    }
+
+    // First hand-craftable recipe that produces this item
+    pub fn producing(item: Item) -> Option<&'static CraftingRecipe> {
+        RECIPES
+            .iter()
+            .filter(|recipe| recipe.hand_craftable)
+            .find(|recipe| recipe.results.iter().any(|(result, _)| *result == item))
+    }
+
+    // Machine-only recipe a furnace runs on this input (one ingredient in)
+    pub fn smelting(input: Item) -> Option<&'static CraftingRecipe> {
+        RECIPES
+            .iter()
+            .filter(|recipe| !recipe.hand_craftable)
+            .find(|recipe| matches!(recipe.ingredients, [(ingredient, _)] if *ingredient == input))
+    }
+
+    // How many of an item one craft yields
+    fn yield_of(&self, item: Item) -> u32 {
+        self.results
+            .iter()
+            .filter(|(result, _)| *result == item)
+            .map(|(_, count)| *count)
+            .sum()
+    }
+}
+
+// Recipe summary for the UI (ingredient and result lists are parallel item/count arrays)
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Debug)]
+pub struct RecipeInfo {
+    id: String,
+    name: String,
+    ingredient_items: Vec<Item>,
+    ingredient_counts: Vec<u32>,
+    result_items: Vec<Item>,
+    result_counts: Vec<u32>,
+    hand_craftable: bool,
+    max_crafts: u32,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl RecipeInfo {
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn id(&self) -> String {
+        self.id.clone()
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn ingredient_items(&self) -> Vec<Item> {
+        self.ingredient_items.clone()
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn ingredient_counts(&self) -> Vec<u32> {
+        self.ingredient_counts.clone()
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn result_items(&self) -> Vec<Item> {
+        self.result_items.clone()
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn result_counts(&self) -> Vec<u32> {
+        self.result_counts.clone()
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn hand_craftable(&self) -> bool {
+        self.hand_craftable
+    }
+
+    // How many times the player can currently hand-craft this recipe, intermediate crafts included
+    // (what craft() would accept)
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn max_crafts(&self) -> u32 {
+        self.max_crafts
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn craftable(&self) -> bool {
+        self.max_crafts > 0
+    }
+}
+
+// Every recipe with the player's current affordability
+pub fn list_recipes(game_state: &GameState) -> Vec<RecipeInfo> {
+    let inventory = game_state.player().inventory();
+    RECIPES
+        .iter()
+        .map(|recipe| RecipeInfo {
+            id: recipe.id.to_string(),
+            name: recipe.name.to_string(),
+            ingredient_items: recipe.ingredients.iter().map(|(item, _)| *item).collect(),
+            ingredient_counts: recipe.ingredients.iter().map(|(_, count)| *count).collect(),
+            result_items: recipe.results.iter().map(|(item, _)| *item).collect(),
+            result_counts: recipe.results.iter().map(|(_, count)| *count).collect(),
+            hand_craftable: recipe.hand_craftable,
+            max_crafts: max_crafts(inventory, recipe.id),
+        })
+        .collect()
+}
+
+// Why a craft could not be planned
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub enum CraftError {
+    UnknownRecipe(String),
+    // The recipe needs a machine (e.g. smelting happens in furnaces)
+    NotHandCraftable(&'static str),
+    // Raw materials (items with no hand-craftable recipe) and how many more are needed
+    MissingMaterials(Vec<(Item, u32)>),
+    // The plan needs more of an item than can be counted
+    TooMany(Item),
+}
+
+impl fmt::Display for CraftError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CraftError::UnknownRecipe(recipe_id) => write!(f, "Unknown recipe: {}", recipe_id),
+            CraftError::NotHandCraftable(name) => write!(f, "{} can't be crafted by hand!", name),
+            CraftError::MissingMaterials(missing) => {
+                let missing: Vec<String> = missing
+                    .iter()
+                    .map(|(item, count)| format!("{} {}", count, item.name()))
+                    .collect();
+                write!(f, "Not enough resources! Need {} more", missing.join(", "))
             }
+            CraftError::TooMany(item) => write!(f, "Can't craft that many! Too much {} needed", item.name()),
         }
-        
-        if has_all {
-            // Remove ingredients
-            for (item_type, count) in &recipe.ingredients {
-                game_state.player_mut().inventory_mut().remove_items(item_type.clone(), *count);
-            }
-            
-            // Add result to inventory
-            game_state.player_mut().add_to_inventory(recipe.result.clone());
-            
-            // Validate selection after inventory change
-            game_state.validate_selection();
-            
-            game_state.add_console_message("Crafted Belt!".to_string());
-        }
-    }
-}
-
-// Handle arm crafting
-pub fn handle_arm_crafting(game_state: &mut GameState) {
-    let recipes = get_crafting_recipes();
-    
-    // Find arm recipe
-    if let Some(recipe) = recipes.iter().find(|r| matches!(r.result, Item::Arm)) {
-        // Check if player has all required ingredients
-        let mut has_all = true;
-        for (item_type, required_count) in &recipe.ingredients {
-            let count = get_item_count(game_state, item_type);
-            if count < *required_count {
-                has_all = false;
-                let message = format!("Not enough resources! Need {} {} (have {})", required_count, get_item_name(item_type), count);
-                game_state.add_console_message(message);
-                break;
-            }
+    }
+}
+
+// Hand-crafting steps (recipe, crafts) that make `count` crafts of a recipe from the inventory,
+// intermediate recipes first. The recipe itself is the last step.
+pub fn plan_craft(inventory: &Inventory, recipe_id: &str, count: u32) -> Result<Vec<(&'static CraftingRecipe, u32)>, CraftError> {
+    let recipe = CraftingRecipe::find(recipe_id).ok_or_else(|| CraftError::UnknownRecipe(recipe_id.to_string()))?;
+    if !recipe.hand_craftable {
+        return Err(CraftError::NotHandCraftable(recipe.name));
+    }
+
+    let mut planner = CraftPlanner {
+        inventory,
+        stock: HashMap::new(),
+        steps: Vec::new(),
+        missing: Vec::new(),
+        in_progress: vec![recipe.id],
+    };
+    for (item, required) in recipe.ingredients {
+        let amount = required.checked_mul(count).ok_or(CraftError::TooMany(*item))?;
+        planner.require(*item, amount)?;
+    }
+    planner.steps.push((recipe, count));
+
+    if planner.missing.is_empty() {
+        Ok(planner.steps)
+    } else {
+        planner.missing.sort_by_key(|(item, _)| *item as usize);
+        Err(CraftError::MissingMaterials(planner.missing))
+    }
+}
+
+// Dependency resolver state for plan_craft
+struct CraftPlanner<'a> {
+    inventory: &'a Inventory,
+    stock: HashMap<Item, u32>, // What the plan still has of each item it has touched
+    steps: Vec<(&'static CraftingRecipe, u32)>,
+    missing: Vec<(Item, u32)>,
+    in_progress: Vec<&'static str>, // Recipes being expanded, so recipe cycles count as missing material
+}
+
+impl CraftPlanner<'_> {
+    // Take amount of an item from stock, planning crafts (or noting missing material) for the rest
+    fn require(&mut self, item: Item, amount: u32) -> Result<(), CraftError> {
+        let inventory = self.inventory;
+        let stock = self.stock.entry(item).or_insert_with(|| inventory.count(item));
+        let taken = (*stock).min(amount);
+        *stock -= taken;
+        let short = amount - taken;
+        if short == 0 {
+            return Ok(());
         }
-        
-        if has_all {
-            // Remove ingredients
-            for (item_type, count) in &recipe.ingredients {
-                game_state.player_mut().inventory_mut().remove_items(item_type.clone(), *count);
-            }
-            
-            // Add result to inventory
-            game_state.player_mut().add_to_inventory(recipe.result.clone());
-            
-            // Validate selection after inventory change
-            game_state.validate_selection();
-            
-            game_state.add_console_message("Crafted Arm!".to_string());
-        }
-    }
-}
-
-// Handle drill crafting
-pub fn handle_drill_crafting(game_state: &mut GameState) {
-    let recipes = get_crafting_recipes();
-    
-    // Find drill recipe
-    if let Some(recipe) = recipes.iter().find(|r| matches!(r.result, Item::Drill)) {
-        // Check if player has all required ingredients
-        let mut has_all = true;
-        for (item_type, required_count) in &recipe.ingredients {
-            let count = get_item_count(game_state, item_type);
-            if count < *required_count {
-                has_all = false;
-                let message = format!("Not enough resources! Need {} {} (have {})", required_count, get_item_name(item_type), count);
-                game_state.add_console_message(message);
-                break;
+
+        let recipe = CraftingRecipe::producing(item).filter(|recipe| !self.in_progress.contains(&recipe.id));
+        let recipe = match recipe {
+            Some(recipe) => recipe,
+            None => {
+                // Raw material
+                match self.missing.iter_mut().find(|(missing, _)| *missing == item) {
+                    Some((_, count)) => *count = count.checked_add(short).ok_or(CraftError::TooMany(item))?,
+                    None => self.missing.push((item, short)),
+                }
+                return Ok(());
             }
+        };
+
+        let produced = recipe.yield_of(item);
+        let crafts = short.div_ceil(produced);
+
+        self.in_progress.push(recipe.id);
+        for (ingredient, required) in recipe.ingredients {
+            let amount = required.checked_mul(crafts).ok_or(CraftError::TooMany(*ingredient))?;
+            self.require(*ingredient, amount)?;
         }
-        
-        if has_all {
-            // Remove ingredients
-            for (item_type, count) in &recipe.ingredients {
-                game_state.player_mut().inventory_mut().remove_items(item_type.clone(), *count);
+        self.in_progress.pop();
+        self.steps.push((recipe, crafts));
+
+        // Leftovers (extra output and by-products) are available to later steps
+        for (result, count) in recipe.results {
+            let made = count.checked_mul(crafts).ok_or(CraftError::TooMany(*result))?;
+            let extra = if *result == item { made - short } else { made };
+            let inventory = self.inventory;
+            let stock = self.stock.entry(*result).or_insert_with(|| inventory.count(*result));
+            *stock = stock.checked_add(extra).ok_or(CraftError::TooMany(*result))?;
+        }
+        Ok(())
+    }
+}
+
+// What a plan takes from the inventory: everything its steps use beyond what earlier steps make
+fn plan_inputs(steps: &[(&'static CraftingRecipe, u32)]) -> Vec<(Item, u32)> {
+    let mut balance = vec![0i64; ITEM_DEFS.len()];
+    for (recipe, crafts) in steps {
+        for (item, required) in recipe.ingredients {
+            balance[*item as usize] -= *required as i64 * *crafts as i64;
+        }
+        for (item, made) in recipe.results {
+            balance[*item as usize] += *made as i64 * *crafts as i64;
+        }
+    }
+    Item::all()
+        .filter(|item| balance[*item as usize] < 0)
+        .map(|item| (item, -balance[item as usize] as u32))
+        .collect()
+}
+
+fn add_held(held: &mut Vec<(Item, u32)>, item: Item, count: u32) {
+    match held.iter_mut().find(|(held_item, _)| *held_item == item) {
+        Some((_, held_count)) => *held_count += count,
+        None => held.push((item, count)),
+    }
+}
+
+fn held_count(held: &[(Item, u32)], item: Item) -> u32 {
+    held.iter().filter(|(held_item, _)| *held_item == item).map(|(_, count)| *count).sum()
+}
+
+// Take up to count of an item from held materials, returns how many were taken
+fn take_held(held: &mut Vec<(Item, u32)>, item: Item, count: u32) -> u32 {
+    let Some(index) = held.iter().position(|(held_item, _)| *held_item == item) else {
+        return 0;
+    };
+    let taken = held[index].1.min(count);
+    held[index].1 -= taken;
+    if held[index].1 == 0 {
+        held.remove(index);
+    }
+    taken
+}
+
+// The jobs of the plan the job at index belongs to (only that job for a single-step craft)
+fn plan_jobs(queue: &VecDeque<CraftJob>, index: usize) -> Range<usize> {
+    let mut start = index;
+    while start > 0 && queue[start - 1].feeds_next {
+        start -= 1;
+    }
+    let mut end = index;
+    while queue[end].feeds_next && end + 1 < queue.len() {
+        end += 1;
+    }
+    start..end + 1
+}
+
+// Hand items from the front job on: an intermediate step gives them to the next step of its plan,
+// anything else puts them in the inventory
+fn deliver(game_state: &mut GameState, items: &[(Item, u32)], feeds_next: bool) {
+    if feeds_next {
+        if let Some(next) = game_state.player_mut().crafting_queue_mut().get_mut(1) {
+            for (item_type, count) in items {
+                add_held(&mut next.held, *item_type, *count);
             }
-            
-            // Add result to inventory
-            game_state.player_mut().add_to_inventory(recipe.result.clone());
-            
-            // Validate selection after inventory change
-            game_state.validate_selection();
-            
-            game_state.add_console_message("Crafted Drill!".to_string());
-        }
-    }
-}
-
-// Handle chest crafting
-pub fn handle_chest_crafting(game_state: &mut GameState) {
-    let recipes = get_crafting_recipes();
-    
-    // Find chest recipe
-    if let Some(recipe) = recipes.iter().find(|r| matches!(r.result, Item::Chest)) {
-        // Check if player has all required ingredients
-        let mut has_all = true;
-        for (item_type, required_count) in &recipe.ingredients {
-            let count = get_item_count(game_state, item_type);
-            if count < *required_count {
-                has_all = false;
-                let message = format!("Not enough resources! Need {} {} (have {})", required_count, get_item_name(item_type), count);
-                game_state.add_console_message(message);
-                break;
+            return;
+        }
+    }
+    for (item_type, count) in items {
+        game_state.player_mut().inventory_mut().add_items(*item_type, *count);
+    }
+}
+
+// Most crafts of a recipe that can be planned from the inventory (0 for machine-only recipes).
+// Bigger plans need at least as much of everything, so the answer is found by bisection.
+fn max_crafts(inventory: &Inventory, recipe_id: &str) -> u32 {
+    let (mut low, mut high) = (0, u32::MAX);
+    while low < high {
+        let mid = low + (high - low).div_ceil(2);
+        if plan_craft(inventory, recipe_id, mid).is_ok() {
+            low = mid;
+        } else {
+            high = mid - 1;
+        }
+    }
+    low
+}
+
+// Whether `count` crafts can be made from the inventory, crafting intermediates as needed
+pub fn can_craft(game_state: &GameState, recipe_id: &str, count: u32) -> bool {
+    plan_craft(game_state.player().inventory(), recipe_id, count).is_ok()
+}
+
+// Queue `count` crafts of a recipe, together with any intermediate crafts it needs.
+// Everything the crafts use from the inventory is taken when they are queued, so the queue
+// always delivers what it reports. Returns how many crafts of the recipe were queued.
+pub fn craft(game_state: &mut GameState, recipe_id: &str, count: u32) -> u32 {
+    if count == 0 {
+        return 0;
+    }
+
+    let steps = match plan_craft(game_state.player().inventory(), recipe_id, count) {
+        Ok(steps) => steps,
+        Err(error) => {
+            game_state.emit(GameEvent::ActionFailed(ActionError::Craft(error)));
+            return 0;
+        }
+    };
+
+    if let [(recipe, crafts)] = steps[..] {
+        // Remove ingredients
+        for (item_type, required) in recipe.ingredients {
+            game_state.player_mut().inventory_mut().remove_items(*item_type, required * crafts);
+        }
+        game_state.player_mut().crafting_queue_mut().push_back(CraftJob::new(recipe, crafts));
+
+        // Validate selection after inventory change
+        game_state.validate_selection();
+    } else {
+        // The first step holds the plan's materials and passes what it doesn't use on to the next
+        let mut reserved = plan_inputs(&steps);
+        for (item_type, count) in &reserved {
+            game_state.player_mut().inventory_mut().remove_items(*item_type, *count);
+        }
+        let last = steps.len() - 1;
+        for (index, (recipe, crafts)) in steps.iter().enumerate() {
+            let mut job = CraftJob::deferred(recipe, *crafts);
+            job.feeds_next = index < last;
+            if index == 0 {
+                job.held = std::mem::take(&mut reserved);
             }
+            game_state.player_mut().crafting_queue_mut().push_back(job);
+        }
+        game_state.validate_selection();
+    }
+
+    let recipe = steps[steps.len() - 1].0;
+    game_state.emit(GameEvent::CraftQueued { recipe: recipe.id.to_string(), count });
+
+    count
+}
+
+// Remove a job from the crafting queue, together with the rest of its plan, and refund
+// the materials the plan holds and the ingredients of its unfinished crafts
+pub fn cancel_craft(game_state: &mut GameState, index: usize) -> bool {
+    let queue = game_state.player().crafting_queue();
+    if index >= queue.len() {
+        return false;
+    }
+    let jobs = plan_jobs(queue, index);
+
+    // Deferred jobs haven't taken their ingredients yet
+    let mut refund = Vec::new();
+    for job in queue.range(jobs.clone()) {
+        refund.extend(job.held.iter().copied());
+        if let Some(recipe) = CraftingRecipe::find(&job.recipe_id).filter(|_| !job.deferred) {
+            refund.extend(recipe.ingredients.iter().map(|(item_type, required)| (*item_type, required * job.count)));
         }
-        
-        if has_all {
-            // Remove ingredients
-            for (item_type, count) in &recipe.ingredients {
-                game_state.player_mut().inventory_mut().remove_items(item_type.clone(), *count);
+    }
+    if !game_state.player().inventory().can_add_all(&refund) {
+        game_state.emit(GameEvent::ActionFailed(ActionError::InventoryFull));
+        return false;
+    }
+
+    let cancelled: Vec<CraftJob> = game_state.player_mut().crafting_queue_mut().drain(jobs).collect();
+    for (item_type, count) in refund {
+        game_state.player_mut().inventory_mut().add_items(item_type, count);
+    }
+    game_state.validate_selection();
+
+    for job in cancelled {
+        game_state.emit(GameEvent::CraftCancelled { recipe: job.recipe_id });
+    }
+
+    true
+}
+
+// Advance the job at the front of the crafting queue by one tick
+pub fn handle_crafting_tick(game_state: &mut GameState) {
+    if !start_deferred_job(game_state) {
+        return;
+    }
+
+    let (recipe_id, was_waiting, last_craft, feeds_next, held) = match game_state.player_mut().crafting_queue_mut().front_mut() {
+        Some(job) => {
+            let was_waiting = job.ticks_remaining == 0;
+            job.ticks_remaining = job.ticks_remaining.saturating_sub(1);
+            if job.ticks_remaining > 0 {
+                return;
             }
-            
-            // Add result to inventory
-            game_state.player_mut().add_to_inventory(recipe.result.clone());
-            
-            // Validate selection after inventory change
-            game_state.validate_selection();
-            
-            game_state.add_console_message("Crafted Chest!".to_string());
-        }
-    }
-}
-
-// Handle crafting
-pub fn handle_crafting(game_state: &mut GameState) {
-    let recipes = get_crafting_recipes();
-    
-    // Try to craft a furnace (first recipe for now)
-    if let Some(recipe) = recipes.first() {
-        // Check if player has all required ingredients
-        let mut has_all = true;
-        for (item_type, required_count) in &recipe.ingredients {
-            let count = get_item_count(game_state, item_type);
-            if count < *required_count {
-                has_all = false;
-                let message = format!("Not enough resources! Need {} {} (have {})", required_count, get_item_name(item_type), count);
-                game_state.add_console_message(message);
-                break;
+            (job.recipe_id.clone(), was_waiting, job.count <= 1, job.feeds_next, job.held.clone())
+        }
+        None => return,
+    };
+    let recipe = CraftingRecipe::find(&recipe_id);
+
+    // A finished craft waits until its results fit in the inventory
+    // (with the plan's leftover materials after the last craft); intermediate results go to the next step
+    if let Some(recipe) = recipe {
+        let mut delivered = recipe.results.to_vec();
+        if last_craft {
+            delivered.extend(&held);
+        }
+        if !feeds_next && !game_state.player().inventory().can_add_all(&delivered) {
+            if !was_waiting {
+                game_state.emit(GameEvent::CraftWaiting { recipe: recipe_id });
             }
+            return;
+        }
+    }
+
+    if let Some(recipe) = recipe {
+        deliver(game_state, recipe.results, feeds_next);
+        for (item_type, produced) in recipe.results {
+            game_state.stats_mut().produced(*item_type, *produced);
+        }
+        // Ingredients were taken when the job started, but only count as used once the craft is done
+        for (item_type, needed) in recipe.ingredients {
+            game_state.stats_mut().consumed(*item_type, *needed);
         }
-        
-        if has_all {
-            // Remove ingredients
-            for (item_type, count) in &recipe.ingredients {
-                game_state.player_mut().inventory_mut().remove_items(item_type.clone(), *count);
+    }
+
+    // One craft finished - start the next one or drop the job, handing its leftover materials on
+    if last_craft || recipe.is_none() {
+        deliver(game_state, &held, feeds_next);
+        game_state.player_mut().crafting_queue_mut().pop_front();
+    } else if let (Some(job), Some(recipe)) = (game_state.player_mut().crafting_queue_mut().front_mut(), recipe) {
+        job.count -= 1;
+        job.ticks_remaining = recipe.craft_ticks.max(1);
+    }
+
+    // Validate selection after inventory change
+    game_state.validate_selection();
+
+    if let Some(recipe) = recipe {
+        game_state.emit(GameEvent::Crafted { recipe: recipe_id, results: recipe.results.to_vec() });
+    }
+}
+
+// Take the ingredients of a deferred job once it reaches the front of the queue, from the materials
+// its plan holds (saves from before plans held their materials fall back to the inventory).
+// Returns false when the queue is empty or the job had to be dropped.
+fn start_deferred_job(game_state: &mut GameState) -> bool {
+    let (recipe_id, count, feeds_next, mut held) = match game_state.player_mut().crafting_queue_mut().front_mut() {
+        Some(job) if job.deferred => (job.recipe_id.clone(), job.count, job.feeds_next, std::mem::take(&mut job.held)),
+        Some(_) => return true,
+        None => return false,
+    };
+    let recipe = CraftingRecipe::find(&recipe_id);
+
+    let inventory = game_state.player().inventory();
+    let crafts = recipe.map_or(0, |recipe| {
+        recipe
+            .ingredients
+            .iter()
+            .map(|(item, required)| (held_count(&held, *item) + inventory.count(*item)) / (*required).max(1))
+            .min()
+            .unwrap_or(u32::MAX)
+            .min(count)
+    });
+    let Some(recipe) = recipe.filter(|_| crafts > 0) else {
+        // Nothing to craft with - the rest of the plan gets what this step held
+        if !feeds_next && !game_state.player().inventory().can_add_all(&held) {
+            if let Some(job) = game_state.player_mut().crafting_queue_mut().front_mut() {
+                job.held = held;
             }
-            
-            // Add result to inventory
-            game_state.player_mut().add_to_inventory(recipe.result.clone());
-            
-            // Validate selection after inventory change
-            game_state.validate_selection();
-            
-            let message = format!("Crafted {}!", get_item_name(&recipe.result));
-            game_state.add_console_message(message);
+            return false;
         }
+        deliver(game_state, &held, feeds_next);
+        game_state.player_mut().crafting_queue_mut().pop_front();
+        game_state.validate_selection();
+        game_state.emit(GameEvent::CraftAbandoned { recipe: recipe_id });
+        return false;
+    };
+
+    // Remove ingredients
+    for (item_type, required) in recipe.ingredients {
+        let needed = required * crafts;
+        let from_inventory = needed - take_held(&mut held, *item_type, needed);
+        game_state.player_mut().inventory_mut().remove_items(*item_type, from_inventory);
+    }
+    game_state.validate_selection();
+
+    if let Some(job) = game_state.player_mut().crafting_queue_mut().front_mut() {
+        job.count = crafts;
+        job.deferred = false;
+        job.held = held;
     }
+    true
 }
 
+// One entry in the player's crafting queue
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct CraftJob {
+    recipe_id: String,
+    count: u32,           // Crafts left, including the one in progress
+    ticks_remaining: u32, // Ticks until the current craft finishes
+    #[serde(default)]
+    deferred: bool,       // Ingredients are taken when the job reaches the front of the queue
+    #[serde(default)]
+    feeds_next: bool,     // An intermediate step of a plan: its results go to the next job
+    #[serde(default)]
+    held: Vec<(Item, u32)>, // Plan materials reserved for this step and the ones after it
+}
+
+impl CraftJob {
+    fn new(recipe: &CraftingRecipe, count: u32) -> CraftJob {
+        CraftJob {
+            recipe_id: recipe.id.to_string(),
+            count,
+            ticks_remaining: recipe.craft_ticks.max(1),
+            deferred: false,
+            feeds_next: false,
+            held: Vec::new(),
+        }
+    }
+
+    // Job for a step of a multi-step plan, whose ingredients may not exist yet
+    fn deferred(recipe: &CraftingRecipe, count: u32) -> CraftJob {
+        CraftJob {
+            deferred: true,
+            ..CraftJob::new(recipe, count)
+        }
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl CraftJob {
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn recipe_id(&self) -> String {
+        self.recipe_id.clone()
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn count(&self) -> u32 {
+        self.count
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn ticks_remaining(&self) -> u32 {
+        self.ticks_remaining
+    }
+
+    // Duration of one craft
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn total_ticks(&self) -> u32 {
+        CraftingRecipe::find(&self.recipe_id).map_or(1, |recipe| recipe.craft_ticks.max(1))
+    }
+
+    // Progress of the current craft from 0.0 to 1.0
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn progress(&self) -> f64 {
+        let total = self.total_ticks();
+        (total - self.ticks_remaining.min(total)) as f64 / total as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn huge_craft_counts_are_rejected_instead_of_overflowing() {
+        let game_state = GameState::new();
+        assert_eq!(game_state.plan_craft("furnace", u32::MAX).err(), Some(CraftError::TooMany(Item::Stone)));
+        assert!(!game_state.can_craft("furnace", u32::MAX));
+    }
+
+    #[test]
+    fn machine_only_recipes_are_never_listed_as_craftable() {
+        let game_state = GameState::new();
+        let recipes = game_state.list_recipes();
+        for info in &recipes {
+            assert_eq!(info.craftable(), game_state.can_craft(&info.id(), 1), "{}", info.id());
+            assert!(game_state.can_craft(&info.id(), info.max_crafts()) || info.max_crafts() == 0);
+            assert!(!game_state.can_craft(&info.id(), info.max_crafts() + 1));
+        }
+        // Plates are smelted in furnaces, even with ore in the inventory
+        let plates = recipes.iter().find(|info| info.id() == "iron_plate").unwrap();
+        assert!(!plates.hand_craftable() && !plates.craftable() && plates.max_crafts() == 0);
+        assert!(game_state.item_count(Item::IronOre) > 0);
+        assert_eq!(game_state.plan_craft("iron_plate", 1).err(), Some(CraftError::NotHandCraftable("Iron Plate")));
+        assert!(recipes.iter().any(|info| info.id() == "furnace" && info.craftable()));
+    }
+
+    // Starting game with the gears used up, so splitters need gears made from plates first
+    fn without_gears() -> GameState {
+        let mut game_state = GameState::new();
+        let gears = game_state.item_count(Item::IronGear);
+        game_state.player_mut().inventory_mut().remove_items(Item::IronGear, gears);
+        game_state
+    }
+
+    fn run_queue(game_state: &mut GameState) {
+        while !game_state.crafting_queue().is_empty() {
+            game_state.step(&[]);
+        }
+    }
+
+    #[test]
+    fn missing_intermediates_are_planned_from_raw_materials() {
+        let mut game_state = without_gears();
+        let plates = game_state.item_count(Item::IronPlate);
+        let splitters = game_state.item_count(Item::Splitter);
+
+        let plan = game_state.plan_craft("splitter", 5).unwrap();
+        let steps: Vec<(&str, u32)> = plan.iter().map(|(recipe, crafts)| (recipe.id, *crafts)).collect();
+        assert_eq!(steps, vec![("iron_gear", 10), ("splitter", 5)]);
+
+        assert_eq!(game_state.craft("splitter", 5), 5);
+        run_queue(&mut game_state);
+        assert_eq!(game_state.item_count(Item::Splitter), splitters + 5);
+        assert_eq!(game_state.item_count(Item::IronPlate), plates - 25);
+        assert_eq!(game_state.item_count(Item::IronGear), 0);
+    }
+
+    #[test]
+    fn short_plans_report_raw_materials() {
+        // Plates are machine-only, so they count as raw materials rather than being planned from ore
+        let game_state = without_gears();
+        assert_eq!(game_state.item_count(Item::IronPlate), 50);
+        assert_eq!(
+            game_state.plan_craft("splitter", 20).err(),
+            Some(CraftError::MissingMaterials(vec![(Item::IronPlate, 50), (Item::CopperPlate, 50)]))
+        );
+    }
+
+    #[test]
+    fn recipes_count_as_craftable_through_intermediates() {
+        // With no gears left the splitter's direct ingredients allow none, but gears planned from
+        // plates make it 5 iron plates a splitter: 10 from 50, even with copper to spare
+        let mut game_state = without_gears();
+        game_state.player_mut().inventory_mut().add_items(Item::CopperPlate, 100);
+        let splitter = game_state.list_recipes().into_iter().find(|info| info.id() == "splitter").unwrap();
+        assert!(splitter.craftable());
+        assert_eq!(splitter.max_crafts(), 10);
+        assert!(game_state.can_craft("splitter", 10) && !game_state.can_craft("splitter", 11));
+    }
+
+    #[test]
+    fn queued_plans_keep_their_materials() {
+        let mut game_state = without_gears();
+        let (plates, splitters, chests) = (
+            game_state.item_count(Item::IronPlate),
+            game_state.item_count(Item::Splitter),
+            game_state.item_count(Item::Chest),
+        );
+        assert_eq!(game_state.craft("splitter", 5), 5);
+        assert_eq!(game_state.item_count(Item::IronPlate), plates - 25);
+
+        // Crafting with the plates left over can't eat into the plan
+        assert_eq!(game_state.craft("chest", plates - 25), plates - 25);
+        assert!(!game_state.can_craft("chest", 1));
+        run_queue(&mut game_state);
+        assert_eq!(game_state.item_count(Item::Splitter), splitters + 5);
+        assert_eq!(game_state.item_count(Item::Chest), chests + plates - 25);
+        assert_eq!(game_state.item_count(Item::IronGear), 0);
+    }
+
+    #[test]
+    fn cancelling_any_step_cancels_and_refunds_the_whole_plan() {
+        let mut game_state = without_gears();
+        let (plates, copper) = (game_state.item_count(Item::IronPlate), game_state.item_count(Item::CopperPlate));
+        game_state.craft("chest", 1);
+        game_state.craft("splitter", 5);
+        for _ in 0..100 {
+            game_state.step(&[]);
+        }
+        assert!(game_state.item_count(Item::IronGear) == 0 && game_state.crafting_queue().len() == 2);
+
+        // Cancelling the splitter step also cancels the gear step that is partly done;
+        // the finished gears come back as gears
+        assert!(game_state.cancel_craft(1));
+        assert!(game_state.crafting_queue().is_empty());
+        assert_eq!(game_state.item_count(Item::IronGear), 2);
+        assert_eq!(game_state.item_count(Item::IronPlate), plates - 1 - 4);
+        assert_eq!(game_state.item_count(Item::CopperPlate), copper);
+    }
+
+    #[test]
+    fn steps_of_old_plans_whose_materials_were_used_up_are_abandoned() {
+        // Plans queued before they held their materials take them from the inventory
+        let mut game_state = without_gears();
+        game_state.craft("splitter", 5);
+        let held = game_state.player_mut().crafting_queue_mut().front_mut().map(|job| std::mem::take(&mut job.held)).unwrap();
+        for (item, count) in held {
+            game_state.player_mut().inventory_mut().add_items(item, count);
+        }
+        let plates = game_state.item_count(Item::IronPlate);
+        game_state.player_mut().inventory_mut().remove_items(Item::IronPlate, plates);
+        game_state.drain_events();
+
+        run_queue(&mut game_state);
+        let abandoned: Vec<String> = game_state
+            .drain_events()
+            .into_iter()
+            .filter_map(|record| match record.event {
+                GameEvent::CraftAbandoned { recipe } => Some(recipe),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(abandoned, vec!["iron_gear", "splitter"]);
+        assert_eq!(game_state.item_count(Item::IronGear), 0);
+    }
+}
@@ -1,66 +1,192 @@
+#[cfg(feature = "wasm")]
 use wasm_bindgen::prelude::*;
+use serde::{Serialize, Deserialize};
+use crate::types::{Item, PlaceableType, ResourceType, Direction};
+use crate::entity::{Entity, MachineStatus, TickContext};
+use crate::map::DroppedItem;
+use crate::belt::{BeltLane, BELT_INSERT_PROGRESS};
+use crate::fuel::FuelStore;
+use crate::stats::ProductionStats;
 
-// Drill data - tracks coal and processing
-#[wasm_bindgen]
-#[derive(Clone)]
+// Drill data - tracks fuel and processing
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct DrillData {
-    coal_count: u32,
+    fuel: FuelStore,
     processing_ticks_remaining: u32, // Ticks remaining for current processing (0-60)
+    #[serde(default)]
+    status: MachineStatus, // Updated every tick
 }
 
-#[wasm_bindgen]
+impl Default for DrillData {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
 impl DrillData {
-    #[wasm_bindgen(constructor)]
+    #[cfg_attr(feature = "wasm", wasm_bindgen(constructor))]
     pub fn new() -> DrillData {
         DrillData {
-            coal_count: 0,
+            fuel: FuelStore::new(),
             processing_ticks_remaining: 0,
+            status: MachineStatus::default(),
         }
     }
 
-    #[wasm_bindgen(getter)]
-    pub fn coal_count(&self) -> u32 {
-        self.coal_count
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn fuel(&self) -> FuelStore {
+        self.fuel.clone()
     }
 
-    pub fn add_coal(&mut self) {
-        self.coal_count += 1;
+    // Start processing if we have fuel and not already processing
+    pub fn start_processing_if_able(&mut self) {
+        if self.processing_ticks_remaining == 0 && self.fuel.has_fuel() {
+            self.processing_ticks_remaining = 60; // 60 ticks to process
+        }
     }
 
-    pub fn remove_coal(&mut self, count: u32) -> bool {
-        if self.coal_count >= count {
-            self.coal_count -= count;
-            true
-        } else {
-            false
-        }
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn processing_ticks_remaining(&self) -> u32 {
+        self.processing_ticks_remaining
     }
 
-    // Start processing if we have coal and not already processing
-    pub fn start_processing_if_able(&mut self) {
-        if self.processing_ticks_remaining == 0 && self.coal_count > 0 {
-            self.processing_ticks_remaining = 60; // 60 ticks to process
-        }
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn status(&self) -> MachineStatus {
+        self.status
     }
+}
+
 
-    // Process one tick - returns true if processing completed
-    pub fn process_tick(&mut self) -> bool {
+impl DrillData {
+    // Process one tick - returns true if processing completed (burning fuel for the cycle)
+    pub fn process_tick(&mut self, stats: &mut ProductionStats) -> bool {
         if self.processing_ticks_remaining > 0 {
             self.processing_ticks_remaining -= 1;
             if self.processing_ticks_remaining == 0 {
-                // Processing complete - burn one coal
-                if self.coal_count > 0 {
-                    self.coal_count -= 1;
-                    return true;
-                }
+                return self.fuel.burn_cycle(stats);
             }
         }
         false
     }
 
-    #[wasm_bindgen(getter)]
-    pub fn processing_ticks_remaining(&self) -> u32 {
-        self.processing_ticks_remaining
+    // What this drill mines from the tile under it (stone is not drillable)
+    fn output(ctx: &TickContext) -> Option<Item> {
+        ctx.map.get_resource_at(ctx.x, ctx.y)
+            .filter(|resource_type| !matches!(resource_type, ResourceType::Stone))
+            .map(|resource_type| resource_type.item())
+    }
+
+    // Drills push items onto the near lane of a belt beside them (the right lane of one in line with them)
+    fn belt_lane(belt_direction: Direction, drill_direction: Direction) -> BeltLane {
+        BeltLane::near_side(belt_direction, drill_direction).unwrap_or(BeltLane::Right)
+    }
+
+    // Whether the output tile can take the mined item, checked before starting a new cycle
+    fn target_can_accept(ctx: &TickContext, target: Option<(u32, u32)>, item: Option<Item>) -> bool {
+        let Some((target_x, target_y)) = target else {
+            return false;
+        };
+        if ctx.map.would_collide_with_water(target_x, target_y) {
+            return false;
+        }
+        if let Some(belt_direction) = ctx.map.belt_direction_at(target_x, target_y) {
+            ctx.map.belt_has_room(target_x, target_y, Self::belt_lane(belt_direction, ctx.direction), BELT_INSERT_PROGRESS)
+        } else if ctx.map.get_placeable_at(target_x, target_y).is_some() {
+            // Other placeables have to take the item directly
+            item.is_some_and(|item| ctx.map.accepts_item_at(target_x, target_y, item))
+        } else {
+            // No placeable, check if space is free (no dropped item)
+            ctx.map.get_dropped_item_index_at(target_x, target_y).is_none()
+        }
     }
 }
 
+// Drills produce an item every 60 ticks (burning fuel) if placed over a resource,
+// pushing it onto the tile in front of them
+impl Entity for DrillData {
+    fn tick(&mut self, ctx: &mut TickContext) {
+        let target = ctx.neighbour(ctx.direction);
+        let item = Self::output(ctx);
+        let can_output = Self::target_can_accept(ctx, target, item);
+
+        // Only start a new cycle if the output has somewhere to go and we have coal
+        if self.processing_ticks_remaining == 0 && can_output {
+            self.start_processing_if_able();
+        }
+
+        self.status = if item.is_none() {
+            MachineStatus::NoResourceUnderneath
+        } else if self.processing_ticks_remaining > 0 {
+            MachineStatus::Working
+        } else if !self.fuel.has_fuel() {
+            MachineStatus::NoFuel
+        } else if !can_output {
+            MachineStatus::OutputBlocked
+        } else {
+            MachineStatus::Working
+        };
+
+        if !self.process_tick(ctx.stats) {
+            return;
+        }
+        let (Some((target_x, target_y)), Some(item)) = (target, item) else {
+            return;
+        };
+        if ctx.map.would_collide_with_water(target_x, target_y) {
+            return;
+        }
+
+        let emitted = match ctx.map.get_placeable_at(target_x, target_y) {
+            Some(_) if ctx.insert_item_at(target_x, target_y, item) => true,
+            Some(placeable_type) if placeable_type.is_belt() => {
+                // Place on belt if there's room
+                let belt_item = DroppedItem::new(target_x, target_y, item, 1);
+                let lane = ctx.map.belt_direction_at(target_x, target_y)
+                    .map_or(BeltLane::Right, |belt_direction| Self::belt_lane(belt_direction, ctx.direction));
+                ctx.map.insert_onto_belt(target_x, target_y, belt_item, lane, BELT_INSERT_PROGRESS)
+            }
+            Some(_) => {
+                // Containers that refuse the item block it, anything else gets it dropped on top
+                let is_container = ctx.map.get_placeable_object_at(target_x, target_y)
+                    .is_some_and(|obj| obj.state().is_container());
+                let dropped = !is_container && !ctx.map.would_collide_with_placeable(target_x, target_y);
+                if dropped {
+                    ctx.map.add_dropped_item(DroppedItem::new(target_x, target_y, item, 1));
+                }
+                dropped
+            }
+            None => {
+                // Empty space, place as dropped item
+                ctx.map.add_dropped_item(DroppedItem::new(target_x, target_y, item, 1));
+                true
+            }
+        };
+        if emitted {
+            ctx.produced(PlaceableType::Drill, item);
+        }
+    }
+
+    // Only fuel goes in
+    fn accepts_item(&self, item: Item) -> bool {
+        self.fuel.accepts(item)
+    }
+
+    fn insert_item(&mut self, item: Item) {
+        self.fuel.add(item);
+    }
+
+    // Fuel can't be pulled back out by arms
+    fn take_item(&mut self) -> Option<Item> {
+        None
+    }
+
+    fn contents(&self) -> Vec<(Item, u32)> {
+        self.fuel.contents().into_iter().collect()
+    }
+
+    fn status(&self) -> Option<MachineStatus> {
+        Some(self.status)
+    }
+}
@@ -0,0 +1,344 @@
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+use std::fmt;
+use serde::{Serialize, Deserialize};
+use crate::types::{Item, Direction, PlaceableType};
+use crate::map::Map;
+use crate::stats::ProductionStats;
+use crate::events::GameEvent;
+use crate::furnace::FurnaceData;
+use crate::arm::ArmData;
+use crate::chest::ChestData;
+use crate::drill::DrillData;
+use crate::underground_belt::UndergroundBeltData;
+use crate::splitter::SplitterData;
+
+// Stable identifier of a placed entity - never reused, survives save/load
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct EntityId(pub u32);
+
+impl fmt::Display for EntityId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "#{}", self.0)
+    }
+}
+
+// Why a machine is (or isn't) doing anything, for status icons and diagnostics
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MachineStatus {
+    #[default]
+    Working,
+    NoFuel,
+    NoInput,
+    OutputBlocked,
+    NoResourceUnderneath,
+    TargetFull,  // Arm: nowhere to put the item
+    SourceEmpty, // Arm: nothing to pick up
+}
+
+impl MachineStatus {
+    pub fn name(&self) -> &'static str {
+        match self {
+            MachineStatus::Working => "Working",
+            MachineStatus::NoFuel => "No fuel",
+            MachineStatus::NoInput => "No input",
+            MachineStatus::OutputBlocked => "Output blocked",
+            MachineStatus::NoResourceUnderneath => "No resource",
+            MachineStatus::TargetFull => "Target full",
+            MachineStatus::SourceEmpty => "Source empty",
+        }
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn machine_status_name(status: MachineStatus) -> String {
+    status.name().to_string()
+}
+
+// What an entity sees while it ticks: its own placement and the rest of the world
+pub struct TickContext<'a> {
+    pub id: EntityId,
+    pub x: u32,
+    pub y: u32,
+    pub direction: Direction,
+    pub map: &'a mut Map,
+    pub stats: &'a mut ProductionStats,
+    pub events: &'a mut Vec<GameEvent>, // Emitted after the tick
+}
+
+impl TickContext<'_> {
+    // Report an item this entity finished
+    pub fn produced(&mut self, placeable_type: PlaceableType, item: Item) {
+        self.stats.produced(item, 1);
+        self.events.push(GameEvent::ItemProduced { id: self.id, x: self.x, y: self.y, placeable_type, item });
+    }
+
+    // The adjacent tile in a direction (None off the map)
+    pub fn neighbour(&self, direction: Direction) -> Option<(u32, u32)> {
+        self.map.neighbour(self.x, self.y, direction)
+    }
+
+    // Insert one item into the entity at a position, counting what goes into chests
+    pub fn insert_item_at(&mut self, x: u32, y: u32, item: Item) -> bool {
+        let is_chest = self.map.get_placeable_at(x, y) == Some(PlaceableType::Chest);
+        let inserted = self.map.insert_item_at(x, y, item);
+        if inserted && is_chest {
+            self.stats.stored(item, 1);
+        }
+        inserted
+    }
+
+    // Take one item out of the entity at a position, counting what comes out of chests
+    pub fn take_item_at(&mut self, x: u32, y: u32) -> Option<Item> {
+        let is_chest = self.map.get_placeable_at(x, y) == Some(PlaceableType::Chest);
+        let item = self.map.take_item_at(x, y)?;
+        if is_chest {
+            self.stats.retrieved(item, 1);
+        }
+        Some(item)
+    }
+}
+
+// Behaviour shared by every placed object.
+// Handlers only talk to entities through this trait, so a new machine type
+// just needs an EntityState variant and an impl.
+pub trait Entity {
+    // Advance one simulation tick
+    fn tick(&mut self, ctx: &mut TickContext);
+
+    // Whether one of this item can be inserted right now
+    fn accepts_item(&self, item: Item) -> bool;
+
+    // Insert one item; only called after accepts_item returned true
+    fn insert_item(&mut self, item: Item);
+
+    // Take one item out (what arms and the player harvest)
+    fn take_item(&mut self) -> Option<Item>;
+
+    // Everything stored inside, for display and pickup
+    fn contents(&self) -> Vec<(Item, u32)>;
+
+    // Called when the entity is picked up; returns the items to give back to the player
+    fn on_removed(&mut self) -> Vec<(Item, u32)> {
+        self.contents()
+    }
+
+    // What the machine is doing (None for things that never do anything on their own)
+    fn status(&self) -> Option<MachineStatus> {
+        None
+    }
+}
+
+// Type-specific state of a placed entity
+#[derive(Clone, Serialize, Deserialize)]
+pub enum EntityState {
+    Furnace(FurnaceData),
+    Belt,
+    Arm(ArmData),
+    Chest(ChestData),
+    Drill(DrillData),
+    UndergroundBelt(UndergroundBeltData),
+    Splitter(SplitterData),
+}
+
+impl EntityState {
+    // Fresh state for a newly placed object
+    pub fn new(placeable_type: PlaceableType) -> EntityState {
+        match placeable_type {
+            PlaceableType::Furnace => EntityState::Furnace(FurnaceData::new()),
+            PlaceableType::Belt => EntityState::Belt,
+            PlaceableType::Arm => EntityState::Arm(ArmData::new()),
+            PlaceableType::Chest => EntityState::Chest(ChestData::new()),
+            PlaceableType::Drill => EntityState::Drill(DrillData::new()),
+            PlaceableType::UndergroundBelt => EntityState::UndergroundBelt(UndergroundBeltData::new()),
+            PlaceableType::Splitter => EntityState::Splitter(SplitterData::new()),
+        }
+    }
+
+    pub fn placeable_type(&self) -> PlaceableType {
+        match self {
+            EntityState::Furnace(_) => PlaceableType::Furnace,
+            EntityState::Belt => PlaceableType::Belt,
+            EntityState::Arm(_) => PlaceableType::Arm,
+            EntityState::Chest(_) => PlaceableType::Chest,
+            EntityState::Drill(_) => PlaceableType::Drill,
+            EntityState::UndergroundBelt(_) => PlaceableType::UndergroundBelt,
+            EntityState::Splitter(_) => PlaceableType::Splitter,
+        }
+    }
+
+    fn entity(&self) -> Option<&dyn Entity> {
+        match self {
+            EntityState::Furnace(data) => Some(data),
+            EntityState::Belt | EntityState::UndergroundBelt(_) | EntityState::Splitter(_) => None,
+            EntityState::Arm(data) => Some(data),
+            EntityState::Chest(data) => Some(data),
+            EntityState::Drill(data) => Some(data),
+        }
+    }
+
+    fn entity_mut(&mut self) -> Option<&mut dyn Entity> {
+        match self {
+            EntityState::Furnace(data) => Some(data),
+            EntityState::Belt | EntityState::UndergroundBelt(_) | EntityState::Splitter(_) => None,
+            EntityState::Arm(data) => Some(data),
+            EntityState::Chest(data) => Some(data),
+            EntityState::Drill(data) => Some(data),
+        }
+    }
+
+    // Whether the player can put anything into this entity
+    pub fn is_container(&self) -> bool {
+        Item::all().any(|item| self.accepts_item(item))
+    }
+}
+
+// Belts (underground ones and splitters too) carry items through Map::belt_items and never tick on their own
+impl Entity for EntityState {
+    fn tick(&mut self, ctx: &mut TickContext) {
+        if let Some(entity) = self.entity_mut() {
+            entity.tick(ctx);
+        }
+    }
+
+    fn accepts_item(&self, item: Item) -> bool {
+        self.entity().is_some_and(|entity| entity.accepts_item(item))
+    }
+
+    fn insert_item(&mut self, item: Item) {
+        if let Some(entity) = self.entity_mut() {
+            entity.insert_item(item);
+        }
+    }
+
+    fn take_item(&mut self) -> Option<Item> {
+        self.entity_mut().and_then(|entity| entity.take_item())
+    }
+
+    fn contents(&self) -> Vec<(Item, u32)> {
+        self.entity().map(|entity| entity.contents()).unwrap_or_default()
+    }
+
+    fn on_removed(&mut self) -> Vec<(Item, u32)> {
+        self.entity_mut().map(|entity| entity.on_removed()).unwrap_or_default()
+    }
+
+    fn status(&self) -> Option<MachineStatus> {
+        self.entity().and_then(|entity| entity.status())
+    }
+}
+
+// Point-in-time copy of one entity for the UI and agents.
+// Contents are parallel item/count arrays; the type-specific data getters return None for other types.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Serialize)]
+pub struct EntitySnapshot {
+    id: EntityId,
+    x: u32,
+    y: u32,
+    direction: Direction,
+    content_items: Vec<Item>,
+    content_counts: Vec<u32>,
+    state: EntityState,
+}
+
+impl EntitySnapshot {
+    pub(crate) fn new(id: EntityId, x: u32, y: u32, direction: Direction, contents: Vec<(Item, u32)>, state: EntityState) -> EntitySnapshot {
+        EntitySnapshot {
+            id,
+            x,
+            y,
+            direction,
+            content_items: contents.iter().map(|(item, _)| *item).collect(),
+            content_counts: contents.iter().map(|(_, count)| *count).collect(),
+            state,
+        }
+    }
+
+    pub fn entity_id(&self) -> EntityId {
+        self.id
+    }
+
+    pub fn state(&self) -> &EntityState {
+        &self.state
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl EntitySnapshot {
+    // The entity id as a plain number (what GameState::entity takes)
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn id(&self) -> u32 {
+        self.id.0
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn x(&self) -> u32 {
+        self.x
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn y(&self) -> u32 {
+        self.y
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn placeable_type(&self) -> PlaceableType {
+        self.state.placeable_type()
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn direction(&self) -> Direction {
+        self.direction
+    }
+
+    // Everything stored in or carried on the entity
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn content_items(&self) -> Vec<Item> {
+        self.content_items.clone()
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn content_counts(&self) -> Vec<u32> {
+        self.content_counts.clone()
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn status(&self) -> Option<MachineStatus> {
+        self.state.status()
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn furnace(&self) -> Option<FurnaceData> {
+        match &self.state {
+            EntityState::Furnace(data) => Some(data.clone()),
+            _ => None,
+        }
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn arm(&self) -> Option<ArmData> {
+        match &self.state {
+            EntityState::Arm(data) => Some(data.clone()),
+            _ => None,
+        }
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn chest(&self) -> Option<ChestData> {
+        match &self.state {
+            EntityState::Chest(data) => Some(data.clone()),
+            _ => None,
+        }
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn drill(&self) -> Option<DrillData> {
+        match &self.state {
+            EntityState::Drill(data) => Some(data.clone()),
+            _ => None,
+        }
+    }
+}
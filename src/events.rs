@@ -0,0 +1,206 @@
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+use std::fmt;
+use serde::Serialize;
+use crate::types::{Item, Direction, PlaceableType};
+use crate::entity::EntityId;
+use crate::crafting::{CraftingRecipe, CraftError};
+use crate::splitter::SplitterSide;
+
+// Something that happened in the game. The console text is rendered from these,
+// bots and sound effects can match on them instead of parsing messages.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub enum GameEvent {
+    // The player mined a resource by hand
+    Mined { item: Item, total: u32 },
+    // A machine finished an item (smelted plate, drilled ore)
+    ItemProduced { id: EntityId, x: u32, y: u32, placeable_type: PlaceableType, item: Item },
+    EntityPlaced { id: EntityId, x: u32, y: u32, placeable_type: PlaceableType },
+    // Picked up by the player (with_contents: its contents went to the inventory too)
+    EntityRemoved { id: EntityId, x: u32, y: u32, placeable_type: PlaceableType, with_contents: bool },
+    EntityRotated { id: EntityId, x: u32, y: u32, placeable_type: PlaceableType, direction: Direction },
+    SplitterConfigured {
+        id: EntityId,
+        input_priority: Option<SplitterSide>,
+        output_priority: Option<SplitterSide>,
+        filter: Option<Item>,
+    },
+    // The direction for the next placement changed
+    PlacementRotated { direction: Direction },
+    PlacementRejected { item: Item, x: u32, y: u32, reason: PlacementError },
+    // A line was built: the new entities in route order and the tiles that were skipped
+    LinePlaced { item: Item, placed: Vec<EntityId>, rejected: Vec<(u32, u32, PlacementError)> },
+    // The player moved items between the inventory and the world
+    ItemMoved { item: Item, count: u32, from: ItemLocation, to: ItemLocation },
+    CraftQueued { recipe: String, count: u32 },
+    Crafted { recipe: String, results: Vec<(Item, u32)> },
+    // A finished craft is waiting for inventory space
+    CraftWaiting { recipe: String },
+    CraftCancelled { recipe: String },
+    // A queued intermediate craft was dropped because its materials were used up
+    CraftAbandoned { recipe: String },
+    ActionFailed(ActionError),
+}
+
+// Why an object can't go on a tile
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub enum PlacementError {
+    OutOfBounds,
+    Water,
+    Occupied,
+    OnResource,
+}
+
+// Where moved items came from or went to
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub enum ItemLocation {
+    Inventory,
+    Ground { x: u32, y: u32 },
+    Belt { x: u32, y: u32 },
+    Entity { id: EntityId, x: u32, y: u32, placeable_type: PlaceableType },
+}
+
+// Why a player action did nothing
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub enum ActionError {
+    InventoryFull,
+    NoItemSelected,
+    NotInInventory(Item),
+    NotPlaceable(Item),
+    NoContainer,
+    // The container only takes the listed items
+    NotAccepted { placeable_type: PlaceableType, accepted: Vec<Item> },
+    NothingToHarvest(PlaceableType),
+    NothingToDrop,
+    DropOutOfBounds,
+    DropOnWater,
+    DropOnEntity,
+    DropOnDifferentItem,
+    BeltOccupied,
+    NoSplitter,
+    Craft(CraftError),
+}
+
+// An event and the tick it happened on
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct EventRecord {
+    pub tick: u64,
+    pub event: GameEvent,
+}
+
+fn recipe_name(recipe_id: &str) -> &str {
+    CraftingRecipe::find(recipe_id).map_or(recipe_id, |recipe| recipe.name)
+}
+
+fn placeable_name(placeable_type: PlaceableType) -> String {
+    placeable_type.item().name().to_lowercase()
+}
+
+impl GameEvent {
+    // Console line for the event (None for events too frequent or too minor to log)
+    pub fn console_text(&self) -> Option<String> {
+        let text = match self {
+            GameEvent::Mined { item, total } => format!("Mined {}! Total: {}", item.name(), total),
+            GameEvent::ItemProduced { x, y, placeable_type: PlaceableType::Furnace, item, .. } => {
+                let name = item.name().to_lowercase();
+                let article = if name.starts_with(['a', 'e', 'i', 'o', 'u']) { "an" } else { "a" };
+                format!("Furnace at ({}, {}) produced {} {}!", x, y, article, name)
+            }
+            // Drill output is too frequent to log
+            GameEvent::ItemProduced { .. } => return None,
+            GameEvent::EntityPlaced { placeable_type, .. } => format!("Placed {}!", placeable_name(*placeable_type)),
+            GameEvent::EntityRemoved { placeable_type, with_contents: true, .. } => {
+                format!("Picked up {} with all contents!", placeable_name(*placeable_type))
+            }
+            GameEvent::EntityRemoved { placeable_type, .. } => format!("Picked up {}!", placeable_name(*placeable_type)),
+            GameEvent::EntityRotated { placeable_type, direction, .. } => {
+                format!("Rotated {} to {:?}!", placeable_name(*placeable_type), direction)
+            }
+            GameEvent::SplitterConfigured { input_priority, output_priority, filter, .. } => {
+                let side = |side: &Option<SplitterSide>| side.map_or("none".to_string(), |side| format!("{:?}", side).to_lowercase());
+                let filter = filter.map_or("none", |item| item.name());
+                format!(
+                    "Splitter set: input priority {}, output priority {}, filter {}",
+                    side(input_priority),
+                    side(output_priority),
+                    filter
+                )
+            }
+            GameEvent::PlacementRotated { direction } => format!("Placing facing {:?}", direction),
+            GameEvent::PlacementRejected { reason, .. } => reason.to_string(),
+            GameEvent::LinePlaced { item, placed, rejected } => {
+                let placed = format!("Placed {} {}!", placed.len(), item.name());
+                match rejected.len() {
+                    0 => placed,
+                    1 => format!("{} 1 tile skipped", placed),
+                    skipped => format!("{} {} tiles skipped", placed, skipped),
+                }
+            }
+            GameEvent::ItemMoved { item, count, from, to } => match (from, to) {
+                (ItemLocation::Inventory, ItemLocation::Entity { placeable_type, .. }) => {
+                    format!("Added {} to {}!", item.name().to_lowercase(), placeable_name(*placeable_type))
+                }
+                (ItemLocation::Entity { .. }, ItemLocation::Inventory) => format!("Harvested {}!", item.name().to_lowercase()),
+                (ItemLocation::Inventory, ItemLocation::Belt { .. }) => format!("Placed {} {} on belt!", count, item.name()),
+                (ItemLocation::Belt { .. }, ItemLocation::Inventory) => format!("Picked up {} {} from belt!", count, item.name()),
+                (ItemLocation::Inventory, ItemLocation::Ground { .. }) => format!("Dropped {} {}!", count, item.name()),
+                (ItemLocation::Ground { .. }, ItemLocation::Inventory) => format!("Picked up {} {}!", count, item.name()),
+                _ => return None,
+            },
+            GameEvent::CraftQueued { recipe, count: 1 } => format!("Crafting {}...", recipe_name(recipe)),
+            GameEvent::CraftQueued { recipe, count } => format!("Crafting {} {}...", count, recipe_name(recipe)),
+            GameEvent::Crafted { results, .. } => {
+                let results: Vec<String> = results
+                    .iter()
+                    .map(|(item_type, produced)| match produced {
+                        1 => item_type.name().to_string(),
+                        total => format!("{} {}", total, item_type.name()),
+                    })
+                    .collect();
+                format!("Crafted {}!", results.join(", "))
+            }
+            GameEvent::CraftWaiting { recipe } => format!("Inventory full! {} is waiting for space", recipe_name(recipe)),
+            GameEvent::CraftCancelled { recipe } => format!("Cancelled {}", recipe_name(recipe)),
+            GameEvent::CraftAbandoned { recipe } => format!("Not enough resources to craft {}!", recipe_name(recipe)),
+            GameEvent::ActionFailed(error) => error.to_string(),
+        };
+        Some(text)
+    }
+}
+
+impl fmt::Display for PlacementError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PlacementError::OutOfBounds => write!(f, "Cannot place outside map bounds!"),
+            PlacementError::Water => write!(f, "Cannot place on water!"),
+            PlacementError::Occupied => write!(f, "Position already occupied!"),
+            PlacementError::OnResource => write!(f, "Cannot place on resource!"),
+        }
+    }
+}
+
+impl fmt::Display for ActionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ActionError::InventoryFull => write!(f, "Inventory full!"),
+            ActionError::NoItemSelected => write!(f, "No item selected!"),
+            ActionError::NotInInventory(item) => write!(f, "No {} in inventory!", item.name().to_lowercase()),
+            ActionError::NotPlaceable(_) => write!(f, "Selected item cannot be placed!"),
+            ActionError::NoContainer => write!(f, "No furnace, chest, or drill in front of you!"),
+            ActionError::NotAccepted { placeable_type, accepted } => {
+                let accepted: Vec<&str> = accepted.iter().map(|item| item.name()).collect();
+                write!(f, "{} only accepts {}!", placeable_type.item().name(), accepted.join(", "))
+            }
+            ActionError::NothingToHarvest(placeable_type) => write!(f, "Nothing to harvest from {}!", placeable_name(*placeable_type)),
+            ActionError::NothingToDrop => write!(f, "No items to drop!"),
+            ActionError::DropOutOfBounds => write!(f, "Cannot drop outside map bounds!"),
+            ActionError::DropOnWater => write!(f, "Cannot drop on water!"),
+            ActionError::DropOnEntity => write!(f, "Cannot drop on furnace or chest!"),
+            ActionError::DropOnDifferentItem => write!(f, "Cannot drop on different item!"),
+            ActionError::BeltOccupied => write!(f, "No room on belt!"),
+            ActionError::NoSplitter => write!(f, "No splitter there!"),
+            ActionError::Craft(error) => write!(f, "{}", error),
+        }
+    }
+}
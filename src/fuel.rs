@@ -0,0 +1,104 @@
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+use serde::{Serialize, Deserialize};
+use crate::types::Item;
+use crate::stats::ProductionStats;
+
+// Fuel slot of a burner machine (furnaces, drills): unburnt items of one fuel type,
+// plus the machine cycles left from the unit burning now. Any item with a fuel value fits.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct FuelStore {
+    item: Option<Item>,
+    count: u32,
+    cycles_left: u32, // Cycles the burning unit still powers
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl FuelStore {
+    #[cfg_attr(feature = "wasm", wasm_bindgen(constructor))]
+    pub fn new() -> FuelStore {
+        FuelStore::default()
+    }
+
+    // Unburnt fuel item (None when empty)
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn item(&self) -> Option<Item> {
+        self.item
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn count(&self) -> u32 {
+        self.count
+    }
+
+    // Whether another cycle can be powered
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn has_fuel(&self) -> bool {
+        self.cycles_left > 0 || self.count > 0
+    }
+}
+
+impl FuelStore {
+    // Fuel items only, and only the type already stored
+    pub fn accepts(&self, item: Item) -> bool {
+        item.is_fuel() && self.item.is_none_or(|stored| stored == item)
+    }
+
+    // Only called after accepts returned true
+    pub fn add(&mut self, item: Item) {
+        self.item = Some(item);
+        self.count += 1;
+    }
+
+    pub fn contents(&self) -> Option<(Item, u32)> {
+        self.item.filter(|_| self.count > 0).map(|item| (item, self.count))
+    }
+
+    // Fuel read from a save made when machines only held coal
+    pub(crate) fn coal(count: u32) -> FuelStore {
+        FuelStore {
+            item: (count > 0).then_some(Item::Coal),
+            count,
+            cycles_left: 0,
+        }
+    }
+
+    // Use up one cycle, burning a new unit (counted as consumed) when the last one has run out.
+    // Returns false if there was no fuel.
+    pub(crate) fn burn_cycle(&mut self, stats: &mut ProductionStats) -> bool {
+        if self.cycles_left == 0 {
+            let Some(item) = self.item.filter(|_| self.count > 0) else {
+                return false;
+            };
+            self.count -= 1;
+            if self.count == 0 {
+                self.item = None;
+            }
+            self.cycles_left = item.fuel_value();
+            stats.consumed(item, 1);
+        }
+        self.cycles_left -= 1;
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn each_fuel_unit_powers_its_fuel_value_in_cycles() {
+        let mut fuel = FuelStore::new();
+        let mut stats = ProductionStats::default();
+        assert!(!fuel.accepts(Item::IronOre));
+        assert!(fuel.accepts(Item::Coal));
+        fuel.add(Item::Coal);
+        fuel.add(Item::Coal);
+
+        let cycles = (0..10).take_while(|_| fuel.burn_cycle(&mut stats)).count() as u32;
+        assert_eq!(cycles, 2 * Item::Coal.fuel_value());
+        assert!(!fuel.has_fuel());
+        assert_eq!(fuel.contents(), None);
+    }
+}
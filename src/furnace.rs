@@ -1,188 +1,240 @@
+#[cfg(feature = "wasm")]
 use wasm_bindgen::prelude::*;
-
-// Processing type enum (internal)
-#[derive(Clone, Copy, PartialEq)]
-enum ProcessingType {
-    None,
-    IronPlate,
-    CopperPlate,
-}
-
-// Furnace inventory data
-#[wasm_bindgen]
-#[derive(Clone)]
+use serde::{Serialize, Deserialize};
+use crate::types::{Item, PlaceableType};
+use crate::entity::{Entity, MachineStatus, TickContext};
+use crate::fuel::FuelStore;
+use crate::stats::ProductionStats;
+use crate::crafting::CraftingRecipe;
+
+// Furnace inventory data. Inputs are anything a smelting recipe takes, outputs whatever those recipes yield;
+// both are kept as item counts in the order they first arrived.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct FurnaceData {
-    coal_count: u32,
-    iron_ore_count: u32,
-    iron_plate_count: u32,
-    copper_count: u32,
-    copper_plate_count: u32,
+    fuel: FuelStore,
+    inputs: Vec<(Item, u32)>,  // Items waiting to be smelted
+    outputs: Vec<(Item, u32)>, // Smelted items waiting to be taken out
     processing_ticks_remaining: u32, // Ticks remaining for current processing
-    processing_type: ProcessingType, // What we're currently processing
+    smelting: Option<Item>, // Input of the smelt in progress
+}
+
+impl Default for FurnaceData {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
-#[wasm_bindgen]
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
 impl FurnaceData {
-    #[wasm_bindgen(constructor)]
+    #[cfg_attr(feature = "wasm", wasm_bindgen(constructor))]
     pub fn new() -> FurnaceData {
         FurnaceData {
-            coal_count: 0,
-            iron_ore_count: 0,
-            iron_plate_count: 0,
-            copper_count: 0,
-            copper_plate_count: 0,
+            fuel: FuelStore::new(),
+            inputs: Vec::new(),
+            outputs: Vec::new(),
             processing_ticks_remaining: 0,
-            processing_type: ProcessingType::None,
+            smelting: None,
         }
     }
 
-    #[wasm_bindgen(getter)]
-    pub fn coal_count(&self) -> u32 {
-        self.coal_count
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn fuel(&self) -> FuelStore {
+        self.fuel.clone()
     }
 
-    #[wasm_bindgen(getter)]
-    pub fn iron_ore_count(&self) -> u32 {
-        self.iron_ore_count
+    // Input items held (for display, with input_count)
+    pub fn input_items(&self) -> Vec<Item> {
+        self.inputs.iter().map(|(item, _)| *item).collect()
     }
 
-    #[wasm_bindgen(getter)]
-    pub fn iron_plate_count(&self) -> u32 {
-        self.iron_plate_count
+    pub fn input_count(&self, item: Item) -> u32 {
+        count_of(&self.inputs, item)
     }
 
-    #[wasm_bindgen(getter)]
-    pub fn copper_count(&self) -> u32 {
-        self.copper_count
+    // Smelted items held (for display, with output_count)
+    pub fn output_items(&self) -> Vec<Item> {
+        self.outputs.iter().map(|(item, _)| *item).collect()
     }
 
-    #[wasm_bindgen(getter)]
-    pub fn copper_plate_count(&self) -> u32 {
-        self.copper_plate_count
+    pub fn output_count(&self, item: Item) -> u32 {
+        count_of(&self.outputs, item)
     }
 
-    pub fn add_coal(&mut self) {
-        self.coal_count += 1;
+    // Start processing if we have materials and not already processing
+    pub fn start_processing_if_able(&mut self) {
+        if self.processing_ticks_remaining == 0 && self.fuel.has_fuel() {
+            // Smelt the input that arrived first among those with enough for a recipe
+            let next = self.inputs.iter().find_map(|(item, count)| {
+                CraftingRecipe::smelting(*item).filter(|recipe| *count >= recipe.ingredients[0].1)
+            });
+            if let Some(recipe) = next {
+                self.processing_ticks_remaining = recipe.craft_ticks;
+                self.smelting = Some(recipe.ingredients[0].0);
+            }
+        }
     }
-
-    pub fn add_iron_ore(&mut self) {
-        self.iron_ore_count += 1;
+    
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn processing_ticks_remaining(&self) -> u32 {
+        self.processing_ticks_remaining
     }
 
-    pub fn add_copper(&mut self) {
-        self.copper_count += 1;
+    // Length of the smelt in progress (0 when idle)
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn smelt_ticks(&self) -> u32 {
+        self.smelting.and_then(CraftingRecipe::smelting).map_or(0, |recipe| recipe.craft_ticks)
     }
 
-    pub fn remove_coal(&mut self, count: u32) -> bool {
-        if self.coal_count >= count {
-            self.coal_count -= count;
-            true
+    // Smelting, or about to start the next plate; otherwise what's missing
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn status(&self) -> MachineStatus {
+        let has_input = !self.inputs.is_empty();
+        if self.processing_ticks_remaining > 0 || (has_input && self.fuel.has_fuel()) {
+            MachineStatus::Working
+        } else if has_input {
+            MachineStatus::NoFuel
         } else {
-            false
+            MachineStatus::NoInput
         }
     }
-
-    pub fn remove_iron_ore(&mut self, count: u32) -> bool {
-        if self.iron_ore_count >= count {
-            self.iron_ore_count -= count;
-            true
-        } else {
-            false
-        }
+    
+    // Try to combine coal and iron ore into iron plate (deprecated - kept for compatibility)
+    pub fn try_combine(&mut self) -> bool {
+        // This is now handled by process_tick, but keep for compatibility
+        false
     }
+}
 
-    pub fn remove_copper(&mut self, count: u32) -> bool {
-        if self.copper_count >= count {
-            self.copper_count -= count;
-            true
-        } else {
-            false
+
+impl FurnaceData {
+    // Process one tick, burning fuel for each finished smelt - returns the recipe if one completed
+    pub fn process_tick(&mut self, stats: &mut ProductionStats) -> Option<&'static CraftingRecipe> {
+        if self.processing_ticks_remaining > 0 {
+            self.processing_ticks_remaining -= 1;
+            if self.processing_ticks_remaining == 0 {
+                // Processing complete - swap the recipe's ingredients for its results
+                let recipe = self.smelting.and_then(CraftingRecipe::smelting)?;
+                let (input, count) = recipe.ingredients[0];
+                if count_of(&self.inputs, input) >= count && self.fuel.burn_cycle(stats) {
+                    remove_from(&mut self.inputs, input, count);
+                    for (result, count) in recipe.results {
+                        add_to(&mut self.outputs, *result, *count);
+                    }
+                    self.smelting = None;
+                    return Some(recipe);
+                }
+            }
         }
+        None
     }
+}
 
-    pub fn add_iron_plate(&mut self) {
-        self.iron_plate_count += 1;
-    }
+fn count_of(slots: &[(Item, u32)], item: Item) -> u32 {
+    slots.iter().find(|(held, _)| *held == item).map_or(0, |(_, count)| *count)
+}
 
-    pub fn remove_iron_plate(&mut self, count: u32) -> bool {
-        if self.iron_plate_count >= count {
-            self.iron_plate_count -= count;
-            true
-        } else {
-            false
-        }
+fn add_to(slots: &mut Vec<(Item, u32)>, item: Item, count: u32) {
+    match slots.iter_mut().find(|(held, _)| *held == item) {
+        Some((_, held)) => *held += count,
+        None => slots.push((item, count)),
     }
+}
 
-    pub fn add_copper_plate(&mut self) {
-        self.copper_plate_count += 1;
+// Entries that run out are dropped; false (and nothing removed) if there aren't enough
+fn remove_from(slots: &mut Vec<(Item, u32)>, item: Item, count: u32) -> bool {
+    let Some(index) = slots.iter().position(|(held, _)| *held == item) else {
+        return false;
+    };
+    if slots[index].1 < count {
+        return false;
     }
-
-    pub fn remove_copper_plate(&mut self, count: u32) -> bool {
-        if self.copper_plate_count >= count {
-            self.copper_plate_count -= count;
-            true
-        } else {
-            false
-        }
+    slots[index].1 -= count;
+    if slots[index].1 == 0 {
+        slots.remove(index);
     }
+    true
+}
 
-    // Start processing if we have materials and not already processing
-    pub fn start_processing_if_able(&mut self) {
-        if self.processing_ticks_remaining == 0 {
-            // Try iron plate first
-            if self.coal_count > 0 && self.iron_ore_count > 0 {
-                self.processing_ticks_remaining = 60; // 60 ticks to process
-                self.processing_type = ProcessingType::IronPlate;
+impl Entity for FurnaceData {
+    fn tick(&mut self, ctx: &mut TickContext) {
+        // Start processing if we have materials and not already processing
+        self.start_processing_if_able();
+
+        if let Some(recipe) = self.process_tick(ctx.stats) {
+            for (ingredient, count) in recipe.ingredients {
+                ctx.stats.consumed(*ingredient, *count);
             }
-            // Then try copper plate
-            else if self.coal_count > 0 && self.copper_count > 0 {
-                self.processing_ticks_remaining = 60; // 60 ticks to process
-                self.processing_type = ProcessingType::CopperPlate;
+            for (result, count) in recipe.results {
+                for _ in 0..*count {
+                    ctx.produced(PlaceableType::Furnace, *result);
+                }
             }
         }
     }
-    
-    // Process one tick - returns true if processing completed
-    pub fn process_tick(&mut self) -> bool {
-        if self.processing_ticks_remaining > 0 {
-            self.processing_ticks_remaining -= 1;
-            if self.processing_ticks_remaining == 0 {
-                // Processing complete - convert based on processing type
-                match self.processing_type {
-                    ProcessingType::IronPlate => {
-                        if self.coal_count > 0 && self.iron_ore_count > 0 {
-                            self.coal_count -= 1;
-                            self.iron_ore_count -= 1;
-                            self.iron_plate_count += 1;
-                            self.processing_type = ProcessingType::None;
-                            return true;
-                        }
-                    }
-                    ProcessingType::CopperPlate => {
-                        if self.coal_count > 0 && self.copper_count > 0 {
-                            self.coal_count -= 1;
-                            self.copper_count -= 1;
-                            self.copper_plate_count += 1;
-                            self.processing_type = ProcessingType::None;
-                            return true;
-                        }
-                    }
-                    ProcessingType::None => {}
-                }
-            }
+
+    // Fuel, or anything a smelting recipe takes
+    fn accepts_item(&self, item: Item) -> bool {
+        self.fuel.accepts(item) || CraftingRecipe::smelting(item).is_some()
+    }
+
+    fn insert_item(&mut self, item: Item) {
+        if item.is_fuel() {
+            self.fuel.add(item);
+        } else if CraftingRecipe::smelting(item).is_some() {
+            add_to(&mut self.inputs, item, 1);
         }
-        false
     }
-    
-    #[wasm_bindgen(getter)]
-    pub fn processing_ticks_remaining(&self) -> u32 {
-        self.processing_ticks_remaining
+
+    // Only smelted items come out, oldest first
+    fn take_item(&mut self) -> Option<Item> {
+        let item = self.outputs.first()?.0;
+        remove_from(&mut self.outputs, item, 1);
+        Some(item)
     }
-    
-    // Try to combine coal and iron ore into iron plate (deprecated - kept for compatibility)
-    pub fn try_combine(&mut self) -> bool {
-        // This is now handled by process_tick, but keep for compatibility
-        false
+
+    fn contents(&self) -> Vec<(Item, u32)> {
+        self.fuel
+            .contents()
+            .into_iter()
+            .chain(self.inputs.iter().copied())
+            .chain(self.outputs.iter().copied())
+            .collect()
+    }
+
+    fn status(&self) -> Option<MachineStatus> {
+        Some(FurnaceData::status(self))
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn furnaces_smelt_whatever_the_smelting_recipes_take() {
+        let mut furnace = FurnaceData::new();
+        let mut stats = ProductionStats::default();
+        assert!(furnace.accepts_item(Item::Copper) && furnace.accepts_item(Item::Coal));
+        assert!(!furnace.accepts_item(Item::Stone) && !furnace.accepts_item(Item::IronPlate));
+
+        furnace.insert_item(Item::Copper);
+        furnace.insert_item(Item::IronOre);
+        furnace.insert_item(Item::Coal);
+        furnace.insert_item(Item::Coal);
+        let mut smelted = Vec::new();
+        for _ in 0..200 {
+            furnace.start_processing_if_able();
+            if let Some(recipe) = furnace.process_tick(&mut stats) {
+                smelted.push(recipe.id);
+            }
+        }
+
+        // Inputs are smelted in the order they arrived and come out the same way
+        assert_eq!(smelted, vec!["copper_plate", "iron_plate"]);
+        assert!(furnace.input_items().is_empty());
+        assert_eq!(furnace.take_item(), Some(Item::CopperPlate));
+        assert_eq!(furnace.take_item(), Some(Item::IronPlate));
+        assert_eq!(furnace.take_item(), None);
+    }
+}
@@ -1,271 +1,165 @@
+#[cfg(feature = "wasm")]
 use wasm_bindgen::prelude::*;
-use js_sys;
-use crate::types::{Item, Direction};
+use std::collections::VecDeque;
+use serde::{Serialize, Deserialize};
+use crate::types::{Item, Direction, PlaceableType};
 use crate::player::Player;
 use crate::map::{Map, WaterPatch, Resource, PlaceableObject, IronOre, DroppedItem};
 use crate::furnace::FurnaceData;
 use crate::chest::ChestData;
 use crate::drill::DrillData;
-use crate::map_generation::generate_map;
+use crate::underground_belt::UndergroundBeltData;
+use crate::splitter::{SplitterData, SplitterSide};
+use crate::entity::{EntityId, EntitySnapshot};
+use crate::belt::{BeltItem, BeltShape, BELT_LENGTH, progress_offset};
+use crate::stats::{ProductionStats, StatsWindow, ItemStats};
+use crate::events::{GameEvent, EventRecord, PlacementError};
+use crate::map_generation::{generate_map, GenerationConfig};
+use crate::save::{self, SaveError};
+use crate::replay::Replay;
+use crate::action::{Action, InputFrame};
+use crate::keymap::{KeyState, actions_from_keys};
 use crate::handlers::{
     handle_player_movement, handle_mining, handle_placement,
     cycle_inventory_selection, handle_furnace_add_item, handle_pickup,
-    handle_furnace_tick_processing, handle_belt_tick_processing, handle_drop_item,
-    handle_belt_rotation, handle_pickup_placeable, handle_arm_tick_processing,
-    handle_drill_tick_processing, get_container_at_cursor_or_front
+    handle_belt_tick_processing, handle_drop_item, handle_belt_rotation,
+    handle_pickup_placeable, handle_entity_tick_processing, handle_placement_rotation,
+    get_target_position, handle_line_placement, handle_splitter_configuration,
+    handle_use_selected, handle_drop_selected
 };
-use crate::crafting::{handle_crafting, handle_belt_crafting, handle_arm_crafting, handle_chest_crafting, handle_drill_crafting};
+use crate::crafting::{self, CraftingRecipe, CraftError, RecipeInfo, CraftJob, handle_crafting_tick};
+
+// Lines kept in the console log
+const CONSOLE_LINES: usize = 50;
+// Undrained events kept for drain_events (the oldest are dropped first)
+const MAX_PENDING_EVENTS: usize = 10_000;
+
+// Callback for every event as it happens (Send so a GameState can move to another thread)
+pub type EventListener = Box<dyn FnMut(&EventRecord) + Send>;
+
+// Native servers and bots run the simulation on their own threads
+const _: fn() = || {
+    fn assert_send<T: Send>() {}
+    assert_send::<GameState>();
+};
+
+// What placing the selected item at the target would build, for the ghost preview
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy, Debug)]
+pub struct PlacementPreview {
+    x: u32,
+    y: u32,
+    placeable_type: PlaceableType,
+    direction: Direction,
+    error: Option<PlacementError>,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl PlacementPreview {
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn x(&self) -> u32 {
+        self.x
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn y(&self) -> u32 {
+        self.y
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn placeable_type(&self) -> PlaceableType {
+        self.placeable_type
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn direction(&self) -> Direction {
+        self.direction
+    }
+
+    // Why the placement would fail (None when the ghost should be drawn green)
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn error(&self) -> Option<PlacementError> {
+        self.error
+    }
+}
 
 // GameState struct
-#[wasm_bindgen]
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Serialize, Deserialize)]
 pub struct GameState {
     player: Player,
     map: Map,
-    console_messages: Vec<String>,
-    last_movement_time: f64,
+    console_messages: VecDeque<String>, // Rendered from events
+    config: GenerationConfig,
+    next_movement_tick: u64,
     selected_item: Option<Item>,
+    #[serde(default)]
+    placement_direction: Direction, // Direction new objects are placed facing
     current_tick: u64,
+    #[serde(skip)]
     cursor_x: Option<u32>,
+    #[serde(skip)]
     cursor_y: Option<u32>,
+    #[serde(skip)]
+    recording: Option<Replay>,
+    production: ProductionStats,
+    #[serde(skip)]
+    pending_events: VecDeque<EventRecord>,
+    #[serde(skip)]
+    event_listeners: Vec<EventListener>,
+    #[serde(skip)]
+    queued_actions: Vec<Action>, // Applied (and recorded) with the next next_step
 }
 
-#[wasm_bindgen]
-impl GameState {
-    #[wasm_bindgen(constructor)]
-    pub fn new() -> GameState {
-        let map = generate_map();
-        let player_x = map.width() / 2;
-        let player_y = map.height() / 2;
-        let mut player = Player::new(player_x, player_y);
-
-        // Add 50 of each item to starting inventory
-        for _ in 0..50 {
-            player.add_to_inventory(Item::IronOre);
-            player.add_to_inventory(Item::Copper);
-            player.add_to_inventory(Item::Stone);
-            player.add_to_inventory(Item::Coal);
-            player.add_to_inventory(Item::Furnace);
-            player.add_to_inventory(Item::IronPlate);
-            player.add_to_inventory(Item::Belt);
-            player.add_to_inventory(Item::CopperPlate);
-            player.add_to_inventory(Item::Arm);
-            player.add_to_inventory(Item::Chest);
-            player.add_to_inventory(Item::Drill);
-        }
-
-        let mut game_state = GameState {
-            player,
-            map,
-            console_messages: Vec::new(),
-            last_movement_time: 0.0,
-            selected_item: None,
-            current_tick: 0,
-            cursor_x: None,
-            cursor_y: None,
-        };
-
-        // Validate selection to pick first available item
-        game_state.validate_selection();
+impl Default for GameState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
-        game_state
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl GameState {
+    // Create a game whose world and randomness are fully determined by the seed
+    pub fn with_seed(seed: u64) -> GameState {
+        GameState::with_config(&GenerationConfig::new(seed))
     }
 
-    pub fn add_console_message(&mut self, message: String) {
-        self.console_messages.push(message);
-        // Keep only last 50 messages
-        if self.console_messages.len() > 50 {
-            self.console_messages.remove(0);
-        }
+    // Create a game from a shared seed string (numeric strings are used as-is)
+    pub fn from_seed_str(seed: &str) -> GameState {
+        GameState::with_config(&GenerationConfig::from_seed_str(seed))
     }
 
     pub fn get_console_messages(&self) -> Vec<String> {
-        self.console_messages.clone()
+        self.console_messages.iter().cloned().collect()
     }
 
-    #[wasm_bindgen]
-    pub fn next_step(&mut self, keys: &js_sys::Object, cursor_x: Option<u32>, cursor_y: Option<u32>) {
-        // Update cursor position
-        self.cursor_x = cursor_x;
-        self.cursor_y = cursor_y;
-        // Increment tick counter
-        self.current_tick += 1;
-        
-        // Handle movement (with delay)
-        handle_player_movement(self, keys);
-        
-        // Handle mining (M key)
-        let m_pressed = js_sys::Reflect::get(keys, &JsValue::from_str("m"))
-            .ok()
-            .and_then(|v| v.as_bool())
-            .unwrap_or(false);
-        
-        if m_pressed {
-            handle_mining(self);
-        }
-
-        // Handle crafting (F key for furnace)
-        let f_pressed = js_sys::Reflect::get(keys, &JsValue::from_str("f"))
-            .ok()
-            .and_then(|v| v.as_bool())
-            .unwrap_or(false);
-        
-        if f_pressed {
-            handle_crafting(self);
-        }
-
-        // Handle belt crafting (B key)
-        let b_pressed = js_sys::Reflect::get(keys, &JsValue::from_str("b"))
-            .ok()
-            .and_then(|v| v.as_bool())
-            .unwrap_or(false);
-        
-        if b_pressed {
-            handle_belt_crafting(self);
-        }
-
-        // Handle arm crafting (P key)
-        let p_pressed = js_sys::Reflect::get(keys, &JsValue::from_str("p"))
-            .ok()
-            .and_then(|v| v.as_bool())
-            .unwrap_or(false);
-        
-        if p_pressed {
-            handle_arm_crafting(self);
-        }
-
-        // Handle chest crafting (C key)
-        let c_pressed = js_sys::Reflect::get(keys, &JsValue::from_str("c"))
-            .ok()
-            .and_then(|v| v.as_bool())
-            .unwrap_or(false);
-        
-        if c_pressed {
-            handle_chest_crafting(self);
-        }
-
-        // Handle drill crafting (T key)
-        let t_pressed = js_sys::Reflect::get(keys, &JsValue::from_str("t"))
-            .ok()
-            .and_then(|v| v.as_bool())
-            .unwrap_or(false);
-        
-        if t_pressed {
-            handle_drill_crafting(self);
-        }
-
-        // Handle placement/interaction (Space key)
-        // First check if there's a container (furnace/chest/drill) to add items to,
-        // otherwise try to place the selected item
-        let space_pressed = js_sys::Reflect::get(keys, &JsValue::from_str(" "))
-            .ok()
-            .and_then(|v| v.as_bool())
-            .unwrap_or(false);
-        
-        if space_pressed {
-            // Check if there's a container at cursor or in front of player
-            if get_container_at_cursor_or_front(self).is_some() {
-                // Add item to container
-                handle_furnace_add_item(self);
-            } else {
-                // Try to place item
-                handle_placement(self);
-            }
-        }
-
-        // Handle inventory selection cycling
-        let bracket_left_pressed = js_sys::Reflect::get(keys, &JsValue::from_str("["))
-            .ok()
-            .and_then(|v| v.as_bool())
-            .unwrap_or(false);
-        
-        if bracket_left_pressed {
-            cycle_inventory_selection(self, -1);
-        }
-
-        let bracket_right_pressed = js_sys::Reflect::get(keys, &JsValue::from_str("]"))
-            .ok()
-            .and_then(|v| v.as_bool())
-            .unwrap_or(false);
-        
-        if bracket_right_pressed {
-            cycle_inventory_selection(self, 1);
-        }
-
-        // Handle pickup (h key) - picks up items, or harvests from furnace if no items
-        let h_pressed = js_sys::Reflect::get(keys, &JsValue::from_str("h"))
-            .ok()
-            .and_then(|v| v.as_bool())
-            .unwrap_or(false);
-        
-        if h_pressed {
-            handle_pickup(self);
-        }
-
-        // Handle drop (j key)
-        let j_pressed = js_sys::Reflect::get(keys, &JsValue::from_str("j"))
-            .ok()
-            .and_then(|v| v.as_bool())
-            .unwrap_or(false);
-        
-        if j_pressed {
-            handle_drop_item(self);
-        }
-
-        // Handle belt rotation (r key)
-        let r_pressed = js_sys::Reflect::get(keys, &JsValue::from_str("r"))
-            .ok()
-            .and_then(|v| v.as_bool())
-            .unwrap_or(false);
-        
-        if r_pressed {
-            handle_belt_rotation(self);
-        }
-
-        // Handle pickup placeable (Delete key)
-        let delete_pressed = js_sys::Reflect::get(keys, &JsValue::from_str("delete"))
-            .ok()
-            .and_then(|v| v.as_bool())
-            .unwrap_or(false);
-        
-        if delete_pressed {
-            handle_pickup_placeable(self);
-        }
-
-        // Process furnaces each tick
-        handle_furnace_tick_processing(self);
-        
-        // Process belts each tick
-        handle_belt_tick_processing(self);
-        
-        // Process arms each tick
-        handle_arm_tick_processing(self);
-        
-        // Process drills each tick
-        handle_drill_tick_processing(self);
-    }
-    
-    #[wasm_bindgen(getter)]
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
     pub fn current_tick(&self) -> u64 {
         self.current_tick
     }
 
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn seed(&self) -> u64 {
+        self.config.seed
+    }
+
     // Getters for JavaScript
-    #[wasm_bindgen(getter)]
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
     pub fn player_x(&self) -> u32 {
         self.player.x()
     }
 
-    #[wasm_bindgen(getter)]
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
     pub fn player_y(&self) -> u32 {
         self.player.y()
     }
 
-    #[wasm_bindgen(getter)]
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
     pub fn map_width(&self) -> u32 {
         self.map.width()
     }
 
-    #[wasm_bindgen(getter)]
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
     pub fn map_height(&self) -> u32 {
         self.map.height()
     }
@@ -296,79 +190,148 @@ impl GameState {
         self.map.dropped_items()
     }
 
-    pub fn belt_items(&self) -> Vec<DroppedItem> {
-        // Get all belt items as a vector
+    // How the belt on a tile runs, from the belts feeding into it (None if there's no belt)
+    pub fn belt_shape(&self, x: u32, y: u32) -> Option<BeltShape> {
+        self.map.belt_shape(x, y)
+    }
+
+    // Which way items move as they come onto the belt on a tile - differs from its direction on curves
+    pub fn belt_input_direction(&self, x: u32, y: u32) -> Option<Direction> {
+        let direction = self.map.belt_direction_at(x, y)?;
+        Some(self.map.belt_shape(x, y)?.input_direction(direction))
+    }
+
+    // Every item on a belt, with its offset within the tile for smooth rendering
+    pub fn belt_items(&self) -> Vec<BeltItem> {
         let mut items = Vec::new();
-        for obj in self.map.placeable_objects() {
-            if matches!(obj.placeable_type(), crate::types::PlaceableType::Belt) {
-                if let Some(item) = self.map.get_belt_item(obj.x(), obj.y()) {
-                    items.push(item);
+        for obj in self.map.placeable_objects_ref() {
+            if !obj.placeable_type().is_belt() {
+                continue;
+            }
+            // Splitters carry items on both their tiles
+            for (x, y) in self.map.object_tiles(obj) {
+                let shape = self.map.belt_shape(x, y).unwrap_or_default();
+                // Items past the end of an underground entry are in the tunnel, out of sight
+                for item in self.map.belt_items_at(x, y).iter().filter(|item| item.progress() <= BELT_LENGTH) {
+                    let offset = progress_offset(obj.direction(), shape, item.lane(), item.progress());
+                    items.push(BeltItem::new(x, y, item.item(), item.quantity(), item.lane(), offset));
                 }
             }
         }
         items
     }
 
-    #[wasm_bindgen(getter)]
-    pub fn iron_ore_count(&self) -> u32 {
-        self.player.get_iron_ore_count()
+    // Belt progress per tick (a tile is belt::BELT_LENGTH long)
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn belt_speed(&self) -> u32 {
+        self.map.belt_speed()
     }
 
-    pub fn copper_count(&self) -> u32 {
-        self.player.inventory().count_copper()
+    // Change the belt speed with the next next_step, so recordings see it
+    #[cfg_attr(feature = "wasm", wasm_bindgen(setter))]
+    pub fn set_belt_speed(&mut self, speed: u32) {
+        self.queue_action(Action::SetBeltSpeed(speed));
     }
 
-    pub fn stone_count(&self) -> u32 {
-        self.player.inventory().count_stone()
+    // How many of an item the player is carrying
+    pub fn item_count(&self, item: Item) -> u32 {
+        self.player.inventory().count(item)
     }
 
-    pub fn coal_count(&self) -> u32 {
-        self.player.inventory().count_coal()
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn inventory_capacity(&self) -> usize {
+        self.player.inventory().capacity()
     }
 
-    pub fn furnace_count(&self) -> u32 {
-        self.player.inventory().count_furnace()
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn inventory_used_slots(&self) -> usize {
+        self.player.inventory().used_slots()
     }
 
-    pub fn iron_plate_count(&self) -> u32 {
-        self.player.inventory().count_iron_plate()
+    pub fn get_furnace_data(&self, x: u32, y: u32) -> Option<FurnaceData> {
+        self.map.get_furnace_data(x, y)
     }
 
-    pub fn belt_count(&self) -> u32 {
-        self.player.inventory().count_belt()
+    pub fn get_chest_data(&self, x: u32, y: u32) -> Option<ChestData> {
+        self.map.get_chest_data(x, y)
     }
 
-    pub fn copper_plate_count(&self) -> u32 {
-        self.player.inventory().count_copper_plate()
+    pub fn get_drill_data(&self, x: u32, y: u32) -> Option<DrillData> {
+        self.map.get_drill_data(x, y)
     }
 
-    pub fn arm_count(&self) -> u32 {
-        self.player.inventory().count_arm()
+    pub fn get_underground_belt_data(&self, x: u32, y: u32) -> Option<UndergroundBeltData> {
+        self.map.get_underground_belt_data(x, y)
     }
 
-    pub fn chest_count(&self) -> u32 {
-        self.player.inventory().count_chest()
+    pub fn get_splitter_data(&self, x: u32, y: u32) -> Option<SplitterData> {
+        self.map.get_splitter_data(x, y)
     }
 
-    pub fn drill_count(&self) -> u32 {
-        self.player.inventory().count_drill()
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn placement_direction(&self) -> Direction {
+        self.placement_direction
     }
 
-    pub fn get_furnace_data(&self, x: u32, y: u32) -> Option<FurnaceData> {
-        self.map.get_furnace_data(x, y)
+    // Ghost of what placing the selected item would build at the target tile (None if it isn't placeable)
+    pub fn placement_preview(&self) -> Option<PlacementPreview> {
+        let placeable_type = self.selected_item?.placeable_type()?;
+        let (x, y) = get_target_position(self);
+        Some(PlacementPreview {
+            x,
+            y,
+            placeable_type,
+            direction: self.placement_direction,
+            error: self.map.can_place(x, y, placeable_type, self.placement_direction).err(),
+        })
     }
 
-    pub fn get_chest_data(&self, x: u32, y: u32) -> Option<ChestData> {
-        self.map.get_chest_data(x, y)
+    // Why an object can't be placed on a tile (None if it can), for placement previews
+    pub fn placement_error(&self, placeable_type: PlaceableType, x: u32, y: u32, direction: Direction) -> Option<PlacementError> {
+        self.map.can_place(x, y, placeable_type, direction).err()
     }
 
-    pub fn get_drill_data(&self, x: u32, y: u32) -> Option<DrillData> {
-        self.map.get_drill_data(x, y)
+    // How much of an item was produced, consumed, stored in and retrieved from chests over a window
+    pub fn production_stats(&self, item: Item, window: StatsWindow) -> ItemStats {
+        self.production.item_stats(item, window)
+    }
+
+    // Entity queries - snapshots of the current state, ids stay valid across saves
+    pub fn entity(&self, id: u32) -> Option<EntitySnapshot> {
+        self.map.get_entity(EntityId(id)).map(|obj| self.map.snapshot(obj))
+    }
+
+    pub fn entity_at(&self, x: u32, y: u32) -> Option<EntitySnapshot> {
+        self.map.get_placeable_object_at(x, y).map(|obj| self.map.snapshot(obj))
+    }
+
+    // Entities covering any tile inside the rectangle between two corners (inclusive), in placement order
+    pub fn entities_in_rect(&self, x0: u32, y0: u32, x1: u32, y1: u32) -> Vec<EntitySnapshot> {
+        let (min_x, max_x) = (x0.min(x1), x0.max(x1));
+        let (min_y, max_y) = (y0.min(y1), y0.max(y1));
+        self.map.placeable_objects_ref()
+            .iter()
+            .filter(|obj| {
+                self.map
+                    .object_tiles(obj)
+                    .iter()
+                    .any(|(x, y)| (min_x..=max_x).contains(x) && (min_y..=max_y).contains(y))
+            })
+            .map(|obj| self.map.snapshot(obj))
+            .collect()
+    }
+
+    pub fn entities_of_type(&self, placeable_type: PlaceableType) -> Vec<EntitySnapshot> {
+        self.map.placeable_objects_ref()
+            .iter()
+            .filter(|obj| obj.placeable_type() == placeable_type)
+            .map(|obj| self.map.snapshot(obj))
+            .collect()
     }
 
     pub fn get_selected_item(&self) -> Option<Item> {
         // Just return what's stored - validate_selection() should keep it in sync
-        self.selected_item.clone()
+        self.selected_item
     }
 
     // Validate and update selection if needed (call after inventory changes)
@@ -381,12 +344,12 @@ impl GameState {
         
         // Check if current selection is still valid
         if let Some(selected) = &self.selected_item {
-            let still_available = available.iter().any(|item| std::mem::discriminant(item) == std::mem::discriminant(selected));
+            let still_available = available.contains(selected);
             if !still_available {
                 // Selected item no longer exists, try to find a similar item or select first available
                 // Try to find the same item type first, otherwise select first available
                 if let Some(first_available) = available.first() {
-                    self.selected_item = Some(first_available.clone());
+                    self.selected_item = Some(*first_available);
                 } else {
                     self.selected_item = None;
                 }
@@ -394,7 +357,7 @@ impl GameState {
         } else {
             // No selection, select first available
             if let Some(first_available) = available.first() {
-                self.selected_item = Some(first_available.clone());
+                self.selected_item = Some(*first_available);
             }
         }
     }
@@ -404,7 +367,7 @@ impl GameState {
         if let Some(selected) = &self.selected_item {
             let available = self.player.inventory().get_available_items();
             for (index, item) in available.iter().enumerate() {
-                if std::mem::discriminant(item) == std::mem::discriminant(selected) {
+                if item == selected {
                     return index as i32;
                 }
             }
@@ -416,7 +379,7 @@ impl GameState {
         self.player.inventory().get_available_items()
     }
 
-    #[wasm_bindgen(getter)]
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
     pub fn player_direction(&self) -> Direction {
         self.player.direction()
     }
@@ -425,15 +388,267 @@ impl GameState {
         self.player.direction_value()
     }
 
-    // Keep mine() for backward compatibility, but it now uses next_step
-    #[wasm_bindgen]
-    pub fn mine(&mut self) {
-        handle_mining(self);
+    // Every recipe, with how many times the player can currently afford it
+    pub fn list_recipes(&self) -> Vec<RecipeInfo> {
+        crafting::list_recipes(self)
+    }
+
+    pub fn can_craft(&self, recipe_id: &str, count: u32) -> bool {
+        crafting::can_craft(self, recipe_id, count)
+    }
+
+    // Queued crafting jobs, the one in progress first
+    pub fn crafting_queue(&self) -> Vec<CraftJob> {
+        self.player.crafting_queue().iter().cloned().collect()
+    }
+
+    // Progress of the craft in progress from 0.0 to 1.0 (0.0 when idle)
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn crafting_progress(&self) -> f64 {
+        self.player.crafting_queue().front().map_or(0.0, CraftJob::progress)
+    }
+
+    // Build a line of the item on the next tick, so it goes through step like key input (mouse drags in the browser)
+    pub fn queue_place_line(&mut self, item: Item, from_x: u32, from_y: u32, to_x: u32, to_y: u32) {
+        self.queue_action(Action::PlaceLine { item, from_x, from_y, to_x, to_y });
+    }
+
+}
+
+// Seed used by GameState::new()
+const DEFAULT_SEED: u64 = 0x5EED;
+
+// Native entry points (the browser goes through the wasm bindings)
+impl GameState {
+    pub fn new() -> GameState {
+        GameState::with_seed(DEFAULT_SEED)
+    }
+
+    // Create a game on a map generated from the given config
+    pub fn with_config(config: &GenerationConfig) -> GameState {
+        let map = generate_map(config);
+        let player_x = map.width() / 2;
+        let player_y = map.height() / 2;
+        let mut player = Player::new(player_x, player_y);
+
+        // Add 50 of each item to starting inventory
+        for item in Item::all() {
+            player.inventory_mut().add_items(item, 50);
+        }
+
+        let mut game_state = GameState {
+            player,
+            map,
+            console_messages: VecDeque::new(),
+            config: config.clone(),
+            next_movement_tick: 0,
+            selected_item: None,
+            placement_direction: Direction::East,
+            current_tick: 0,
+            cursor_x: None,
+            cursor_y: None,
+            recording: None,
+            production: ProductionStats::default(),
+            pending_events: VecDeque::new(),
+            event_listeners: Vec::new(),
+            queued_actions: Vec::new(),
+        };
+
+        // Validate selection to pick first available item
+        game_state.validate_selection();
+
+        game_state
+    }
+
+    // Take every event since the last drain, oldest first
+    pub fn drain_events(&mut self) -> Vec<EventRecord> {
+        self.pending_events.drain(..).collect()
+    }
+
+    // Call listener with every event from now on
+    pub fn subscribe(&mut self, listener: EventListener) {
+        self.event_listeners.push(listener);
+    }
+
+    // Hand-crafting steps for count crafts of a recipe, or exactly which raw materials are short
+    pub fn plan_craft(&self, recipe_id: &str, count: u32) -> Result<Vec<(&'static CraftingRecipe, u32)>, CraftError> {
+        crafting::plan_craft(self.player.inventory(), recipe_id, count)
+    }
+
+    // Serialize the whole game to the versioned binary save format
+    pub fn save(&self) -> Vec<u8> {
+        save::save_binary(self)
+    }
+
+    pub fn load(bytes: &[u8]) -> Result<GameState, SaveError> {
+        save::load_binary(bytes)
+    }
+
+    // Human-readable variant of save()
+    pub fn save_json(&self) -> String {
+        save::save_json(self)
+    }
+
+    pub fn load_json(json: &str) -> Result<GameState, SaveError> {
+        save::load_json(json)
+    }
+
+    // Advance the simulation by one tick with the given keys held down
+    pub fn next_step(&mut self, keys: &KeyState, cursor_x: Option<u32>, cursor_y: Option<u32>) {
+        // Update cursor position
+        self.cursor_x = cursor_x;
+        self.cursor_y = cursor_y;
+        let mut actions = std::mem::take(&mut self.queued_actions);
+        actions.extend(actions_from_keys(self, keys));
+        self.step(&actions);
+    }
+
+    // Apply an action with the next next_step, before that tick's key actions
+    pub fn queue_action(&mut self, action: Action) {
+        self.queued_actions.push(action);
+    }
+
+    // Advance the simulation by one tick using a recorded input frame
+    pub fn step_frame(&mut self, frame: &InputFrame) {
+        self.cursor_x = frame.cursor_x;
+        self.cursor_y = frame.cursor_y;
+        self.step(&frame.actions);
+    }
+
+    // Start recording every tick's input into a replay (restarts any recording in progress)
+    pub fn start_recording(&mut self) {
+        // Always snapshot - even at tick 0 the state may differ from a fresh game (e.g. a loaded save)
+        self.recording = Some(Replay::new(self.config.clone(), Some(self.save())));
+    }
+
+    pub fn stop_recording(&mut self) -> Option<Replay> {
+        self.recording.take()
+    }
+
+    pub fn recording(&self) -> Option<&Replay> {
+        self.recording.as_ref()
+    }
+
+    // Advance the simulation by one tick, applying the actions in order
+    pub fn step(&mut self, actions: &[Action]) {
+        if let Some(recording) = &mut self.recording {
+            recording.push_frame(InputFrame {
+                cursor_x: self.cursor_x,
+                cursor_y: self.cursor_y,
+                actions: actions.to_vec(),
+            });
+        }
+
+        // Increment tick counter
+        self.current_tick += 1;
+        self.production.advance_to(self.current_tick);
+
+        // Handle movement (with delay)
+        let directions: Vec<Direction> = actions
+            .iter()
+            .filter_map(|action| match action {
+                Action::Move(direction) => Some(*direction),
+                _ => None,
+            })
+            .collect();
+        handle_player_movement(self, &directions);
+
+        for action in actions {
+            self.apply_action(action);
+        }
+
+        // Advance hand crafting each tick
+        handle_crafting_tick(self);
+
+        // Process belts each tick
+        handle_belt_tick_processing(self);
+
+        // Tick furnaces, arms, drills and other entities
+        handle_entity_tick_processing(self);
+    }
+
+    fn apply_action(&mut self, action: &Action) {
+        match action {
+            Action::Move(_) => {}
+            Action::Mine => handle_mining(self),
+            Action::Craft { recipe, count } => {
+                self.craft(recipe, *count);
+            }
+            Action::CancelCraft { index } => {
+                self.cancel_craft(*index);
+            }
+            Action::Place { item, x, y, direction } => handle_placement(self, *item, *x, *y, *direction),
+            Action::PlaceLine { item, from_x, from_y, to_x, to_y } => {
+                self.place_line(*item, *from_x, *from_y, *to_x, *to_y);
+            }
+            Action::Insert { item, x, y } => handle_furnace_add_item(self, *item, *x, *y),
+            Action::Rotate { x, y } => handle_belt_rotation(self, *x, *y),
+            Action::RotatePlacement => handle_placement_rotation(self),
+            Action::Pickup { x, y } => handle_pickup(self, *x, *y),
+            Action::Drop { item, x, y } => handle_drop_item(self, *item, *x, *y),
+            Action::UseSelected { x, y } => handle_use_selected(self, *x, *y),
+            Action::DropSelected { x, y } => handle_drop_selected(self, *x, *y),
+            Action::Deconstruct { x, y } => handle_pickup_placeable(self, *x, *y),
+            Action::ConfigureSplitter { x, y, input_priority, output_priority, filter } => {
+                self.configure_splitter(*x, *y, *input_priority, *output_priority, *filter);
+            }
+            Action::SelectNext => cycle_inventory_selection(self, 1),
+            Action::SelectPrevious => cycle_inventory_selection(self, -1),
+            Action::SetBeltSpeed(speed) => self.map.set_belt_speed((*speed).min(BELT_LENGTH)),
+        }
+    }
+
+    // Direct versions of player commands, applied by step (outside code sends Actions so they get recorded)
+
+    // Queue count crafts of a recipe plus the intermediate crafts it needs, returns how many were queued
+    pub(crate) fn craft(&mut self, recipe_id: &str, count: u32) -> u32 {
+        crafting::craft(self, recipe_id, count)
+    }
+
+    // Cancel the queued job at index along with the rest of its plan, refunding their materials
+    pub(crate) fn cancel_craft(&mut self, index: usize) -> bool {
+        crafting::cancel_craft(self, index)
+    }
+
+    // Build a line of the item from one tile to another (an L for diagonal drags), returns how many were built
+    pub(crate) fn place_line(&mut self, item: Item, from_x: u32, from_y: u32, to_x: u32, to_y: u32) -> u32 {
+        handle_line_placement(self, item, (from_x, from_y), (to_x, to_y))
+    }
+
+    // Set the priorities (None for no priority) and item filter of the splitter covering a tile
+    pub(crate) fn configure_splitter(
+        &mut self,
+        x: u32,
+        y: u32,
+        input_priority: Option<SplitterSide>,
+        output_priority: Option<SplitterSide>,
+        filter: Option<Item>,
+    ) {
+        handle_splitter_configuration(self, x, y, input_priority, output_priority, filter);
     }
 }
 
 // Internal access methods for handlers
 impl GameState {
+    // Record an event: stamp it, log its console text and pass it to listeners
+    pub fn emit(&mut self, event: GameEvent) {
+        if let Some(text) = event.console_text() {
+            self.console_messages.push_back(text);
+            if self.console_messages.len() > CONSOLE_LINES {
+                self.console_messages.pop_front();
+            }
+        }
+
+        let record = EventRecord { tick: self.current_tick, event };
+        for listener in self.event_listeners.iter_mut() {
+            listener(&record);
+        }
+        if self.pending_events.len() == MAX_PENDING_EVENTS {
+            self.pending_events.pop_front();
+        }
+        self.pending_events.push_back(record);
+    }
+
     pub fn player(&self) -> &Player {
         &self.player
     }
@@ -450,12 +665,25 @@ impl GameState {
         &mut self.map
     }
 
-    pub fn last_movement_time(&self) -> f64 {
-        self.last_movement_time
+    pub fn stats_mut(&mut self) -> &mut ProductionStats {
+        &mut self.production
     }
 
-    pub fn set_last_movement_time(&mut self, time: f64) {
-        self.last_movement_time = time;
+    // Both at once, for entity ticks
+    pub(crate) fn map_and_stats_mut(&mut self) -> (&mut Map, &mut ProductionStats) {
+        (&mut self.map, &mut self.production)
+    }
+
+    pub fn generation_config(&self) -> &GenerationConfig {
+        &self.config
+    }
+
+    pub fn next_movement_tick(&self) -> u64 {
+        self.next_movement_tick
+    }
+
+    pub fn set_next_movement_tick(&mut self, tick: u64) {
+        self.next_movement_tick = tick;
     }
 
     pub fn set_player_position(&mut self, x: u32, y: u32) {
@@ -467,13 +695,17 @@ impl GameState {
     }
 
     pub fn selected_item(&self) -> Option<Item> {
-        self.selected_item.clone()
+        self.selected_item
     }
 
     pub fn set_selected_item(&mut self, item: Option<Item>) {
         self.selected_item = item;
     }
 
+    pub fn set_placement_direction(&mut self, direction: Direction) {
+        self.placement_direction = direction;
+    }
+
     pub fn cursor_x(&self) -> Option<u32> {
         self.cursor_x
     }
@@ -483,3 +715,107 @@ impl GameState {
     }
 }
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splitters_are_found_and_snapshotted_on_both_halves() {
+        let mut game_state = GameState::new();
+        let (x, y) = (game_state.player_x() + 1, game_state.player_y() + 2);
+        game_state.step(&[
+            Action::Place { item: Item::Splitter, x, y, direction: Direction::North },
+            Action::Drop { item: Item::IronOre, x: x + 1, y },
+        ]);
+
+        let found = game_state.entities_in_rect(x + 1, y, x + 1, y);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].placeable_type(), PlaceableType::Splitter);
+        assert_eq!(found[0].content_items(), vec![Item::IronOre]);
+    }
+
+    // A small factory: belts fed by hand, a fuelled furnace, crafting and walking around
+    fn factory_session(game_state: &GameState) -> Vec<Vec<Action>> {
+        let (x, y) = (game_state.player_x() + 1, game_state.player_y() + 2);
+        let mut session = vec![
+            vec![Action::PlaceLine { item: Item::Belt, from_x: x, from_y: y, to_x: x + 6, to_y: y }],
+            vec![Action::Place { item: Item::Furnace, x, y: y + 2, direction: Direction::North }],
+            vec![Action::Insert { item: Item::Coal, x, y: y + 2 }, Action::Insert { item: Item::IronOre, x, y: y + 2 }],
+            vec![Action::Craft { recipe: "iron_plate".to_string(), count: 2 }],
+        ];
+        for tick in 0..300 {
+            let mut actions = Vec::new();
+            if tick % 15 == 0 {
+                actions.push(Action::Drop { item: Item::IronOre, x, y });
+            }
+            if tick % 50 == 0 {
+                actions.push(Action::Move(Direction::West));
+            }
+            session.push(actions);
+        }
+        session
+    }
+
+    #[test]
+    fn the_same_inputs_give_the_same_game() {
+        let run = || {
+            let mut game_state = GameState::with_seed(7);
+            for actions in factory_session(&game_state) {
+                game_state.step(&actions);
+            }
+            (game_state.save(), game_state.drain_events())
+        };
+        let (save, events) = run();
+        assert_eq!((save.clone(), events.clone()), run());
+        assert!(!events.is_empty());
+        assert_ne!(save, GameState::with_seed(7).save());
+    }
+
+    #[test]
+    fn entities_keep_their_ids_through_removal_and_reload() {
+        let mut game_state = GameState::new();
+        let (x, y) = (game_state.player_x() + 1, game_state.player_y() + 2);
+        game_state.step(&[
+            Action::PlaceLine { item: Item::Belt, from_x: x, from_y: y, to_x: x + 2, to_y: y },
+            Action::Place { item: Item::Chest, x, y: y + 1, direction: Direction::North },
+        ]);
+        let ids: Vec<u32> = game_state.entities_in_rect(x, y, x + 2, y + 1).iter().map(|entity| entity.id()).collect();
+        assert_eq!(ids.len(), 4);
+
+        // Removed ids are gone for good, new entities get fresh ones
+        game_state.step(&[Action::Deconstruct { x: x + 1, y }]);
+        game_state.step(&[Action::Place { item: Item::Chest, x: x + 1, y, direction: Direction::North }]);
+        assert!(game_state.entity(ids[1]).is_none());
+        let chest = game_state.entity_at(x + 1, y).unwrap();
+        assert!(!ids.contains(&chest.id()));
+
+        let loaded = GameState::load(&game_state.save()).unwrap();
+        for id in [ids[0], ids[2], ids[3], chest.id()] {
+            let (before, after) = (game_state.entity(id).unwrap(), loaded.entity(id).unwrap());
+            assert_eq!((after.x(), after.y(), after.placeable_type()), (before.x(), before.y(), before.placeable_type()));
+        }
+        assert_eq!(loaded.entities_of_type(PlaceableType::Chest).len(), 2);
+        assert_eq!(loaded.entities_of_type(PlaceableType::Belt).len(), 2);
+    }
+
+    #[test]
+    fn the_placement_direction_is_chosen_before_placing() {
+        let mut game_state = GameState::new();
+        while game_state.get_selected_item() != Some(Item::Belt) {
+            game_state.step(&[Action::SelectNext]);
+        }
+        assert_eq!(game_state.placement_direction(), Direction::East);
+        game_state.step(&[Action::RotatePlacement]);
+        assert_eq!(game_state.placement_direction(), Direction::South);
+
+        let (x, y) = (game_state.player_x() + 2, game_state.player_y() + 2);
+        game_state.step_frame(&InputFrame { cursor_x: Some(x), cursor_y: Some(y), actions: Vec::new() });
+        let preview = game_state.placement_preview().unwrap();
+        assert_eq!((preview.x(), preview.y(), preview.direction(), preview.error()), (x, y, Direction::South, None));
+
+        game_state.step(&[Action::Place { item: Item::Belt, x, y, direction: game_state.placement_direction() }]);
+        assert_eq!(game_state.entity_at(x, y).unwrap().direction(), Direction::South);
+        assert_eq!(game_state.placement_preview().unwrap().error(), Some(PlacementError::Occupied));
+    }
+}
@@ -0,0 +1,52 @@
+// Dense per-tile storage for a width x height map, for O(1) lookups by position
+#[derive(Clone, Debug, Default)]
+pub struct Grid<T> {
+    width: u32,
+    height: u32,
+    cells: Vec<T>,
+}
+
+impl<T: Clone + Default> Grid<T> {
+    pub fn new(width: u32, height: u32) -> Grid<T> {
+        Grid {
+            width,
+            height,
+            cells: vec![T::default(); width as usize * height as usize],
+        }
+    }
+}
+
+impl<T> Grid<T> {
+    fn index(&self, x: u32, y: u32) -> Option<usize> {
+        if x < self.width && y < self.height {
+            Some(y as usize * self.width as usize + x as usize)
+        } else {
+            None
+        }
+    }
+
+    // None outside the grid
+    pub fn get(&self, x: u32, y: u32) -> Option<&T> {
+        self.index(x, y).map(|index| &self.cells[index])
+    }
+
+    pub fn get_mut(&mut self, x: u32, y: u32) -> Option<&mut T> {
+        self.index(x, y).map(|index| &mut self.cells[index])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cells_are_found_by_position_and_nothing_outside() {
+        let mut grid: Grid<u32> = Grid::new(3, 2);
+        *grid.get_mut(2, 1).unwrap() = 7;
+        assert_eq!(grid.get(2, 1), Some(&7));
+        assert_eq!(grid.get(1, 2), None);
+        assert_eq!(grid.get(3, 0), None);
+        assert_eq!(grid.get(u32::MAX, u32::MAX), None);
+        assert!(Grid::<u32>::new(0, 0).get(0, 0).is_none());
+    }
+}
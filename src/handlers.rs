@@ -1,34 +1,28 @@
-use wasm_bindgen::prelude::*;
-use js_sys;
-use crate::types::{Item, Direction, PlaceableType, ResourceType};
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use crate::types::{Item, Direction, PlaceableType};
 use crate::game_state::GameState;
 use crate::map::DroppedItem;
+use crate::entity::{Entity, EntityState, TickContext};
+use crate::events::{GameEvent, ItemLocation, ActionError};
+use crate::underground_belt::UndergroundRole;
+use crate::splitter::SplitterSide;
+use crate::belt::{BeltLane, BELT_LENGTH, BELT_ITEM_SPACING, BELT_INSERT_PROGRESS};
+
+// Ticks between player steps (~33ms at 60 TPS)
+const MOVEMENT_COOLDOWN_TICKS: u64 = 2;
 
 // Handle player movement
-pub fn handle_player_movement(game_state: &mut GameState, keys: &js_sys::Object) {
-    // Check for 20ms delay between movements
-    let current_time = js_sys::Date::now();
-    if current_time - game_state.last_movement_time() < 20.0 {
+pub fn handle_player_movement(game_state: &mut GameState, directions: &[Direction]) {
+    // Wait out the movement cooldown (counted in ticks so replays stay deterministic)
+    if game_state.current_tick() < game_state.next_movement_tick() {
         return;
     }
 
-    // Check for key presses
-    let w_pressed = js_sys::Reflect::get(keys, &JsValue::from_str("w"))
-        .ok()
-        .and_then(|v| v.as_bool())
-        .unwrap_or(false);
-    let a_pressed = js_sys::Reflect::get(keys, &JsValue::from_str("a"))
-        .ok()
-        .and_then(|v| v.as_bool())
-        .unwrap_or(false);
-    let s_pressed = js_sys::Reflect::get(keys, &JsValue::from_str("s"))
-        .ok()
-        .and_then(|v| v.as_bool())
-        .unwrap_or(false);
-    let d_pressed = js_sys::Reflect::get(keys, &JsValue::from_str("d"))
-        .ok()
-        .and_then(|v| v.as_bool())
-        .unwrap_or(false);
+    // Several directions can be requested in one tick (diagonal movement)
+    let w_pressed = directions.iter().any(|d| matches!(d, Direction::North));
+    let a_pressed = directions.iter().any(|d| matches!(d, Direction::West));
+    let s_pressed = directions.iter().any(|d| matches!(d, Direction::South));
+    let d_pressed = directions.iter().any(|d| matches!(d, Direction::East));
 
     // Check if any movement key is pressed
     if !w_pressed && !a_pressed && !s_pressed && !d_pressed {
@@ -42,19 +36,19 @@ pub fn handle_player_movement(game_state: &mut GameState, keys: &js_sys::Object)
     
     // Try to move in each direction (1 tile per movement) and update direction
     if w_pressed && new_y > 0 {
-        new_y = new_y - 1;
+        new_y -= 1;
         new_direction = Direction::North;
     }
-    if s_pressed && new_y < game_state.map_height() - 1 {
-        new_y = new_y + 1;
+    if s_pressed && new_y < game_state.map_height().saturating_sub(1) {
+        new_y += 1;
         new_direction = Direction::South;
     }
     if a_pressed && new_x > 0 {
-        new_x = new_x - 1;
+        new_x -= 1;
         new_direction = Direction::West;
     }
-    if d_pressed && new_x < game_state.map_width() - 1 {
-        new_x = new_x + 1;
+    if d_pressed && new_x < game_state.map_width().saturating_sub(1) {
+        new_x += 1;
         new_direction = Direction::East;
     }
     
@@ -63,7 +57,7 @@ pub fn handle_player_movement(game_state: &mut GameState, keys: &js_sys::Object)
         && !game_state.map().would_collide_with_placeable(new_x, new_y) {
         game_state.set_player_position(new_x, new_y);
         game_state.set_player_direction(new_direction);
-        game_state.set_last_movement_time(current_time);
+        game_state.set_next_movement_tick(game_state.current_tick() + MOVEMENT_COOLDOWN_TICKS);
     }
 }
 
@@ -97,42 +91,19 @@ pub fn handle_mining(game_state: &mut GameState) {
         // Check if there's a resource at this position
         if let Some(resource_type) = game_state.map().get_resource_at(check_x, check_y) {
             // Add item to inventory based on resource type
-            let item = match resource_type {
-                ResourceType::IronOre => Item::IronOre,
-                ResourceType::Copper => Item::Copper,
-                ResourceType::Stone => Item::Stone,
-                ResourceType::Coal => Item::Coal,
-            };
+            let item = resource_type.item();
             
-            game_state.player_mut().add_to_inventory(item.clone());
+            if !game_state.player_mut().add_to_inventory(item) {
+                game_state.emit(GameEvent::ActionFailed(ActionError::InventoryFull));
+                return;
+            }
+            game_state.stats_mut().produced(item, 1);
             
             // Validate selection after inventory change
             game_state.validate_selection();
             
-            // Log to console
-            let resource_name = match resource_type {
-                ResourceType::IronOre => "Iron Ore",
-                ResourceType::Copper => "Copper",
-                ResourceType::Stone => "Stone",
-                ResourceType::Coal => "Coal",
-            };
-            
-            let count = match &item {
-                Item::IronOre => game_state.player().get_iron_ore_count(),
-                Item::Copper => game_state.player().inventory().count_copper(),
-                Item::Stone => game_state.player().inventory().count_stone(),
-                Item::Coal => game_state.player().inventory().count_coal(),
-                Item::Furnace => game_state.player().inventory().count_furnace(),
-                Item::IronPlate => game_state.player().inventory().count_iron_plate(),
-                Item::Belt => game_state.player().inventory().count_belt(),
-                Item::CopperPlate => game_state.player().inventory().count_copper_plate(),
-                Item::Arm => game_state.player().inventory().count_arm(),
-                Item::Chest => game_state.player().inventory().count_chest(),
-                Item::Drill => game_state.player().inventory().count_drill(),
-            };
-            
-            let message = format!("Mined {}! Total: {}", resource_name, count);
-            game_state.add_console_message(message);
+            let total = game_state.item_count(item);
+            game_state.emit(GameEvent::Mined { item, total });
             
             break; // Only mine one resource per action
         }
@@ -140,154 +111,99 @@ pub fn handle_mining(game_state: &mut GameState) {
 }
 
 // Handle placement
-pub fn handle_placement(game_state: &mut GameState) {
-    // Use cursor position if available, otherwise use position in front of player
-    let (place_x, place_y) = if let (Some(cx), Some(cy)) = (game_state.cursor_x(), game_state.cursor_y()) {
-        (cx, cy)
-    } else {
-        let player_x = game_state.player_x();
-        let player_y = game_state.player_y();
-        let direction = game_state.player_direction();
-        
-        // Calculate position in front of player
-        match direction {
-            Direction::North => (player_x, player_y.saturating_sub(1)),
-            Direction::South => (player_x, player_y + 1),
-            Direction::East => (player_x + 1, player_y),
-            Direction::West => (player_x.saturating_sub(1), player_y),
-        }
-    };
-    
-    // Check bounds
-    if place_x >= game_state.map_width() || place_y >= game_state.map_height() {
-        game_state.add_console_message("Cannot place outside map bounds!".to_string());
-        return;
-    }
-    
-    // Get selected item
-    let selected_item = match game_state.get_selected_item() {
-        Some(item) => item,
+pub fn handle_placement(game_state: &mut GameState, selected_item: Item, place_x: u32, place_y: u32, direction: Direction) {
+    // Check if selected item is placeable
+    let place_type = match selected_item.placeable_type() {
+        Some(place_type) => place_type,
         None => {
-            game_state.add_console_message("No item selected!".to_string());
+            game_state.emit(GameEvent::ActionFailed(ActionError::NotPlaceable(selected_item)));
             return;
         }
     };
-    
-    // Check if selected item is placeable
-    let placeable_type = match selected_item {
-        Item::Furnace => {
-            if game_state.player().inventory().count_furnace() == 0 {
-                game_state.add_console_message("No furnace in inventory!".to_string());
-                return;
-            }
-            Some(PlaceableType::Furnace)
-        }
-        Item::Belt => {
-            if game_state.player().inventory().count_belt() == 0 {
-                game_state.add_console_message("No belt in inventory!".to_string());
-                return;
-            }
-            Some(PlaceableType::Belt)
-        }
-        Item::Arm => {
-            if game_state.player().inventory().count_arm() == 0 {
-                game_state.add_console_message("No arm in inventory!".to_string());
-                return;
-            }
-            Some(PlaceableType::Arm)
-        }
-        Item::Chest => {
-            if game_state.player().inventory().count_chest() == 0 {
-                game_state.add_console_message("No chest in inventory!".to_string());
-                return;
-            }
-            Some(PlaceableType::Chest)
-        }
-        Item::Drill => {
-            if game_state.player().inventory().count_drill() == 0 {
-                game_state.add_console_message("No drill in inventory!".to_string());
-                return;
-            }
-            Some(PlaceableType::Drill)
+    if game_state.item_count(selected_item) == 0 {
+        game_state.emit(GameEvent::ActionFailed(ActionError::NotInInventory(selected_item)));
+        return;
+    }
+
+    // Place the object, then take it from the inventory
+    match game_state.map_mut().add_placeable(place_x, place_y, place_type, direction) {
+        Ok(id) => {
+            game_state.player_mut().inventory_mut().remove_items(selected_item, 1);
+            game_state.validate_selection();
+            game_state.emit(GameEvent::EntityPlaced { id, x: place_x, y: place_y, placeable_type: place_type });
         }
-        _ => {
-            game_state.add_console_message("Selected item cannot be placed!".to_string());
-            return;
+        Err(reason) => {
+            game_state.emit(GameEvent::PlacementRejected { item: selected_item, x: place_x, y: place_y, reason });
         }
+    }
+}
+
+// Tiles from one end of a line to the other: along x first, then along y (an L for diagonal drags).
+// Each tile faces the next one; the last keeps the direction of the final step.
+fn line_route(from: (u32, u32), to: (u32, u32)) -> Vec<(u32, u32, Direction)> {
+    let mut tiles = Vec::new();
+    let (mut x, mut y) = from;
+    while x != to.0 {
+        let direction = if to.0 > x { Direction::East } else { Direction::West };
+        tiles.push((x, y, direction));
+        x = if to.0 > x { x + 1 } else { x - 1 };
+    }
+    while y != to.1 {
+        let direction = if to.1 > y { Direction::South } else { Direction::North };
+        tiles.push((x, y, direction));
+        y = if to.1 > y { y + 1 } else { y - 1 };
+    }
+    let last_direction = tiles.last().map_or(Direction::East, |(_, _, direction)| *direction);
+    tiles.push((x, y, last_direction));
+    tiles
+}
+
+// Handle placing a line of objects between two tiles.
+// Belts face along the route, other objects face the pending placement direction.
+// Tiles that fail validation are skipped; only built tiles use up items. Returns how many were built.
+pub fn handle_line_placement(game_state: &mut GameState, selected_item: Item, from: (u32, u32), to: (u32, u32)) -> u32 {
+    let Some(place_type) = selected_item.placeable_type() else {
+        game_state.emit(GameEvent::ActionFailed(ActionError::NotPlaceable(selected_item)));
+        return 0;
     };
-    
-    if let Some(place_type) = placeable_type {
-        // Check if position is valid
-        if game_state.map().would_collide_with_water(place_x, place_y) {
-            game_state.add_console_message("Cannot place on water!".to_string());
-            return;
-        }
-        if game_state.map().get_placeable_at(place_x, place_y).is_some() {
-            game_state.add_console_message("Position already occupied!".to_string());
-            return;
+    // Clamp both ends to the map, so the route never runs past it however far the drag went
+    let max_x = game_state.map_width().saturating_sub(1);
+    let max_y = game_state.map_height().saturating_sub(1);
+    let (from, to) = ((from.0.min(max_x), from.1.min(max_y)), (to.0.min(max_x), to.1.min(max_y)));
+
+    // A single tile is an ordinary placement
+    let route = if from == to {
+        vec![(from.0, from.1, game_state.placement_direction())]
+    } else {
+        line_route(from, to)
+    };
+
+    let mut placed = Vec::new();
+    let mut rejected = Vec::new();
+    for (x, y, route_direction) in route {
+        if game_state.item_count(selected_item) == 0 {
+            game_state.emit(GameEvent::ActionFailed(ActionError::NotInInventory(selected_item)));
+            break;
         }
-        // Drills can be placed on resources, other items cannot
-        if !matches!(place_type, PlaceableType::Drill) {
-            if game_state.map().get_resource_at(place_x, place_y).is_some() {
-                game_state.add_console_message("Cannot place on resource!".to_string());
-                return;
+        let direction = if place_type == PlaceableType::Belt { route_direction } else { game_state.placement_direction() };
+        match game_state.map_mut().add_placeable(x, y, place_type, direction) {
+            Ok(id) => {
+                game_state.player_mut().inventory_mut().remove_items(selected_item, 1);
+                placed.push(id);
             }
+            Err(reason) => rejected.push((x, y, reason)),
         }
-        
-        // Remove item from inventory
-        let had_multiple = match place_type {
-            PlaceableType::Furnace => game_state.player().inventory().count_furnace() > 1,
-            PlaceableType::Belt => game_state.player().inventory().count_belt() > 1,
-            PlaceableType::Arm => game_state.player().inventory().count_arm() > 1,
-            PlaceableType::Chest => game_state.player().inventory().count_chest() > 1,
-            PlaceableType::Drill => game_state.player().inventory().count_drill() > 1,
-        };
-        
-        match place_type {
-            PlaceableType::Furnace => {
-                game_state.player_mut().inventory_mut().remove_items(Item::Furnace, 1);
-            }
-            PlaceableType::Belt => {
-                game_state.player_mut().inventory_mut().remove_items(Item::Belt, 1);
-            }
-            PlaceableType::Arm => {
-                game_state.player_mut().inventory_mut().remove_items(Item::Arm, 1);
-            }
-            PlaceableType::Chest => {
-                game_state.player_mut().inventory_mut().remove_items(Item::Chest, 1);
-            }
-            PlaceableType::Drill => {
-                game_state.player_mut().inventory_mut().remove_items(Item::Drill, 1);
-            }
-        }
-        
-        // If we just removed the last item, adjust selection
-        if !had_multiple {
-            // The selected item was just removed, so we need to pick a new one
-            // Just use validate_selection which will pick the first available item
-            game_state.validate_selection();
-        } else {
-            // Still have items of this type, validate selection
-            game_state.validate_selection();
-        }
-        
-        // Place the object
-        game_state.map_mut().add_placeable(place_x, place_y, place_type);
-        let item_name = match place_type {
-            PlaceableType::Furnace => "furnace",
-            PlaceableType::Belt => "belt",
-            PlaceableType::Arm => "arm",
-            PlaceableType::Chest => "chest",
-            PlaceableType::Drill => "drill",
-        };
-        game_state.add_console_message(format!("Placed {}!", item_name));
     }
+    game_state.validate_selection();
+
+    let built = placed.len() as u32;
+    game_state.emit(GameEvent::LinePlaced { item: selected_item, placed, rejected });
+    built
 }
 
-// Get furnace position at cursor or in front of player
-pub fn get_furnace_at_cursor_or_front(game_state: &GameState) -> Option<(u32, u32)> {
-    // Use cursor position if available, otherwise use position in front of player
-    let (check_x, check_y) = if let (Some(cx), Some(cy)) = (game_state.cursor_x(), game_state.cursor_y()) {
+// Get the tile the player is targeting: the cursor if available, otherwise the tile in front of the player
+pub fn get_target_position(game_state: &GameState) -> (u32, u32) {
+    if let (Some(cx), Some(cy)) = (game_state.cursor_x(), game_state.cursor_y()) {
         (cx, cy)
     } else {
         let player_x = game_state.player_x();
@@ -301,21 +217,7 @@ pub fn get_furnace_at_cursor_or_front(game_state: &GameState) -> Option<(u32, u3
             Direction::East => (player_x + 1, player_y),
             Direction::West => (player_x.saturating_sub(1), player_y),
         }
-    };
-    
-    // Check bounds
-    if check_x >= game_state.map_width() || check_y >= game_state.map_height() {
-        return None;
     }
-    
-    // Check if there's a furnace at this position
-    if let Some(placeable_type) = game_state.map().get_placeable_at(check_x, check_y) {
-        if matches!(placeable_type, PlaceableType::Furnace) {
-            return Some((check_x, check_y));
-        }
-    }
-    
-    None
 }
 
 // Cycle inventory selection
@@ -346,307 +248,140 @@ pub fn cycle_inventory_selection(game_state: &mut GameState, direction: i32) {
     };
     
     // Set new selection
-    game_state.set_selected_item(Some(available[new_index].clone()));
+    game_state.set_selected_item(Some(available[new_index]));
 }
 
-// Get container (furnace or chest) position at cursor or in front of player
-pub fn get_container_at_cursor_or_front(game_state: &GameState) -> Option<((u32, u32), PlaceableType)> {
-    // Use cursor position if available, otherwise use position in front of player
-    let (check_x, check_y) = if let (Some(cx), Some(cy)) = (game_state.cursor_x(), game_state.cursor_y()) {
-        (cx, cy)
+// Get the container (any entity that items can be put into) at the given position
+pub fn get_container_at(game_state: &GameState, check_x: u32, check_y: u32) -> Option<((u32, u32), PlaceableType)> {
+    let obj = game_state.map().get_placeable_object_at(check_x, check_y)?;
+    if obj.state().is_container() {
+        Some(((check_x, check_y), obj.placeable_type()))
     } else {
-        let player_x = game_state.player_x();
-        let player_y = game_state.player_y();
-        let direction = game_state.player_direction();
-        
-        // Calculate position in front of player
-        match direction {
-            Direction::North => (player_x, player_y.saturating_sub(1)),
-            Direction::South => (player_x, player_y + 1),
-            Direction::East => (player_x + 1, player_y),
-            Direction::West => (player_x.saturating_sub(1), player_y),
-        }
+        None
+    }
+}
+
+// Where items go when they are put into (or taken from) the entity at a position
+fn entity_location(game_state: &GameState, x: u32, y: u32) -> Option<ItemLocation> {
+    let obj = game_state.map().get_placeable_object_at(x, y)?;
+    Some(ItemLocation::Entity { id: obj.entity_id(), x, y, placeable_type: obj.placeable_type() })
+}
+
+// Handle adding an item to the container (furnace, chest, drill, ...) at the given position
+pub fn handle_furnace_add_item(game_state: &mut GameState, selected_item: Item, x: u32, y: u32) {
+    let Some(((container_x, container_y), container_type)) = get_container_at(game_state, x, y) else {
+        game_state.emit(GameEvent::ActionFailed(ActionError::NoContainer));
+        return;
     };
-    
-    // Check bounds
-    if check_x >= game_state.map_width() || check_y >= game_state.map_height() {
-        return None;
+
+    if !game_state.map().accepts_item_at(container_x, container_y, selected_item) {
+        let accepted = Item::all()
+            .filter(|item| game_state.map().accepts_item_at(container_x, container_y, *item))
+            .collect();
+        game_state.emit(GameEvent::ActionFailed(ActionError::NotAccepted { placeable_type: container_type, accepted }));
+        return;
     }
-    
-    // Check if there's a furnace, chest, or drill at this position
-    if let Some(placeable_type) = game_state.map().get_placeable_at(check_x, check_y) {
-        if matches!(placeable_type, PlaceableType::Furnace | PlaceableType::Chest | PlaceableType::Drill) {
-            return Some(((check_x, check_y), placeable_type));
-        }
+
+    if game_state.item_count(selected_item) == 0 {
+        game_state.emit(GameEvent::ActionFailed(ActionError::NotInInventory(selected_item)));
+        return;
     }
-    
-    None
-}
+    let Some(to) = entity_location(game_state, container_x, container_y) else {
+        return;
+    };
 
-// Handle adding selected item to furnace or chest
-pub fn handle_furnace_add_item(game_state: &mut GameState) {
-    if let Some(((container_x, container_y), container_type)) = get_container_at_cursor_or_front(game_state) {
-        // Get selected item
-        let selected_item = match game_state.get_selected_item() {
-            Some(item) => item,
-            None => {
-                game_state.add_console_message("No item selected!".to_string());
-                return;
-            }
-        };
-        
-        // Handle chest (accepts any item)
-        if matches!(container_type, PlaceableType::Chest) {
-            // Get item count
-            let item_count = match selected_item {
-                Item::IronOre => game_state.player().inventory().count_iron_ore(),
-                Item::Copper => game_state.player().inventory().count_copper(),
-                Item::Stone => game_state.player().inventory().count_stone(),
-                Item::Coal => game_state.player().inventory().count_coal(),
-                Item::Furnace => game_state.player().inventory().count_furnace(),
-                Item::IronPlate => game_state.player().inventory().count_iron_plate(),
-                Item::Belt => game_state.player().inventory().count_belt(),
-                Item::CopperPlate => game_state.player().inventory().count_copper_plate(),
-                Item::Arm => game_state.player().inventory().count_arm(),
-                Item::Chest => game_state.player().inventory().count_chest(),
-                Item::Drill => game_state.player().inventory().count_drill(),
-            };
-            
-            if item_count > 0 {
-                if let Some(mut chest_data) = game_state.map().get_chest_data(container_x, container_y) {
-                    let had_item = item_count > 1;
-                    game_state.player_mut().inventory_mut().remove_items(selected_item.clone(), 1);
-                    chest_data.add_item(selected_item.clone(), 1);
-                    game_state.map_mut().set_chest_data(container_x, container_y, chest_data);
-                    game_state.add_console_message(format!("Added {} to chest!", selected_item.name()));
-                    
-                    if !had_item {
-                        game_state.validate_selection();
-                    } else {
-                        game_state.validate_selection();
-                    }
-                }
-            } else {
-                game_state.add_console_message(format!("No {} in inventory!", selected_item.name()));
-            }
-            return;
-        }
-        
-        // Handle drill (only allows coal)
-        if matches!(container_type, PlaceableType::Drill) {
-            match selected_item {
-                Item::Coal => {
-                    if game_state.player().inventory().count_coal() > 0 {
-                        if let Some(mut drill_data) = game_state.map().get_drill_data(container_x, container_y) {
-                            let had_coal = game_state.player().inventory().count_coal() > 1;
-                            game_state.player_mut().inventory_mut().remove_items(Item::Coal, 1);
-                            drill_data.add_coal();
-                            game_state.map_mut().set_drill_data(container_x, container_y, drill_data);
-                            game_state.add_console_message("Added coal to drill!".to_string());
-                            
-                            if !had_coal {
-                                game_state.validate_selection();
-                            } else {
-                                game_state.validate_selection();
-                            }
-                        }
-                    } else {
-                        game_state.add_console_message("No coal in inventory!".to_string());
-                    }
-                }
-                _ => {
-                    game_state.add_console_message("Drill only accepts coal!".to_string());
-                }
-            }
-            return;
-        }
-        
-        // Handle furnace (only allows coal, iron ore, or copper)
-        // Only allow coal or iron ore
-        match selected_item {
-            Item::Coal => {
-                if game_state.player().inventory().count_coal() > 0 {
-                    if let Some(mut furnace_data) = game_state.map().get_furnace_data(container_x, container_y) {
-                        let had_coal = game_state.player().inventory().count_coal() > 1;
-                        game_state.player_mut().inventory_mut().remove_items(Item::Coal, 1);
-                        furnace_data.add_coal();
-                        game_state.map_mut().set_furnace_data(container_x, container_y, furnace_data);
-                        game_state.add_console_message("Added coal to furnace!".to_string());
-                        
-                        // If we just removed the last coal, adjust selection
-                        if !had_coal {
-                            // Coal count is now 0, need to change selection
-                            // Just use validate_selection which will pick the first available item
-                            game_state.validate_selection();
-                        } else {
-                            // Still have coal, validate selection
-                            game_state.validate_selection();
-                        }
-                    }
-                } else {
-                    game_state.add_console_message("No coal in inventory!".to_string());
-                }
-            }
-            Item::IronOre => {
-                if game_state.player().inventory().count_iron_ore() > 0 {
-                    if let Some(mut furnace_data) = game_state.map().get_furnace_data(container_x, container_y) {
-                        let had_iron = game_state.player().inventory().count_iron_ore() > 1;
-                        game_state.player_mut().inventory_mut().remove_items(Item::IronOre, 1);
-                        furnace_data.add_iron_ore();
-                        game_state.map_mut().set_furnace_data(container_x, container_y, furnace_data);
-                        game_state.add_console_message("Added iron ore to furnace!".to_string());
-                        
-                        // If we just removed the last iron ore, adjust selection
-                        if !had_iron {
-                            // Iron ore count is now 0, need to change selection
-                            // Just use validate_selection which will pick the first available item
-                            game_state.validate_selection();
-                        } else {
-                            // Still have iron ore, validate selection
-                            game_state.validate_selection();
-                        }
-                    }
-                } else {
-                    game_state.add_console_message("No iron ore in inventory!".to_string());
-                }
-            }
-            Item::Copper => {
-                if game_state.player().inventory().count_copper() > 0 {
-                    if let Some(mut furnace_data) = game_state.map().get_furnace_data(container_x, container_y) {
-                        let had_copper = game_state.player().inventory().count_copper() > 1;
-                        game_state.player_mut().inventory_mut().remove_items(Item::Copper, 1);
-                        furnace_data.add_copper();
-                        game_state.map_mut().set_furnace_data(container_x, container_y, furnace_data);
-                        game_state.add_console_message("Added copper to furnace!".to_string());
-                        
-                        // If we just removed the last copper, adjust selection
-                        if !had_copper {
-                            game_state.validate_selection();
-                        } else {
-                            game_state.validate_selection();
-                        }
-                    }
-                } else {
-                    game_state.add_console_message("No copper in inventory!".to_string());
-                }
-            }
-            _ => {
-                game_state.add_console_message("Furnace only accepts coal, iron ore, or copper!".to_string());
-            }
-        }
-    } else {
-        game_state.add_console_message("No furnace, chest, or drill in front of you!".to_string());
+    game_state.player_mut().inventory_mut().remove_items(selected_item, 1);
+    game_state.map_mut().insert_item_at(container_x, container_y, selected_item);
+    if matches!(container_type, PlaceableType::Chest) {
+        game_state.stats_mut().stored(selected_item, 1);
     }
+    game_state.emit(GameEvent::ItemMoved { item: selected_item, count: 1, from: ItemLocation::Inventory, to });
+    game_state.validate_selection();
 }
 
 // Handle picking up items from ground (or harvesting from furnace if no items)
-pub fn handle_pickup(game_state: &mut GameState) {
-    // Use cursor position if available, otherwise use position in front of player
-    let (check_x, check_y) = if let (Some(cx), Some(cy)) = (game_state.cursor_x(), game_state.cursor_y()) {
-        (cx, cy)
-    } else {
-        let player_x = game_state.player_x();
-        let player_y = game_state.player_y();
-        let direction = game_state.player_direction();
-        
-        // Calculate position in front of player
-        match direction {
-            Direction::North => (player_x, player_y.saturating_sub(1)),
-            Direction::South => (player_x, player_y + 1),
-            Direction::East => (player_x + 1, player_y),
-            Direction::West => (player_x.saturating_sub(1), player_y),
-        }
-    };
-    
+pub fn handle_pickup(game_state: &mut GameState, check_x: u32, check_y: u32) {
     // Check bounds
     if check_x >= game_state.map_width() || check_y >= game_state.map_height() {
         return;
     }
     
-    // First, check if there's an item on a belt at this position
-    if let Some(belt_item) = game_state.map().get_belt_item(check_x, check_y) {
+    // First, check if there's an item on a belt at this position (the front one is taken first)
+    if let Some(belt_item) = game_state.map().front_belt_item(check_x, check_y) {
         let item = belt_item.item();
         let quantity = belt_item.quantity();
         
-        // Add all items to inventory
-        for _ in 0..quantity {
-            game_state.player_mut().add_to_inventory(item.clone());
+        if !game_state.player().inventory().can_add(item, quantity) {
+            game_state.emit(GameEvent::ActionFailed(ActionError::InventoryFull));
+            return;
         }
         
+        // Add all items to inventory
+        game_state.player_mut().inventory_mut().add_items(item, quantity);
+        
         // Remove the belt item
-        game_state.map_mut().remove_belt_item(check_x, check_y);
+        game_state.map_mut().take_belt_item(check_x, check_y);
         
-        game_state.add_console_message(format!("Picked up {} {} from belt!", quantity, item.name()));
+        let from = ItemLocation::Belt { x: check_x, y: check_y };
+        game_state.emit(GameEvent::ItemMoved { item, count: quantity, from, to: ItemLocation::Inventory });
         game_state.validate_selection();
         return;
     }
     
     // Check if there's a dropped item at this position
     if let Some(index) = game_state.map().get_dropped_item_index_at(check_x, check_y) {
-        let dropped_item = game_state.map().dropped_items()[index].clone();
+        let dropped_item = game_state.map().dropped_items_ref()[index].clone();
         let item = dropped_item.item();
         let quantity = dropped_item.quantity();
         
-        // Add all items to inventory
-        for _ in 0..quantity {
-            game_state.player_mut().add_to_inventory(item.clone());
+        if !game_state.player().inventory().can_add(item, quantity) {
+            game_state.emit(GameEvent::ActionFailed(ActionError::InventoryFull));
+            return;
         }
         
+        // Add all items to inventory
+        game_state.player_mut().inventory_mut().add_items(item, quantity);
+        
         // Remove the dropped item
         game_state.map_mut().remove_dropped_item(index);
         
-        game_state.add_console_message(format!("Picked up {} {}!", quantity, item.name()));
+        let from = ItemLocation::Ground { x: check_x, y: check_y };
+        game_state.emit(GameEvent::ItemMoved { item, count: quantity, from, to: ItemLocation::Inventory });
         game_state.validate_selection();
         return;
     }
     
-    // If no item, try furnace harvest (backward compatibility)
-    if let Some((furnace_x, furnace_y)) = get_furnace_at_cursor_or_front(game_state) {
-        if let Some(mut furnace_data) = game_state.map().get_furnace_data(furnace_x, furnace_y) {
-            if furnace_data.iron_plate_count() > 0 {
-                // Remove iron plate from furnace
-                furnace_data.remove_iron_plate(1);
-                game_state.map_mut().set_furnace_data(furnace_x, furnace_y, furnace_data);
-                // Add iron plate to player inventory
-                game_state.player_mut().add_to_inventory(Item::IronPlate);
-                game_state.add_console_message("Harvested iron plate!".to_string());
-                // Validate selection after inventory change
-                game_state.validate_selection();
-            } else if furnace_data.copper_plate_count() > 0 {
-                // Remove copper plate from furnace
-                furnace_data.remove_copper_plate(1);
-                game_state.map_mut().set_furnace_data(furnace_x, furnace_y, furnace_data);
-                // Add copper plate to player inventory
-                game_state.player_mut().add_to_inventory(Item::CopperPlate);
-                game_state.add_console_message("Harvested copper plate!".to_string());
-                // Validate selection after inventory change
-                game_state.validate_selection();
-            } else {
-                game_state.add_console_message("No plates in furnace!".to_string());
-            }
+    // If no item, try to harvest from a container (furnace plates, chest contents)
+    if let Some(((container_x, container_y), container_type)) = get_container_at(game_state, check_x, check_y) {
+        let Some(obj) = game_state.map().get_placeable_object_at(container_x, container_y) else {
+            return;
+        };
+        // Take from a copy first so nothing is lost if it doesn't fit
+        let mut state = obj.state().clone();
+        let Some(item) = state.take_item() else {
+            game_state.emit(GameEvent::ActionFailed(ActionError::NothingToHarvest(container_type)));
+            return;
+        };
+        if !game_state.player().inventory().can_add(item, 1) {
+            game_state.emit(GameEvent::ActionFailed(ActionError::InventoryFull));
+            return;
         }
+        let Some(from) = entity_location(game_state, container_x, container_y) else {
+            return;
+        };
+        game_state.map_mut().take_item_at(container_x, container_y);
+        game_state.player_mut().add_to_inventory(item);
+        if matches!(container_type, PlaceableType::Chest) {
+            game_state.stats_mut().retrieved(item, 1);
+        }
+        game_state.emit(GameEvent::ItemMoved { item, count: 1, from, to: ItemLocation::Inventory });
+        // Validate selection after inventory change
+        game_state.validate_selection();
     }
 }
 
 // Handle belt rotation
-pub fn handle_belt_rotation(game_state: &mut GameState) {
-    // Use cursor position if available, otherwise use position in front of player
-    let (rotate_x, rotate_y) = if let (Some(cx), Some(cy)) = (game_state.cursor_x(), game_state.cursor_y()) {
-        (cx, cy)
-    } else {
-        let player_x = game_state.player_x();
-        let player_y = game_state.player_y();
-        let direction = game_state.player_direction();
-        
-        // Calculate position in front of player
-        match direction {
-            Direction::North => (player_x, player_y.saturating_sub(1)),
-            Direction::South => (player_x, player_y + 1),
-            Direction::East => (player_x + 1, player_y),
-            Direction::West => (player_x.saturating_sub(1), player_y),
-        }
-    };
-    
+pub fn handle_belt_rotation(game_state: &mut GameState, rotate_x: u32, rotate_y: u32) {
     // Check bounds
     if rotate_x >= game_state.map_width() || rotate_y >= game_state.map_height() {
         return;
@@ -654,114 +389,114 @@ pub fn handle_belt_rotation(game_state: &mut GameState) {
     
     // Check if there's a belt, arm, or drill at this position
     if let Some(obj) = game_state.map_mut().get_placeable_object_at_mut(rotate_x, rotate_y) {
-        if matches!(obj.placeable_type(), PlaceableType::Belt) || matches!(obj.placeable_type(), PlaceableType::Arm) || matches!(obj.placeable_type(), PlaceableType::Drill) {
-            // Rotate direction: North -> East -> South -> West -> North
-            let new_direction = match obj.direction() {
-                Direction::North => Direction::East,
-                Direction::East => Direction::South,
-                Direction::South => Direction::West,
-                Direction::West => Direction::North,
-            };
+        if obj.placeable_type().is_rotatable() {
+            let new_direction = obj.direction().rotated_clockwise();
             obj.set_direction(new_direction);
-            let dir_name = match new_direction {
-                Direction::North => "North",
-                Direction::South => "South",
-                Direction::East => "East",
-                Direction::West => "West",
-            };
-            let obj_name = if matches!(obj.placeable_type(), PlaceableType::Belt) {
-                "belt"
-            } else if matches!(obj.placeable_type(), PlaceableType::Arm) {
-                "arm"
-            } else {
-                "drill"
+            let event = GameEvent::EntityRotated {
+                id: obj.entity_id(),
+                x: rotate_x,
+                y: rotate_y,
+                placeable_type: obj.placeable_type(),
+                direction: new_direction,
             };
-            game_state.add_console_message(format!("Rotated {} to {}!", obj_name, dir_name));
+            game_state.emit(event);
         }
     }
 }
 
-// Handle dropping items
-pub fn handle_drop_item(game_state: &mut GameState) {
-    // Use cursor position if available, otherwise use position in front of player
-    let (drop_x, drop_y) = if let (Some(cx), Some(cy)) = (game_state.cursor_x(), game_state.cursor_y()) {
-        (cx, cy)
-    } else {
-        let player_x = game_state.player_x();
-        let player_y = game_state.player_y();
-        let direction = game_state.player_direction();
-        
-        // Calculate position in front of player
-        match direction {
-            Direction::North => (player_x, player_y.saturating_sub(1)),
-            Direction::South => (player_x, player_y + 1),
-            Direction::East => (player_x + 1, player_y),
-            Direction::West => (player_x.saturating_sub(1), player_y),
-        }
+// Handle changing a splitter's priorities and filter
+pub fn handle_splitter_configuration(
+    game_state: &mut GameState,
+    x: u32,
+    y: u32,
+    input_priority: Option<SplitterSide>,
+    output_priority: Option<SplitterSide>,
+    filter: Option<Item>,
+) {
+    let Some(id) = game_state.map().get_placeable_object_at(x, y).map(|obj| obj.entity_id()) else {
+        game_state.emit(GameEvent::ActionFailed(ActionError::NoSplitter));
+        return;
     };
-    
+    let Some(splitter) = game_state.map_mut().splitter_at_mut(x, y) else {
+        game_state.emit(GameEvent::ActionFailed(ActionError::NoSplitter));
+        return;
+    };
+    splitter.configure(input_priority, output_priority, filter);
+    game_state.emit(GameEvent::SplitterConfigured { id, input_priority, output_priority, filter });
+}
+
+// Rotate the direction new objects are placed facing
+pub fn handle_placement_rotation(game_state: &mut GameState) {
+    let direction = game_state.placement_direction().rotated_clockwise();
+    game_state.set_placement_direction(direction);
+    game_state.emit(GameEvent::PlacementRotated { direction });
+}
+
+// Handle the selected item being used at a position: into the container there, otherwise placed
+pub fn handle_use_selected(game_state: &mut GameState, x: u32, y: u32) {
+    match game_state.get_selected_item() {
+        Some(item) if get_container_at(game_state, x, y).is_some() => handle_furnace_add_item(game_state, item, x, y),
+        Some(item) => handle_placement(game_state, item, x, y, game_state.placement_direction()),
+        None => game_state.emit(GameEvent::ActionFailed(ActionError::NoItemSelected)),
+    }
+}
+
+// Handle dropping the selected item
+pub fn handle_drop_selected(game_state: &mut GameState, x: u32, y: u32) {
+    match game_state.get_selected_item() {
+        Some(item) => handle_drop_item(game_state, item, x, y),
+        None => game_state.emit(GameEvent::ActionFailed(ActionError::NoItemSelected)),
+    }
+}
+
+// Handle dropping items
+pub fn handle_drop_item(game_state: &mut GameState, selected_item: Item, drop_x: u32, drop_y: u32) {
     // Check bounds
     if drop_x >= game_state.map_width() || drop_y >= game_state.map_height() {
-        game_state.add_console_message("Cannot drop outside map bounds!".to_string());
+        game_state.emit(GameEvent::ActionFailed(ActionError::DropOutOfBounds));
         return;
     }
     
     // Check if position is valid (not on water, not on furnace)
     if game_state.map().would_collide_with_water(drop_x, drop_y) {
-        game_state.add_console_message("Cannot drop on water!".to_string());
+        game_state.emit(GameEvent::ActionFailed(ActionError::DropOnWater));
         return;
     }
     
     if let Some(placeable_type) = game_state.map().get_placeable_at(drop_x, drop_y) {
         if matches!(placeable_type, PlaceableType::Furnace | PlaceableType::Chest) {
-            game_state.add_console_message("Cannot drop on furnace or chest!".to_string());
+            game_state.emit(GameEvent::ActionFailed(ActionError::DropOnEntity));
             return;
         }
     }
     
-    // Get selected item
-    let selected_item = match game_state.get_selected_item() {
-        Some(item) => item,
-        None => {
-            game_state.add_console_message("No item selected!".to_string());
-            return;
-        }
-    };
-    
     // Get quantity of selected item in inventory
-    let available_count = match selected_item {
-        Item::IronOre => game_state.player().inventory().count_iron_ore(),
-        Item::Copper => game_state.player().inventory().count_copper(),
-        Item::Stone => game_state.player().inventory().count_stone(),
-        Item::Coal => game_state.player().inventory().count_coal(),
-        Item::Furnace => game_state.player().inventory().count_furnace(),
-        Item::IronPlate => game_state.player().inventory().count_iron_plate(),
-        Item::Belt => game_state.player().inventory().count_belt(),
-        Item::CopperPlate => game_state.player().inventory().count_copper_plate(),
-        Item::Arm => game_state.player().inventory().count_arm(),
-        Item::Chest => game_state.player().inventory().count_chest(),
-        Item::Drill => game_state.player().inventory().count_drill(),
-    };
+    let available_count = game_state.item_count(selected_item);
     
     if available_count == 0 {
-        game_state.add_console_message("No items to drop!".to_string());
+        game_state.emit(GameEvent::ActionFailed(ActionError::NothingToDrop));
         return;
     }
     
     // Check if dropping on a belt
     if let Some(placeable_type) = game_state.map().get_placeable_at(drop_x, drop_y) {
-        if matches!(placeable_type, PlaceableType::Belt) {
-            // Belts can only hold 1 stack at a time
-            if game_state.map().has_belt_item(drop_x, drop_y) {
-                game_state.add_console_message("Belt already has an item!".to_string());
+        if placeable_type.is_belt() {
+            // Items go on the first lane with room around the middle of the tile
+            let lane = BeltLane::ALL
+                .into_iter()
+                .find(|lane| game_state.map().belt_has_room(drop_x, drop_y, *lane, BELT_INSERT_PROGRESS));
+            let Some(lane) = lane else {
+                game_state.emit(GameEvent::ActionFailed(ActionError::BeltOccupied));
                 return;
-            }
+            };
             
-            // Place only 1 item on the belt (it will move in belt direction every 30 ticks)
+            // Place only 1 item on the belt
             let drop_count = 1;
-            game_state.player_mut().inventory_mut().remove_items(selected_item.clone(), drop_count);
-            game_state.map_mut().set_belt_item(drop_x, drop_y, DroppedItem::new(drop_x, drop_y, selected_item.clone(), drop_count));
-            game_state.add_console_message(format!("Placed {} {} on belt!", drop_count, selected_item.name()));
+            game_state.player_mut().inventory_mut().remove_items(selected_item, drop_count);
+            let belt_item = DroppedItem::new(drop_x, drop_y, selected_item, drop_count);
+            game_state.map_mut().insert_onto_belt(drop_x, drop_y, belt_item, lane, BELT_INSERT_PROGRESS);
+            let to = ItemLocation::Belt { x: drop_x, y: drop_y };
+            game_state.emit(GameEvent::ItemMoved { item: selected_item, count: drop_count, from: ItemLocation::Inventory, to });
             game_state.validate_selection();
             return;
         }
@@ -769,178 +504,103 @@ pub fn handle_drop_item(game_state: &mut GameState) {
     
     // Check if there's already a dropped item of the same type at this position
     if let Some(index) = game_state.map().get_dropped_item_index_at(drop_x, drop_y) {
-        let existing_item_type = game_state.map().dropped_items()[index].item();
+        let existing_item_type = game_state.map().dropped_items_ref()[index].item();
         if std::mem::discriminant(&existing_item_type) == std::mem::discriminant(&selected_item) {
             // Stack with existing item - drop only 1 item
             let drop_count = 1;
             if let Some(existing_item) = game_state.map_mut().get_dropped_item_mut(index) {
                 existing_item.add_quantity(drop_count);
             }
-            game_state.player_mut().inventory_mut().remove_items(selected_item.clone(), drop_count);
-            game_state.add_console_message(format!("Dropped {} {}!", drop_count, selected_item.name()));
+            game_state.player_mut().inventory_mut().remove_items(selected_item, drop_count);
+            let to = ItemLocation::Ground { x: drop_x, y: drop_y };
+            game_state.emit(GameEvent::ItemMoved { item: selected_item, count: drop_count, from: ItemLocation::Inventory, to });
             game_state.validate_selection();
             return;
         } else {
-            game_state.add_console_message("Cannot drop on different item!".to_string());
+            game_state.emit(GameEvent::ActionFailed(ActionError::DropOnDifferentItem));
             return;
         }
     }
     
     // Drop only 1 item
     let drop_count = 1;
-    game_state.player_mut().inventory_mut().remove_items(selected_item.clone(), drop_count);
-    game_state.map_mut().add_dropped_item(DroppedItem::new(drop_x, drop_y, selected_item.clone(), drop_count));
-    game_state.add_console_message(format!("Dropped {} {}!", drop_count, selected_item.name()));
+    game_state.player_mut().inventory_mut().remove_items(selected_item, drop_count);
+    game_state.map_mut().add_dropped_item(DroppedItem::new(drop_x, drop_y, selected_item, drop_count));
+    let to = ItemLocation::Ground { x: drop_x, y: drop_y };
+    game_state.emit(GameEvent::ItemMoved { item: selected_item, count: drop_count, from: ItemLocation::Inventory, to });
     game_state.validate_selection();
 }
 
 // Handle picking up placeable objects (delete key)
-pub fn handle_pickup_placeable(game_state: &mut GameState) {
-    // Use cursor position if available, otherwise use position in front of player
-    let (check_x, check_y) = if let (Some(cx), Some(cy)) = (game_state.cursor_x(), game_state.cursor_y()) {
-        (cx, cy)
-    } else {
-        let player_x = game_state.player_x();
-        let player_y = game_state.player_y();
-        let direction = game_state.player_direction();
-        
-        // Calculate position in front of player
-        match direction {
-            Direction::North => (player_x, player_y.saturating_sub(1)),
-            Direction::South => (player_x, player_y + 1),
-            Direction::East => (player_x + 1, player_y),
-            Direction::West => (player_x.saturating_sub(1), player_y),
-        }
-    };
-    
+pub fn handle_pickup_placeable(game_state: &mut GameState, check_x: u32, check_y: u32) {
     // Check bounds
     if check_x >= game_state.map_width() || check_y >= game_state.map_height() {
         return;
     }
     
     // Check if there's a placeable object at this position
-    if let Some(placeable_type) = game_state.map().get_placeable_at(check_x, check_y) {
-        // Collect all items from the placeable object
-        match placeable_type {
-            PlaceableType::Furnace => {
-                // Get all items from furnace
-                if let Some(furnace_data) = game_state.map().get_furnace_data(check_x, check_y) {
-                    // Add all coal
-                    for _ in 0..furnace_data.coal_count() {
-                        game_state.player_mut().add_to_inventory(Item::Coal);
-                    }
-                    // Add all iron ore
-                    for _ in 0..furnace_data.iron_ore_count() {
-                        game_state.player_mut().add_to_inventory(Item::IronOre);
-                    }
-                    // Add all iron plates
-                    for _ in 0..furnace_data.iron_plate_count() {
-                        game_state.player_mut().add_to_inventory(Item::IronPlate);
-                    }
-                    // Add all copper
-                    for _ in 0..furnace_data.copper_count() {
-                        game_state.player_mut().add_to_inventory(Item::Copper);
-                    }
-                    // Add all copper plates
-                    for _ in 0..furnace_data.copper_plate_count() {
-                        game_state.player_mut().add_to_inventory(Item::CopperPlate);
-                    }
-                }
-                // Add furnace back to inventory
-                game_state.player_mut().add_to_inventory(Item::Furnace);
-                game_state.add_console_message("Picked up furnace with all contents!".to_string());
-            }
-            PlaceableType::Belt => {
-                // Get item from belt if any
-                if let Some(belt_item) = game_state.map().get_belt_item(check_x, check_y) {
-                    let item = belt_item.item();
-                    let quantity = belt_item.quantity();
-                    for _ in 0..quantity {
-                        game_state.player_mut().add_to_inventory(item.clone());
-                    }
-                    game_state.map_mut().remove_belt_item(check_x, check_y);
-                }
-                // Add belt back to inventory
-                game_state.player_mut().add_to_inventory(Item::Belt);
-                game_state.add_console_message("Picked up belt!".to_string());
-            }
-            PlaceableType::Arm => {
-                // Arms don't store items, just add arm back to inventory
-                game_state.player_mut().add_to_inventory(Item::Arm);
-                game_state.add_console_message("Picked up arm!".to_string());
-            }
-            PlaceableType::Chest => {
-                // Get all items from chest
-                if let Some(chest_data) = game_state.map().get_chest_data(check_x, check_y) {
-                    let items = chest_data.get_all_items();
-                    for (index, item) in items.iter().enumerate() {
-                        let quantity = chest_data.get_item_quantity(index);
-                        for _ in 0..quantity {
-                            game_state.player_mut().add_to_inventory(item.clone());
-                        }
-                    }
-                }
-                // Add chest back to inventory
-                game_state.player_mut().add_to_inventory(Item::Chest);
-                game_state.add_console_message("Picked up chest with all contents!".to_string());
-            }
-            PlaceableType::Drill => {
-                // Get all coal from drill
-                if let Some(drill_data) = game_state.map().get_drill_data(check_x, check_y) {
-                    for _ in 0..drill_data.coal_count() {
-                        game_state.player_mut().add_to_inventory(Item::Coal);
-                    }
-                }
-                // Add drill back to inventory
-                game_state.player_mut().add_to_inventory(Item::Drill);
-                game_state.add_console_message("Picked up drill with all contents!".to_string());
+    if let Some(obj) = game_state.map().get_placeable_object_at(check_x, check_y) {
+        let placeable_type = obj.placeable_type();
+        let id = obj.entity_id();
+        // Collect all items from the placeable object, plus the object itself
+        let mut state = obj.state().clone();
+        let has_contents = state.is_container();
+        let mut items = state.on_removed();
+        // Belts carry their items on the map rather than in the entity (on every tile it covers)
+        for (x, y) in game_state.map().object_tiles(obj) {
+            for belt_item in game_state.map().belt_items_at(x, y) {
+                items.push((belt_item.item(), belt_item.quantity()));
             }
         }
+        items.push((placeable_type.item(), 1));
+        
+        // Leave the object alone if its contents don't fit
+        if !game_state.player().inventory().can_add_all(&items) {
+            game_state.emit(GameEvent::ActionFailed(ActionError::InventoryFull));
+            return;
+        }
+        
+        for (item, quantity) in items {
+            game_state.player_mut().inventory_mut().add_items(item, quantity);
+        }
         
-        // Remove the placeable object from the map
+        // Remove the placeable object (and anything on it) from the map
         game_state.map_mut().remove_placeable(check_x, check_y);
+        let event = GameEvent::EntityRemoved { id, x: check_x, y: check_y, placeable_type, with_contents: has_contents };
+        game_state.emit(event);
         
         // Validate selection after inventory change
         game_state.validate_selection();
     }
 }
 
-// Process furnaces each tick
-pub fn handle_furnace_tick_processing(game_state: &mut GameState) {
-    // Get list of furnace positions first to avoid borrowing issues
-    let furnace_positions: Vec<(u32, u32)> = game_state.map().placeable_objects()
-        .iter()
-        .filter(|obj| matches!(obj.placeable_type(), PlaceableType::Furnace))
-        .map(|obj| (obj.x(), obj.y()))
-        .collect();
-    
-    // Iterate through all furnaces
-    for (x, y) in furnace_positions {
-        if let Some(mut furnace_data) = game_state.map().get_furnace_data(x, y) {
-            // Store counts before processing to detect what was produced
-            let iron_before = furnace_data.iron_plate_count();
-            let copper_before = furnace_data.copper_plate_count();
-            
-            // Start processing if we have materials and not already processing
-            furnace_data.start_processing_if_able();
-            
-            // Process one tick
-            let completed = furnace_data.process_tick();
-            
-            // Check what was produced by comparing counts
-            if completed {
-                let iron_after = furnace_data.iron_plate_count();
-                let copper_after = furnace_data.copper_plate_count();
-                
-                if iron_after > iron_before {
-                    game_state.add_console_message(format!("Furnace at ({}, {}) produced an iron plate!", x, y));
-                } else if copper_after > copper_before {
-                    game_state.add_console_message(format!("Furnace at ({}, {}) produced a copper plate!", x, y));
-                }
-            }
-            
-            game_state.map_mut().set_furnace_data(x, y, furnace_data);
-        }
+// Tick every entity in placement order.
+// Each entity works on its own state while the rest of the map stays reachable through the context.
+pub fn handle_entity_tick_processing(game_state: &mut GameState) {
+    let mut events = Vec::new();
+    for index in 0..game_state.map().placeable_objects_ref().len() {
+        let obj = &mut game_state.map_mut().placeable_objects_mut()[index];
+        let (id, x, y, direction) = (obj.entity_id(), obj.x(), obj.y(), obj.direction());
+        // Move the state out while it ticks, leaving a placeholder that owns nothing.
+        // Entities only look at the tiles around them, never at their own.
+        let mut state = std::mem::replace(obj.state_mut(), EntityState::Belt);
+
+        let (map, stats) = game_state.map_and_stats_mut();
+        let mut ctx = TickContext {
+            id,
+            x,
+            y,
+            direction,
+            map,
+            stats,
+            events: &mut events,
+        };
+        state.tick(&mut ctx);
+
+        *game_state.map_mut().placeable_objects_mut()[index].state_mut() = state;
+    }
+    for event in events {
+        game_state.emit(event);
     }
 }
 
@@ -954,16 +614,324 @@ fn get_next_position_in_direction(x: u32, y: u32, direction: Direction) -> (u32,
     }
 }
 
-// Process belts each tick - move player and items in belt direction every 30 ticks
+// Where items leaving a belt tile go
+#[derive(Clone, Copy, PartialEq)]
+enum BeltExit {
+    Belt,               // Straight on, each item keeping its lane
+    SideLoad(BeltLane), // Onto the side of a crossing belt, into its near lane
+    Ground,
+    Blocked,
+}
+
+// Belts hand items on to the next belt (unless it faces them head-on), or drop them on free ground.
+// A belt that turns the corner takes items on the same lane, any other crossing belt gets them side-loaded.
+// Underground belts only take items in from behind, at their entry; splitters from behind, into either half.
+// Belts cannot directly put items in furnaces or chests - only arms can do that.
+fn belt_exit(game_state: &GameState, direction: Direction, next: Option<(u32, u32)>) -> BeltExit {
+    let Some((next_x, next_y)) = next else {
+        return BeltExit::Blocked;
+    };
+    let map = game_state.map();
+    match map.get_placeable_object_at(next_x, next_y).map(|obj| (obj.placeable_type(), obj.direction())) {
+        Some((PlaceableType::Belt, next_direction)) if next_direction == direction => BeltExit::Belt,
+        Some((PlaceableType::Belt, next_direction))
+            if map.belt_shape(next_x, next_y).is_some_and(|shape| shape.input_direction(next_direction) == direction) =>
+        {
+            BeltExit::Belt
+        }
+        Some((PlaceableType::Belt, next_direction)) => match BeltLane::near_side(next_direction, direction) {
+            Some(lane) => BeltExit::SideLoad(lane),
+            None => BeltExit::Blocked,
+        },
+        Some((PlaceableType::UndergroundBelt, next_direction))
+            if next_direction == direction
+                && map.underground_belt_at(next_x, next_y).is_some_and(|data| data.role() == UndergroundRole::Entry) =>
+        {
+            BeltExit::Belt
+        }
+        Some((PlaceableType::Splitter, next_direction)) if next_direction == direction => BeltExit::Belt,
+        Some((PlaceableType::Furnace | PlaceableType::Chest | PlaceableType::UndergroundBelt | PlaceableType::Splitter, _)) => {
+            BeltExit::Blocked
+        }
+        _ if map.would_collide_with_water(next_x, next_y) => BeltExit::Blocked,
+        _ if map.get_dropped_item_index_at(next_x, next_y).is_some() => BeltExit::Blocked,
+        _ => BeltExit::Ground,
+    }
+}
+
+// Where a belt tile sends its items: how they leave, the tile they go to and how far they travel first.
+// Underground entries send them through the tunnel to their exit, so the tunnel counts as part of the entry tile.
+fn belt_route(game_state: &GameState, x: u32, y: u32, direction: Direction) -> (BeltExit, Option<(u32, u32)>, u32) {
+    if let Some(data) = game_state.map().underground_belt_at(x, y).filter(|data| data.role() == UndergroundRole::Entry) {
+        return match data.partner() {
+            Some((exit_x, exit_y)) => {
+                let tiles = exit_x.abs_diff(x) + exit_y.abs_diff(y);
+                (BeltExit::Belt, Some((exit_x, exit_y)), tiles * BELT_LENGTH)
+            }
+            None => (BeltExit::Blocked, None, BELT_LENGTH),
+        };
+    }
+    let next = game_state.map().neighbour(x, y, direction);
+    (belt_exit(game_state, direction, next), next, BELT_LENGTH)
+}
+
+// The two tiles of the splitter placed at (x, y): its left half, then its right half
+fn splitter_halves(game_state: &GameState, x: u32, y: u32, direction: Direction) -> Option<[(u32, u32); 2]> {
+    let right = game_state.map().neighbour(x, y, direction.rotated_clockwise())?;
+    Some([(x, y), right])
+}
+
+// The belts a belt hands its items to (none if it drops them, is blocked or faces another belt head-on).
+// Splitters can feed the belts ahead of both halves.
+fn belt_downstream(game_state: &GameState, x: u32, y: u32, direction: Direction) -> Vec<(u32, u32)> {
+    let routes = match splitter_halves(game_state, x, y, direction) {
+        Some(halves) if game_state.map().get_placeable_at(x, y) == Some(PlaceableType::Splitter) => halves
+            .iter()
+            .map(|&(half_x, half_y)| {
+                let next = game_state.map().neighbour(half_x, half_y, direction);
+                (belt_exit(game_state, direction, next), next)
+            })
+            .collect(),
+        _ => {
+            let (exit, next, _) = belt_route(game_state, x, y, direction);
+            vec![(exit, next)]
+        }
+    };
+    routes
+        .into_iter()
+        .filter_map(|route| match route {
+            (BeltExit::Belt | BeltExit::SideLoad(_), next) => next,
+            (BeltExit::Ground | BeltExit::Blocked, _) => None,
+        })
+        .collect()
+}
+
+// Belts in the order their items should move: every belt right after the one it feeds,
+// so items ahead make room for the ones behind them. This only depends on the layout,
+// never on the order belts were placed in. A loop has nothing ahead of it, so it starts
+// from the belt where following the loop from its top-left-most belt first comes back round.
+// A splitter moves the items on both its halves in one step.
+fn belt_processing_order(game_state: &GameState) -> Vec<(u32, u32, Direction)> {
+    let map = game_state.map();
+    // Keyed by (y, x) so the top-left-most belt comes first; splitters by the tile they were placed on
+    let belts: BTreeMap<(u32, u32), Direction> = map.placeable_objects_ref()
+        .iter()
+        .filter(|obj| obj.placeable_type().is_belt())
+        .map(|obj| ((obj.y(), obj.x()), obj.direction()))
+        .collect();
+    let step_key = |(x, y): (u32, u32)| map.get_placeable_object_at(x, y).map_or((y, x), |obj| (obj.y(), obj.x()));
+    let mut downstream: HashMap<(u32, u32), Vec<(u32, u32)>> = HashMap::new();
+    let mut upstream: HashMap<(u32, u32), Vec<(u32, u32)>> = HashMap::new();
+    for (&(y, x), &direction) in &belts {
+        for next in belt_downstream(game_state, x, y, direction) {
+            let next = step_key(next);
+            downstream.entry((y, x)).or_default().push(next);
+            upstream.entry(next).or_default().push((y, x));
+        }
+    }
+
+    // Walk back up the belts feeding into a head, breadth first
+    fn visit_upstream(
+        head: (u32, u32),
+        upstream: &HashMap<(u32, u32), Vec<(u32, u32)>>,
+        visited: &mut HashSet<(u32, u32)>,
+        order: &mut Vec<(u32, u32)>,
+    ) {
+        let mut queue = VecDeque::from([head]);
+        visited.insert(head);
+        while let Some(tile) = queue.pop_front() {
+            order.push(tile);
+            for &from in upstream.get(&tile).into_iter().flatten() {
+                if visited.insert(from) {
+                    queue.push_back(from);
+                }
+            }
+        }
+    }
+
+    let mut order = Vec::with_capacity(belts.len());
+    let mut visited = HashSet::new();
+    // Belt lines that end somewhere first
+    for &tile in belts.keys() {
+        if !downstream.contains_key(&tile) {
+            visit_upstream(tile, &upstream, &mut visited, &mut order);
+        }
+    }
+    // Everything left is a loop or feeds into one
+    for &start in belts.keys() {
+        if visited.contains(&start) {
+            continue;
+        }
+        let mut seen = HashSet::new();
+        let mut head = start;
+        while seen.insert(head) {
+            head = downstream[&head][0];
+        }
+        visit_upstream(head, &upstream, &mut visited, &mut order);
+    }
+    order.into_iter().map(|(y, x)| (x, y, belts[&(y, x)])).collect()
+}
+
+// Move the items on one belt tile forward by the belt speed, keeping each lane spaced out,
+// and pass the ones that reach the exit edge on to the next tile.
+// Items passed on to a belt that hasn't moved yet this tick are held back by the speed,
+// so they don't move twice.
+fn move_belt_items(game_state: &mut GameState, x: u32, y: u32, direction: Direction, speed: u32, moved: &HashSet<(u32, u32)>) {
+    let mut items = game_state.map_mut().take_belt_items(x, y);
+    if items.is_empty() {
+        return;
+    }
+    let (exit, next, length) = belt_route(game_state, x, y, direction);
+
+    // How far the front item of a lane may go - past the exit edge only if it has somewhere to go
+    let front_limit = |lane: BeltLane| match (exit, next) {
+        (BeltExit::Belt, Some((next_x, next_y))) => {
+            let rear = game_state.map().belt_items_at(next_x, next_y).iter().rfind(|item| item.lane() == lane);
+            match rear {
+                // Stay clear of the rearmost item in the same lane of the next belt
+                Some(rear) => (length + rear.progress()).saturating_sub(BELT_ITEM_SPACING),
+                None => length + BELT_LENGTH,
+            }
+        }
+        (BeltExit::SideLoad(near), Some((next_x, next_y))) => {
+            if game_state.map().belt_has_room(next_x, next_y, near, BELT_INSERT_PROGRESS) {
+                length + BELT_LENGTH
+            } else {
+                length
+            }
+        }
+        (BeltExit::Ground, _) => length + BELT_LENGTH,
+        _ => length,
+    };
+    let mut limits = BeltLane::ALL.map(front_limit);
+    for item in items.iter_mut() {
+        let limit = &mut limits[item.lane() as usize];
+        let progress = (item.progress() + speed).min(*limit).max(item.progress());
+        item.set_progress(progress);
+        *limit = progress.saturating_sub(BELT_ITEM_SPACING);
+    }
+
+    // Hand on everything that crossed the exit edge (items are ordered front first)
+    if let Some(next) = next {
+        let mut index = 0;
+        while items.get(index).is_some_and(|item| item.progress() >= length) {
+            let overshoot = items[index].progress() - length;
+            if hand_on_belt_item(game_state, items[index].clone(), exit, next, overshoot, speed, moved) {
+                items.remove(index);
+            } else {
+                index += 1;
+            }
+        }
+    }
+
+    back_up_belt_items(&mut items, length);
+    game_state.map_mut().set_belt_items(x, y, items);
+}
+
+// Pass an item that crossed a belt's exit edge (by `overshoot`) on to the next tile, returns false if it can't go yet.
+// Items passed on to a belt that hasn't moved yet this tick are held back by the speed, so they don't move twice.
+fn hand_on_belt_item(
+    game_state: &mut GameState,
+    item: DroppedItem,
+    exit: BeltExit,
+    (next_x, next_y): (u32, u32),
+    overshoot: u32,
+    speed: u32,
+    moved: &HashSet<(u32, u32)>,
+) -> bool {
+    let held_back = if moved.contains(&(next_x, next_y)) { 0 } else { speed };
+    match exit {
+        BeltExit::Belt => {
+            let lane = item.lane();
+            let progress = overshoot.min(BELT_LENGTH).saturating_sub(held_back);
+            game_state.map_mut().insert_onto_belt(next_x, next_y, item, lane, progress)
+        }
+        BeltExit::SideLoad(near) => {
+            game_state.map_mut().insert_onto_belt(next_x, next_y, item, near, BELT_INSERT_PROGRESS - held_back.min(BELT_INSERT_PROGRESS))
+        }
+        // Only one stack fits on a ground tile
+        BeltExit::Ground if game_state.map().get_dropped_item_index_at(next_x, next_y).is_none() => {
+            let mut dropped = item;
+            dropped.set_position(next_x, next_y);
+            dropped.set_progress(0);
+            game_state.map_mut().add_dropped_item(dropped);
+            true
+        }
+        _ => false,
+    }
+}
+
+// Whatever couldn't leave waits at the exit edge, with the items behind it in each lane backed up
+fn back_up_belt_items(items: &mut [DroppedItem], length: u32) {
+    let mut limits = [length; 2];
+    for item in items.iter_mut() {
+        let limit = &mut limits[item.lane() as usize];
+        let progress = item.progress().min(*limit);
+        item.set_progress(progress);
+        *limit = progress.saturating_sub(BELT_ITEM_SPACING);
+    }
+}
+
+// Move the items on both halves of a splitter up to its exit edge, then send the ones waiting there
+// to an output chosen by the splitter's settings. Each output hands items on like the end of a belt.
+fn move_splitter_items(game_state: &mut GameState, x: u32, y: u32, direction: Direction, speed: u32, moved: &HashSet<(u32, u32)>) {
+    let Some(halves) = splitter_halves(game_state, x, y, direction) else {
+        return;
+    };
+    let Some(mut splitter) = game_state.map().get_splitter_data(x, y) else {
+        return;
+    };
+    let mut items = halves.map(|(half_x, half_y)| game_state.map_mut().take_belt_items(half_x, half_y));
+    for half_items in items.iter_mut() {
+        for item in half_items.iter_mut() {
+            item.set_progress(item.progress() + speed);
+        }
+        back_up_belt_items(half_items, BELT_LENGTH);
+    }
+
+    for input in splitter.input_order() {
+        let mut index = 0;
+        while items[input as usize].get(index).is_some_and(|item| item.progress() >= BELT_LENGTH) {
+            let item = items[input as usize][index].clone();
+            let sent = splitter.output_order(input, item.item()).into_iter().find(|&output| {
+                let (half_x, half_y) = halves[output as usize];
+                let next = game_state.map().neighbour(half_x, half_y, direction);
+                let exit = belt_exit(game_state, direction, next);
+                next.is_some_and(|next| hand_on_belt_item(game_state, item.clone(), exit, next, 0, speed, moved))
+            });
+            match sent {
+                Some(output) => {
+                    splitter.item_sent(input, output);
+                    items[input as usize].remove(index);
+                }
+                None => index += 1,
+            }
+        }
+    }
+
+    for ((half_x, half_y), half_items) in halves.into_iter().zip(items) {
+        game_state.map_mut().set_belt_items(half_x, half_y, half_items);
+    }
+    if let Some(data) = game_state.map_mut().splitter_at_mut(x, y) {
+        *data = splitter;
+    }
+}
+
+// Process belts each tick - carry the player along and move items forward by the belt speed
 pub fn handle_belt_tick_processing(game_state: &mut GameState) {
+    let speed = game_state.map().belt_speed();
+    if speed == 0 {
+        return;
+    }
     let player_x = game_state.player_x();
     let player_y = game_state.player_y();
     
     // Check if player is standing on a belt
     if let Some(obj) = game_state.map().get_placeable_object_at(player_x, player_y) {
         if matches!(obj.placeable_type(), PlaceableType::Belt) {
-            // Move player in belt direction every 30 ticks
-            if game_state.current_tick() % 30 == 0 {
+            // Move player in belt direction a tile at a time, as fast as the items
+            let ticks_per_tile = (BELT_LENGTH / speed).max(1) as u64;
+            if game_state.current_tick().is_multiple_of(ticks_per_tile) {
                 let belt_direction = obj.direction();
                 let (new_x, new_y) = get_next_position_in_direction(player_x, player_y, belt_direction);
                 
@@ -978,590 +946,212 @@ pub fn handle_belt_tick_processing(game_state: &mut GameState) {
         }
     }
     
-    // Move items on belts
-    if game_state.current_tick() % 30 == 0 {
-        // Process belt items (items directly on belts)
-        // Collect belt positions with their directions for proper ordering
-        let mut belt_data: Vec<(u32, u32, Direction)> = game_state.map().placeable_objects()
-            .iter()
-            .filter(|obj| matches!(obj.placeable_type(), PlaceableType::Belt))
-            .map(|obj| (obj.x(), obj.y(), obj.direction()))
-            .collect();
-        
-        // Sort belts in reverse order of their direction to ensure local processing
-        // This prevents items from moving multiple tiles in one tick
-        // For East: process right-to-left (descending x)
-        // For West: process left-to-right (ascending x)
-        // For South: process bottom-to-top (descending y)
-        // For North: process top-to-bottom (ascending y)
-        belt_data.sort_by(|a, b| {
-            match (a.2, b.2) {
-                (Direction::East, Direction::East) => b.0.cmp(&a.0), // Reverse x order
-                (Direction::West, Direction::West) => a.0.cmp(&b.0),  // Normal x order
-                (Direction::South, Direction::South) => b.1.cmp(&a.1), // Reverse y order
-                (Direction::North, Direction::North) => a.1.cmp(&b.1), // Normal y order
-                // If directions differ, group by direction first, then by position
-                _ => {
-                    let dir_order_a = match a.2 {
-                        Direction::North => 0,
-                        Direction::South => 1,
-                        Direction::East => 2,
-                        Direction::West => 3,
-                    };
-                    let dir_order_b = match b.2 {
-                        Direction::North => 0,
-                        Direction::South => 1,
-                        Direction::East => 2,
-                        Direction::West => 3,
-                    };
-                    dir_order_a.cmp(&dir_order_b)
-                        .then_with(|| match a.2 {
-                            Direction::East => b.0.cmp(&a.0),
-                            Direction::West => a.0.cmp(&b.0),
-                            Direction::South => b.1.cmp(&a.1),
-                            Direction::North => a.1.cmp(&b.1),
-                        })
-                }
-            }
-        });
-        
-        // Process each belt in the sorted order
-        for (belt_x, belt_y, belt_direction) in belt_data {
-            // Check if there's an item on this belt
-            if let Some(belt_item) = game_state.map().get_belt_item(belt_x, belt_y) {
-                let mut belt_item = belt_item.clone();
-                let (next_x, next_y) = get_next_position_in_direction(belt_x, belt_y, belt_direction);
-                
-                // Check if next position is valid and free
-                // Belts cannot directly put items in furnaces or chests - only arms can do that
-                if next_x < game_state.map_width() && next_y < game_state.map_height()
-                    && !game_state.map().would_collide_with_water(next_x, next_y)
-                    && !game_state.map().has_belt_item(next_x, next_y)
-                    && game_state.map().get_dropped_item_index_at(next_x, next_y).is_none() {
-                    
-                    // Check if next position is a furnace or chest - belts cannot move items there
-                    if let Some(next_obj) = game_state.map().get_placeable_object_at(next_x, next_y) {
-                        if matches!(next_obj.placeable_type(), PlaceableType::Furnace | PlaceableType::Chest) {
-                            // Belt cannot put items in furnaces or chests - item stays on belt (blocked)
-                            continue;
-                        }
-                    }
-                    
-                    // Check if next position would collide with a placeable (other than belt)
-                    if !game_state.map().would_collide_with_placeable(next_x, next_y) {
-                        // Check if next position is a belt
-                        if let Some(next_obj) = game_state.map().get_placeable_object_at(next_x, next_y) {
-                            if matches!(next_obj.placeable_type(), PlaceableType::Belt) {
-                                // Move to next belt
-                                belt_item.set_position(next_x, next_y);
-                                game_state.map_mut().remove_belt_item(belt_x, belt_y);
-                                game_state.map_mut().set_belt_item(next_x, next_y, belt_item);
-                            } else {
-                                // Not a belt, move to dropped items
-                                game_state.map_mut().remove_belt_item(belt_x, belt_y);
-                                game_state.map_mut().add_dropped_item(belt_item);
-                            }
-                        } else {
-                            // Empty space, move to dropped items
-                            belt_item.set_position(next_x, next_y);
-                            game_state.map_mut().remove_belt_item(belt_x, belt_y);
-                            game_state.map_mut().add_dropped_item(belt_item);
-                        }
-                    }
-                }
-                // If blocked, item stays on belt
-            }
+    // Move items on belts, front of each belt line first
+    let mut moved = HashSet::new();
+    for (belt_x, belt_y, belt_direction) in belt_processing_order(game_state) {
+        if game_state.map().get_placeable_at(belt_x, belt_y) == Some(PlaceableType::Splitter) {
+            move_splitter_items(game_state, belt_x, belt_y, belt_direction, speed, &moved);
+            moved.extend(splitter_halves(game_state, belt_x, belt_y, belt_direction).into_iter().flatten());
+        } else {
+            move_belt_items(game_state, belt_x, belt_y, belt_direction, speed, &moved);
+            moved.insert((belt_x, belt_y));
         }
+    }
+    
+    // Process dropped items that are on belts - feed entire stacks onto the belt's entry edge
+    // This handles the case where a stack is dropped on a belt position
+    let mut items_to_feed: Vec<(usize, u32, u32)> = Vec::new();
+    
+    for (index, item) in game_state.map().dropped_items_ref().iter().enumerate() {
+        let item_x = item.x();
+        let item_y = item.y();
         
-        // Process dropped items that are on belts - move entire stacks onto belts
-        // This handles the case where a stack is dropped on a belt position
-        let mut items_to_feed: Vec<(usize, u32, u32)> = Vec::new();
-        
-        for (index, item) in game_state.map().dropped_items().iter().enumerate() {
-            let item_x = item.x();
-            let item_y = item.y();
-            
-            // Check if item is on a belt (but not already a belt item)
-            if let Some(obj) = game_state.map().get_placeable_object_at(item_x, item_y) {
-                if matches!(obj.placeable_type(), PlaceableType::Belt) && !game_state.map().has_belt_item(item_x, item_y) {
-                    // Try to feed this entire stack onto the belt
-                    items_to_feed.push((index, item_x, item_y));
-                }
+        // Check if item is on a belt
+        if let Some(obj) = game_state.map().get_placeable_object_at(item_x, item_y) {
+            if obj.placeable_type().is_belt() {
+                items_to_feed.push((index, item_x, item_y));
             }
         }
-        
-        // Feed entire stacks onto belts (only if belt is empty and can move)
-        for (index, belt_x, belt_y) in items_to_feed {
-            if !game_state.map().has_belt_item(belt_x, belt_y) {
-                if let Some(belt_obj) = game_state.map().get_placeable_object_at(belt_x, belt_y) {
-                    let belt_direction = belt_obj.direction();
-                    let (next_x, next_y) = get_next_position_in_direction(belt_x, belt_y, belt_direction);
-                    
-                    // Check if belt can move item (next position must be free)
-                    if next_x < game_state.map_width() && next_y < game_state.map_height()
-                        && !game_state.map().would_collide_with_water(next_x, next_y)
-                        && !game_state.map().would_collide_with_placeable(next_x, next_y)
-                        && !game_state.map().has_belt_item(next_x, next_y)
-                        && game_state.map().get_dropped_item_index_at(next_x, next_y).is_none() {
-                        
-                        // Move entire stack onto belt
-                        if let Some(dropped_item) = game_state.map_mut().get_dropped_item_mut(index) {
-                            if dropped_item.quantity() > 0 {
-                                let item_type = dropped_item.item();
-                                let quantity = dropped_item.quantity();
-                                
-                                // Remove the entire stack from dropped items
-                                dropped_item.remove_quantity(quantity);
-                                
-                                // Place entire stack on belt
-                                game_state.map_mut().set_belt_item(belt_x, belt_y, DroppedItem::new(belt_x, belt_y, item_type, quantity));
-                                
-                                // Stack will be removed below if quantity is now 0
-                            }
-                        }
-                    }
-                }
-            }
+    }
+    
+    // Feed entire stacks onto the first lane with room at the entry edge
+    for (index, belt_x, belt_y) in items_to_feed {
+        let Some(lane) = BeltLane::ALL.into_iter().find(|lane| game_state.map().belt_has_room(belt_x, belt_y, *lane, 0)) else {
+            continue;
+        };
+        let Some(dropped_item) = game_state.map_mut().get_dropped_item_mut(index) else {
+            continue;
+        };
+        if dropped_item.quantity() == 0 {
+            continue;
         }
+        let stack = DroppedItem::new(belt_x, belt_y, dropped_item.item(), dropped_item.quantity());
         
-        // Remove items with zero quantity
-        let mut indices_to_remove: Vec<usize> = Vec::new();
-        for (index, item) in game_state.map().dropped_items().iter().enumerate() {
-            if item.quantity() == 0 {
-                indices_to_remove.push(index);
-            }
-        }
-        // Remove in reverse order to maintain indices
-        indices_to_remove.sort_by(|a, b| b.cmp(a));
-        for index in indices_to_remove {
-            game_state.map_mut().remove_dropped_item(index);
-        }
+        // Remove the entire stack from dropped items (removed below once its quantity is 0)
+        dropped_item.remove_quantity(stack.quantity());
+        game_state.map_mut().insert_onto_belt(belt_x, belt_y, stack, lane, 0);
     }
-}
-
-// Process arms each tick - move items from below to above every 120 ticks
-pub fn handle_arm_tick_processing(game_state: &mut GameState) {
     
-    // Get list of arm positions first to avoid borrowing issues
-    let arm_positions: Vec<(u32, u32)> = game_state.map().placeable_objects()
-        .iter()
-        .filter(|obj| matches!(obj.placeable_type(), PlaceableType::Arm))
-        .map(|obj| (obj.x(), obj.y()))
-        .collect();
-    
-    // Iterate through all arms
-    for (arm_x, arm_y) in arm_positions {
-        if let Some(mut arm_data) = game_state.map().get_arm_data(arm_x, arm_y) {
-            // Process one tick - returns true if it's time to act
-            let should_act = arm_data.process_tick();
-            game_state.map_mut().set_arm_data(arm_x, arm_y, arm_data);
-            
-            if should_act {
-                // Get the arm object to get its direction
-                if let Some(arm_obj) = game_state.map().get_placeable_object_at(arm_x, arm_y) {
-                    let direction = arm_obj.direction();
-                    
-                    // Calculate source position (below arm) and target position (above arm)
-                    // "Below" means opposite of direction, "above" means in direction
-                    let (source_x, source_y) = match direction {
-                        Direction::North => (arm_x, arm_y + 1), // Source is south (below)
-                        Direction::South => (arm_x, arm_y.saturating_sub(1)), // Source is north (below)
-                        Direction::East => (arm_x.saturating_sub(1), arm_y), // Source is west (below)
-                        Direction::West => (arm_x + 1, arm_y), // Source is east (below)
-                    };
-                    
-                    let (target_x, target_y) = match direction {
-                        Direction::North => (arm_x, arm_y.saturating_sub(1)), // Target is north (above)
-                        Direction::South => (arm_x, arm_y + 1), // Target is south (above)
-                        Direction::East => (arm_x + 1, arm_y), // Target is east (above)
-                        Direction::West => (arm_x.saturating_sub(1), arm_y), // Target is west (above)
-                    };
-                    
-                    // Check bounds
-                    if source_x < game_state.map_width() && source_y < game_state.map_height()
-                        && target_x < game_state.map_width() && target_y < game_state.map_height() {
-                        
-                        // First, check if target is occupied (before taking item from source)
-                        // Target is occupied if:
-                        // - It's a belt and has a belt item
-                        // - It's empty space and has a dropped item
-                        // Chest/furnace/drill can accept items, so they're not considered "occupied"
-                        let target_occupied = {
-                            if let Some(target_obj) = game_state.map().get_placeable_object_at(target_x, target_y) {
-                                if matches!(target_obj.placeable_type(), PlaceableType::Belt) {
-                                    // Belt is occupied if it has an item
-                                    game_state.map().has_belt_item(target_x, target_y)
-                                } else {
-                                    // Chest/furnace/drill can accept items, not occupied
-                                    false
-                                }
-                            } else {
-                                // Empty space is occupied if it has a dropped item
-                                game_state.map().get_dropped_item_index_at(target_x, target_y).is_some()
-                            }
-                        };
-                        
-                        // Only proceed if target is not occupied
-                        if !target_occupied {
-                            // Check if source position has an item (furnace, chest, belt, or dropped item)
-                            let mut item_to_move: Option<DroppedItem> = None;
-                            
-                            // First, check if source is a chest - try to take an item from it
-                            if let Some(placeable_type) = game_state.map().get_placeable_at(source_x, source_y) {
-                                if matches!(placeable_type, PlaceableType::Chest) {
-                                    if let Some(mut chest_data) = game_state.map().get_chest_data(source_x, source_y) {
-                                        // Get first available item from chest
-                                        let items = chest_data.get_all_items();
-                                        if let Some(item) = items.first() {
-                                            let quantity = chest_data.get_item_quantity(0);
-                                            if quantity > 0 {
-                                                let item_to_take = item.clone();
-                                                chest_data.remove_item(item_to_take.clone(), 1);
-                                                game_state.map_mut().set_chest_data(source_x, source_y, chest_data);
-                                                item_to_move = Some(DroppedItem::new(source_x, source_y, item_to_take, 1));
-                                            }
-                                        }
-                                    }
-                                }
-                                // Then check if source is a furnace - try to harvest plates from it
-                                else if matches!(placeable_type, PlaceableType::Furnace) {
-                                    if let Some(mut furnace_data) = game_state.map().get_furnace_data(source_x, source_y) {
-                                        // Try to harvest iron plate first, then copper plate
-                                        if furnace_data.iron_plate_count() > 0 {
-                                            furnace_data.remove_iron_plate(1);
-                                            game_state.map_mut().set_furnace_data(source_x, source_y, furnace_data);
-                                            item_to_move = Some(DroppedItem::new(source_x, source_y, Item::IronPlate, 1));
-                                        } else if furnace_data.copper_plate_count() > 0 {
-                                            furnace_data.remove_copper_plate(1);
-                                            game_state.map_mut().set_furnace_data(source_x, source_y, furnace_data);
-                                            item_to_move = Some(DroppedItem::new(source_x, source_y, Item::CopperPlate, 1));
-                                        }
-                                    }
-                                }
-                            }
-                            
-                            // If no item from furnace, check for belt items or dropped items
-                            if item_to_move.is_none() {
-                                // Check if there's a belt item at source
-                                if let Some(belt_item) = game_state.map().get_belt_item(source_x, source_y) {
-                                    item_to_move = Some(belt_item.clone());
-                                    game_state.map_mut().remove_belt_item(source_x, source_y);
-                                }
-                                // Then check if there's a dropped item at source
-                                else if let Some(index) = game_state.map().get_dropped_item_index_at(source_x, source_y) {
-                                    item_to_move = Some(game_state.map().dropped_items()[index].clone());
-                                    game_state.map_mut().remove_dropped_item(index);
-                                }
-                            }
-                            
-                            // If we found an item, try to place it at target
-                            if let Some(mut item) = item_to_move {
-                            let item_type = item.item();
-                            
-                            // Check if target is a chest - try to place item into it
-                            if let Some(placeable_type) = game_state.map().get_placeable_at(target_x, target_y) {
-                                if matches!(placeable_type, PlaceableType::Chest) {
-                                    // Chest accepts any item
-                                    if let Some(mut chest_data) = game_state.map().get_chest_data(target_x, target_y) {
-                                        let quantity = item.quantity();
-                                        if quantity > 0 {
-                                            // Place 1 item at a time into chest
-                                            chest_data.add_item(item_type.clone(), 1);
-                                            game_state.map_mut().set_chest_data(target_x, target_y, chest_data);
-                                            
-                                            // If there are more items, put them back at source
-                                            if quantity > 1 {
-                                                item.remove_quantity(1);
-                                                item.set_position(source_x, source_y);
-                                                game_state.map_mut().add_dropped_item(item);
-                                            }
-                                            // Item successfully placed in chest, move to next arm
-                                            continue;
-                                        }
-                                    }
-                                }
-                                // Check if target is a furnace - try to place item into it
-                                else if matches!(placeable_type, PlaceableType::Furnace) {
-                                    // Check if item can be placed in furnace (coal, iron ore, or copper)
-                                    match item_type {
-                                        Item::Coal | Item::IronOre | Item::Copper => {
-                                            if let Some(mut furnace_data) = game_state.map().get_furnace_data(target_x, target_y) {
-                                                // Only place 1 item at a time into furnace
-                                                let quantity = item.quantity();
-                                                if quantity > 0 {
-                                                    match item_type {
-                                                        Item::Coal => {
-                                                            furnace_data.add_coal();
-                                                            game_state.map_mut().set_furnace_data(target_x, target_y, furnace_data);
-                                                        }
-                                                        Item::IronOre => {
-                                                            furnace_data.add_iron_ore();
-                                                            game_state.map_mut().set_furnace_data(target_x, target_y, furnace_data);
-                                                        }
-                                                        Item::Copper => {
-                                                            furnace_data.add_copper();
-                                                            game_state.map_mut().set_furnace_data(target_x, target_y, furnace_data);
-                                                        }
-                                                        _ => {}
-                                                    }
-                                                    
-                                                    // If there are more items, put them back at source
-                                                    if quantity > 1 {
-                                                        item.remove_quantity(1);
-                                                        item.set_position(source_x, source_y);
-                                                        game_state.map_mut().add_dropped_item(item);
-                                                    }
-                                                    // Item successfully placed in furnace, move to next arm
-                                                    continue;
-                                                }
-                                            }
-                                        }
-                                        _ => {
-                                            // Item cannot be placed in furnace, fall through to normal placement
-                                        }
-                                    }
-                                }
-                                // Check if target is a drill - try to place coal into it
-                                else if matches!(placeable_type, PlaceableType::Drill) {
-                                    // Drill only accepts coal
-                                    if matches!(item_type, Item::Coal) {
-                                        if let Some(mut drill_data) = game_state.map().get_drill_data(target_x, target_y) {
-                                            // Only place 1 item at a time into drill
-                                            let quantity = item.quantity();
-                                            if quantity > 0 {
-                                                drill_data.add_coal();
-                                                game_state.map_mut().set_drill_data(target_x, target_y, drill_data);
-                                                
-                                                // If there are more items, put them back at source
-                                                if quantity > 1 {
-                                                    item.remove_quantity(1);
-                                                    item.set_position(source_x, source_y);
-                                                    game_state.map_mut().add_dropped_item(item);
-                                                }
-                                                // Item successfully placed in drill, move to next arm
-                                                continue;
-                                            }
-                                        }
-                                    }
-                                }
-                            }
-                            
-                            // If target is not a furnace, or item cannot be placed in furnace, use normal placement logic
-                            // Check if target position is valid
-                            if !game_state.map().would_collide_with_water(target_x, target_y) {
-                                // Check if target is a belt
-                                if let Some(target_obj) = game_state.map().get_placeable_object_at(target_x, target_y) {
-                                    if matches!(target_obj.placeable_type(), PlaceableType::Belt) {
-                                        // Place on belt if belt is empty
-                                        if !game_state.map().has_belt_item(target_x, target_y) {
-                                            item.set_position(target_x, target_y);
-                                            game_state.map_mut().set_belt_item(target_x, target_y, item);
-                                        } else {
-                                            // Belt is full, put item back at source
-                                            item.set_position(source_x, source_y);
-                                            game_state.map_mut().add_dropped_item(item);
-                                        }
-                                    } else {
-                                        // Not a belt, place as dropped item
-                                        item.set_position(target_x, target_y);
-                                        game_state.map_mut().add_dropped_item(item);
-                                    }
-                                } else {
-                                    // Empty space, place as dropped item
-                                    item.set_position(target_x, target_y);
-                                    game_state.map_mut().add_dropped_item(item);
-                                }
-                            } else {
-                                // Target is water, put item back at source
-                                item.set_position(source_x, source_y);
-                                game_state.map_mut().add_dropped_item(item);
-                            }
-                        }
-                        }
-                    }
-                }
-            }
+    // Remove items with zero quantity
+    let mut indices_to_remove: Vec<usize> = Vec::new();
+    for (index, item) in game_state.map().dropped_items_ref().iter().enumerate() {
+        if item.quantity() == 0 {
+            indices_to_remove.push(index);
         }
     }
+    // Remove in reverse order to maintain indices
+    indices_to_remove.sort_by(|a, b| b.cmp(a));
+    for index in indices_to_remove {
+        game_state.map_mut().remove_dropped_item(index);
+    }
 }
 
-// Process drills each tick - produces items every 60 ticks if placed over resources
-pub fn handle_drill_tick_processing(game_state: &mut GameState) {
-    // Get list of drill positions first to avoid borrowing issues
-    let drill_positions: Vec<(u32, u32)> = game_state.map().placeable_objects()
-        .iter()
-        .filter(|obj| matches!(obj.placeable_type(), PlaceableType::Drill))
-        .map(|obj| (obj.x(), obj.y()))
-        .collect();
-    
-    // Iterate through all drills
-    for (drill_x, drill_y) in drill_positions {
-        if let Some(mut drill_data) = game_state.map().get_drill_data(drill_x, drill_y) {
-            // Only start processing if not already processing
-            if drill_data.processing_ticks_remaining() == 0 {
-                // Check if target position is free before starting a new cycle
-                if let Some(drill_obj) = game_state.map().get_placeable_object_at(drill_x, drill_y) {
-                    let direction = drill_obj.direction();
-                    
-                    // Calculate target position (below drill in its direction)
-                    let (target_x, target_y) = match direction {
-                        Direction::North => (drill_x, drill_y.saturating_sub(1)),
-                        Direction::South => (drill_x, drill_y + 1),
-                        Direction::East => (drill_x + 1, drill_y),
-                        Direction::West => (drill_x.saturating_sub(1), drill_y),
-                    };
-                    
-                    // Determine what item will be produced (based on resource being mined)
-                    let item_to_produce = game_state.map().get_resource_at(drill_x, drill_y)
-                        .and_then(|resource_type| match resource_type {
-                            ResourceType::IronOre => Some(Item::IronOre),
-                            ResourceType::Coal => Some(Item::Coal),
-                            ResourceType::Copper => Some(Item::Copper),
-                            ResourceType::Stone => None,
-                        });
-                    
-                    // Check if target position can accept the item
-                    let target_can_accept = if target_x < game_state.map_width() 
-                        && target_y < game_state.map_height()
-                        && !game_state.map().would_collide_with_water(target_x, target_y) {
-                        
-                        if let Some(placeable_type) = game_state.map().get_placeable_at(target_x, target_y) {
-                            // Check if target placeable can accept the item
-                            if let Some(item) = item_to_produce {
-                                match placeable_type {
-                                    PlaceableType::Chest => {
-                                        // Chests accept any item
-                                        true
-                                    }
-                                    PlaceableType::Furnace => {
-                                        // Furnaces accept coal, iron ore, or copper
-                                        matches!(item, Item::Coal | Item::IronOre | Item::Copper)
-                                    }
-                                    PlaceableType::Drill => {
-                                        // Drills accept coal
-                                        matches!(item, Item::Coal)
-                                    }
-                                    _ => false,
-                                }
-                            } else {
-                                false
-                            }
-                        } else {
-                            // No placeable, check if space is free (no belt item, no dropped item)
-                            !game_state.map().has_belt_item(target_x, target_y)
-                                && game_state.map().get_dropped_item_index_at(target_x, target_y).is_none()
-                        }
-                    } else {
-                        false
-                    };
-                    
-                    // Only start processing if target can accept the item and we have coal
-                    if target_can_accept {
-                        drill_data.start_processing_if_able();
-                    }
-                }
-            }
-            
-            // Process one tick
-            let completed = drill_data.process_tick();
-            game_state.map_mut().set_drill_data(drill_x, drill_y, drill_data);
-            
-            if completed {
-                // Get the drill object to get its direction
-                if let Some(drill_obj) = game_state.map().get_placeable_object_at(drill_x, drill_y) {
-                    let direction = drill_obj.direction();
-                    
-                    // Calculate target position (below drill in its direction)
-                    let (target_x, target_y) = match direction {
-                        Direction::North => (drill_x, drill_y.saturating_sub(1)),
-                        Direction::South => (drill_x, drill_y + 1),
-                        Direction::East => (drill_x + 1, drill_y),
-                        Direction::West => (drill_x.saturating_sub(1), drill_y),
-                    };
-                    
-                    // Check bounds
-                    if target_x < game_state.map_width() && target_y < game_state.map_height() {
-                        // Check if drill is placed over a resource (iron, coal, or copper)
-                        if let Some(resource_type) = game_state.map().get_resource_at(drill_x, drill_y) {
-                            match resource_type {
-                                ResourceType::IronOre | ResourceType::Coal | ResourceType::Copper => {
-                                    // Produce the corresponding item
-                                    let item = match resource_type {
-                                        ResourceType::IronOre => Item::IronOre,
-                                        ResourceType::Coal => Item::Coal,
-                                        ResourceType::Copper => Item::Copper,
-                                        ResourceType::Stone => Item::Stone, // Shouldn't happen but handle it
-                                    };
-                                    
-                                    // Check if target position is valid (not on water)
-                                    if !game_state.map().would_collide_with_water(target_x, target_y) {
-                                        // Check if target is a placeable that can accept items
-                                        if let Some(target_obj) = game_state.map().get_placeable_object_at(target_x, target_y) {
-                                            match target_obj.placeable_type() {
-                                                PlaceableType::Chest => {
-                                                    // Chest accepts any item
-                                                    if let Some(mut chest_data) = game_state.map().get_chest_data(target_x, target_y) {
-                                                        chest_data.add_item(item, 1);
-                                                        game_state.map_mut().set_chest_data(target_x, target_y, chest_data);
-                                                    }
-                                                }
-                                                PlaceableType::Furnace => {
-                                                    // Furnace accepts coal, iron ore, or copper
-                                                    if matches!(item, Item::Coal | Item::IronOre | Item::Copper) {
-                                                        if let Some(mut furnace_data) = game_state.map().get_furnace_data(target_x, target_y) {
-                                                            match item {
-                                                                Item::Coal => {
-                                                                    furnace_data.add_coal();
-                                                                }
-                                                                Item::IronOre => {
-                                                                    furnace_data.add_iron_ore();
-                                                                }
-                                                                Item::Copper => {
-                                                                    furnace_data.add_copper();
-                                                                }
-                                                                _ => {}
-                                                            }
-                                                            game_state.map_mut().set_furnace_data(target_x, target_y, furnace_data);
-                                                        }
-                                                    }
-                                                }
-                                                PlaceableType::Drill => {
-                                                    // Drill accepts coal
-                                                    if matches!(item, Item::Coal) {
-                                                        if let Some(mut target_drill_data) = game_state.map().get_drill_data(target_x, target_y) {
-                                                            target_drill_data.add_coal();
-                                                            game_state.map_mut().set_drill_data(target_x, target_y, target_drill_data);
-                                                        }
-                                                    }
-                                                }
-                                                PlaceableType::Belt => {
-                                                    // Place on belt if belt is empty
-                                                    if !game_state.map().has_belt_item(target_x, target_y) {
-                                                        game_state.map_mut().set_belt_item(target_x, target_y, DroppedItem::new(target_x, target_y, item, 1));
-                                                    }
-                                                }
-                                                _ => {
-                                                    // Not a placeable that accepts items, place as dropped item
-                                                    if !game_state.map().would_collide_with_placeable(target_x, target_y) {
-                                                        game_state.map_mut().add_dropped_item(DroppedItem::new(target_x, target_y, item, 1));
-                                                    }
-                                                }
-                                            }
-                                        } else {
-                                            // Empty space, place as dropped item
-                                            game_state.map_mut().add_dropped_item(DroppedItem::new(target_x, target_y, item, 1));
-                                        }
-                                    }
-                                }
-                                ResourceType::Stone => {
-                                    // Stone is not drillable
-                                }
-                            }
-                        }
-                    }
-                }
-            }
+#[cfg(test)]
+mod tests {
+    use crate::game_state::GameState;
+    use crate::map_generation::GenerationConfig;
+    use crate::action::Action;
+    use crate::types::{Direction, Item, PlaceableType};
+
+    // A map with no water or resources, so layouts fit anywhere around the player
+    fn empty_world() -> GameState {
+        GameState::with_config(&GenerationConfig { water_density: 0.0, resources: Vec::new(), ..GenerationConfig::new(1) })
+    }
+
+    // Every unit of an item in the world: inventory, ground, and belts or entities
+    fn world_count(game_state: &GameState, item: Item) -> u32 {
+        let map = game_state.map();
+        let on_ground: u32 = map.dropped_items_ref().iter().filter(|dropped| dropped.item() == item).map(|dropped| dropped.quantity()).sum();
+        let in_entities: u32 = game_state
+            .entities_in_rect(0, 0, map.width() - 1, map.height() - 1)
+            .iter()
+            .flat_map(|entity| entity.content_items().into_iter().zip(entity.content_counts()))
+            .filter(|(held, _)| *held == item)
+            .map(|(_, count)| count)
+            .sum();
+        game_state.item_count(item) + on_ground + in_entities
+    }
+
+    fn place(x: u32, y: u32, item: Item, direction: Direction) -> Action {
+        Action::Place { item, x, y, direction }
+    }
+
+    // Run ticks with the given drops, checking no iron ore appears or disappears on any tick
+    fn run_conserving(game_state: &mut GameState, ticks: u32, drops: &[(u32, u32)], drop_every: u32) {
+        let total = world_count(game_state, Item::IronOre);
+        for tick in 0..ticks {
+            let actions: Vec<Action> = if tick % drop_every == 0 {
+                drops.iter().map(|&(x, y)| Action::Drop { item: Item::IronOre, x, y }).collect()
+            } else {
+                Vec::new()
+            };
+            game_state.step(&actions);
+            assert_eq!(world_count(game_state, Item::IronOre), total);
         }
     }
-}
 
+    // Whether iron ore has landed on the ground at a tile
+    fn landed(game_state: &GameState, x: u32, y: u32) -> bool {
+        game_state.dropped_items().iter().any(|dropped| (dropped.x(), dropped.y()) == (x, y))
+    }
+
+    #[test]
+    fn items_on_a_straight_belt_are_never_lost() {
+        let mut game_state = empty_world();
+        let (x, y) = (game_state.player_x() + 2, game_state.player_y() + 2);
+        game_state.step(&[Action::PlaceLine { item: Item::Belt, from_x: x, from_y: y, to_x: x + 5, to_y: y }]);
+
+        // Drop faster than the belt clears the tile, so items queue up behind each other
+        run_conserving(&mut game_state, 1500, &[(x, y)], 5);
+        assert!(game_state.map().belt_items_at(x + 5, y).len() > 1);
+        assert!(landed(&game_state, x + 6, y));
+    }
+
+    #[test]
+    fn side_loaded_items_are_never_lost() {
+        let mut game_state = empty_world();
+        let (x, y) = (game_state.player_x() + 2, game_state.player_y() + 3);
+        game_state.step(&[
+            Action::PlaceLine { item: Item::Belt, from_x: x, from_y: y, to_x: x + 4, to_y: y },
+            Action::Place { item: Item::Belt, x: x + 2, y: y - 2, direction: Direction::South },
+            Action::Place { item: Item::Belt, x: x + 2, y: y - 1, direction: Direction::South },
+        ]);
+
+        run_conserving(&mut game_state, 2000, &[(x, y), (x + 2, y - 2)], 20);
+        assert!(landed(&game_state, x + 5, y));
+    }
+
+    #[test]
+    fn items_on_a_belt_loop_are_never_lost() {
+        let mut game_state = empty_world();
+        let (x, y) = (game_state.player_x() + 2, game_state.player_y() + 2);
+        let ring: Vec<(u32, u32, Direction)> = (0..4)
+            .flat_map(|i| [
+                (x + i, y, Direction::East),
+                (x + 4, y + i, Direction::South),
+                (x + 4 - i, y + 4, Direction::West),
+                (x, y + 4 - i, Direction::North),
+            ])
+            .collect();
+        game_state.step(&ring.iter().map(|&(x, y, direction)| place(x, y, Item::Belt, direction)).collect::<Vec<_>>());
+        let carried = game_state.item_count(Item::IronOre);
+
+        // Two items on every tile of the ring, then let them go round
+        let tiles: Vec<(u32, u32)> = ring.iter().map(|&(x, y, _)| (x, y)).collect();
+        run_conserving(&mut game_state, 2, &tiles, 1);
+        run_conserving(&mut game_state, 2000, &[], 1);
+
+        assert_eq!(game_state.item_count(Item::IronOre), carried - 32);
+        assert!(game_state.dropped_items().is_empty());
+    }
+
+    #[test]
+    fn items_through_a_tunnel_are_never_lost() {
+        let mut game_state = empty_world();
+        let (x, y) = (game_state.player_x() + 2, game_state.player_y() + 3);
+        game_state.step(&[
+            Action::PlaceLine { item: Item::Belt, from_x: x, from_y: y, to_x: x + 1, to_y: y },
+            place(x + 2, y, Item::UndergroundBelt, Direction::East),
+            place(x + 3, y, Item::Chest, Direction::East),
+            place(x + 4, y, Item::UndergroundBelt, Direction::East),
+            place(x + 5, y, Item::Belt, Direction::East),
+        ]);
+
+        run_conserving(&mut game_state, 1500, &[(x, y)], 20);
+        assert!(landed(&game_state, x + 6, y));
+        assert!(game_state.get_chest_data(x + 3, y).unwrap().get_all_items().is_empty());
+    }
+
+    #[test]
+    fn items_through_a_splitter_are_never_lost() {
+        let mut game_state = empty_world();
+        let (x, y) = (game_state.player_x() + 2, game_state.player_y() + 3);
+        // The splitter covers (x + 2, y) and (x + 2, y + 1), feeding two lines that end on the ground
+        game_state.step(&[
+            Action::PlaceLine { item: Item::Belt, from_x: x, from_y: y, to_x: x + 1, to_y: y },
+            place(x + 2, y, Item::Splitter, Direction::East),
+            Action::PlaceLine { item: Item::Belt, from_x: x + 3, from_y: y, to_x: x + 5, to_y: y },
+            Action::PlaceLine { item: Item::Belt, from_x: x + 3, from_y: y + 1, to_x: x + 5, to_y: y + 1 },
+        ]);
+
+        run_conserving(&mut game_state, 2000, &[(x, y)], 10);
+        assert!(landed(&game_state, x + 6, y) && landed(&game_state, x + 6, y + 1));
+    }
+
+    #[test]
+    fn lines_dragged_off_the_map_stop_at_its_edge() {
+        let mut game_state = empty_world();
+        let (width, height) = (game_state.map_width(), game_state.map_height());
+        let carried = game_state.item_count(Item::Belt);
+
+        let built = game_state.place_line(Item::Belt, 0, 0, u32::MAX, u32::MAX);
+        assert_eq!(built, carried);
+        assert_eq!(game_state.entity_at(carried - 1, 0).map(|belt| belt.direction()), Some(Direction::East));
+
+        // Starting off the map builds along the edge instead
+        game_state.player_mut().inventory_mut().add_items(Item::Belt, 10);
+        assert_eq!(game_state.place_line(Item::Belt, u32::MAX, u32::MAX, u32::MAX, height - 10), 10);
+        assert!(game_state.entity_at(width - 1, height - 1).is_some());
+        assert!(game_state.entities_of_type(PlaceableType::Belt).iter().all(|belt| belt.x() < width && belt.y() < height));
+    }
+}
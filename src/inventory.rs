@@ -1,127 +1,178 @@
+#[cfg(feature = "wasm")]
 use wasm_bindgen::prelude::*;
-use crate::types::Item;
+use serde::{Serialize, Deserialize};
+use crate::types::{Item, ITEM_DEFS};
+
+// Number of slots in the player's inventory
+pub const INVENTORY_SLOTS: usize = 40;
+
+// A stack of one item type in an inventory slot (count never exceeds the item's stack size)
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ItemStack {
+    item: Item,
+    count: u32,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl ItemStack {
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn item(&self) -> Item {
+        self.item
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn count(&self) -> u32 {
+        self.count
+    }
+}
 
-// Inventory struct
-#[wasm_bindgen]
+// Inventory struct - a fixed number of slots holding item stacks
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Serialize, Deserialize)]
+#[serde(from = "InventorySlots")]
 pub struct Inventory {
-    items: Vec<Item>,
+    slots: Vec<Option<ItemStack>>,
+    // Total held per item, indexed by registry order (rebuilt from slots on load)
+    #[serde(skip)]
+    counts: Vec<u32>,
 }
 
-#[wasm_bindgen]
-impl Inventory {
-    #[wasm_bindgen(constructor)]
-    pub fn new() -> Inventory {
+// Saved form of Inventory
+#[derive(Deserialize)]
+struct InventorySlots {
+    slots: Vec<Option<ItemStack>>,
+}
+
+impl From<InventorySlots> for Inventory {
+    fn from(saved: InventorySlots) -> Inventory {
+        let mut counts = vec![0; ITEM_DEFS.len()];
+        for stack in saved.slots.iter().flatten() {
+            counts[stack.item as usize] += stack.count;
+        }
         Inventory {
-            items: Vec::new(),
+            slots: saved.slots,
+            counts,
         }
     }
+}
 
-    pub fn add_item(&mut self, item: Item) {
-        self.items.push(item);
+impl Default for Inventory {
+    fn default() -> Self {
+        Self::new()
     }
+}
 
-    pub fn count_iron_ore(&self) -> u32 {
-        self.items.iter().filter(|item| matches!(item, Item::IronOre)).count() as u32
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl Inventory {
+    #[cfg_attr(feature = "wasm", wasm_bindgen(constructor))]
+    pub fn new() -> Inventory {
+        Inventory::with_slots(INVENTORY_SLOTS)
     }
 
-    pub fn count_copper(&self) -> u32 {
-        self.items.iter().filter(|item| matches!(item, Item::Copper)).count() as u32
+    pub fn with_slots(slots: usize) -> Inventory {
+        Inventory {
+            slots: vec![None; slots],
+            counts: vec![0; ITEM_DEFS.len()],
+        }
     }
 
-    pub fn count_stone(&self) -> u32 {
-        self.items.iter().filter(|item| matches!(item, Item::Stone)).count() as u32
+    // Number of slots
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn capacity(&self) -> usize {
+        self.slots.len()
     }
 
-    pub fn count_coal(&self) -> u32 {
-        self.items.iter().filter(|item| matches!(item, Item::Coal)).count() as u32
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn used_slots(&self) -> usize {
+        self.slots.iter().filter(|slot| slot.is_some()).count()
     }
 
-    pub fn count_furnace(&self) -> u32 {
-        self.items.iter().filter(|item| matches!(item, Item::Furnace)).count() as u32
+    pub fn count(&self, item: Item) -> u32 {
+        self.counts[item as usize]
     }
 
-    pub fn count_iron_plate(&self) -> u32 {
-        self.items.iter().filter(|item| matches!(item, Item::IronPlate)).count() as u32
+    // How many more of an item fit, counting partial stacks and empty slots
+    pub fn space_for(&self, item: Item) -> u32 {
+        let stack_size = item.stack_size();
+        self.slots
+            .iter()
+            .map(|slot| match slot {
+                Some(stack) if stack.item == item => stack_size.saturating_sub(stack.count),
+                Some(_) => 0,
+                None => stack_size,
+            })
+            .sum()
     }
 
-    pub fn count_belt(&self) -> u32 {
-        self.items.iter().filter(|item| matches!(item, Item::Belt)).count() as u32
+    pub fn can_add(&self, item: Item, count: u32) -> bool {
+        self.space_for(item) >= count
     }
 
-    pub fn count_copper_plate(&self) -> u32 {
-        self.items.iter().filter(|item| matches!(item, Item::CopperPlate)).count() as u32
+    // Add one item, returns false if the inventory is full
+    pub fn add_item(&mut self, item: Item) -> bool {
+        self.add_items(item, 1) == 0
     }
 
-    pub fn count_arm(&self) -> u32 {
-        self.items.iter().filter(|item| matches!(item, Item::Arm)).count() as u32
-    }
+    // Add items, topping up existing stacks before opening new slots.
+    // Returns how many didn't fit.
+    pub fn add_items(&mut self, item: Item, count: u32) -> u32 {
+        let stack_size = item.stack_size();
+        let mut remaining = count;
 
-    pub fn count_chest(&self) -> u32 {
-        self.items.iter().filter(|item| matches!(item, Item::Chest)).count() as u32
-    }
+        for stack in self.slots.iter_mut().flatten() {
+            if remaining == 0 {
+                break;
+            }
+            if stack.item == item && stack.count < stack_size {
+                let added = (stack_size - stack.count).min(remaining);
+                stack.count += added;
+                remaining -= added;
+            }
+        }
+
+        for slot in self.slots.iter_mut() {
+            if remaining == 0 {
+                break;
+            }
+            if slot.is_none() {
+                let added = stack_size.min(remaining);
+                *slot = Some(ItemStack { item, count: added });
+                remaining -= added;
+            }
+        }
 
-    pub fn count_drill(&self) -> u32 {
-        self.items.iter().filter(|item| matches!(item, Item::Drill)).count() as u32
+        self.counts[item as usize] += count - remaining;
+        remaining
     }
 
     pub fn get_available_items(&self) -> Vec<Item> {
-        let mut available = Vec::new();
-        let mut seen = std::collections::HashSet::new();
-        
-        for item in &self.items {
-            let item_discriminant = std::mem::discriminant(item);
-            if !seen.contains(&item_discriminant) {
-                seen.insert(item_discriminant);
-                available.push(item.clone());
-            }
-        }
-        
-        // Sort items in a consistent order based on item type
+        // Items in registry order
         // This ensures the visual order matches the cycling order
-        available.sort_by(|a, b| {
-            let order_a = match a {
-                Item::IronOre => 0,
-                Item::Copper => 1,
-                Item::Stone => 2,
-                Item::Coal => 3,
-                Item::Furnace => 4,
-                Item::IronPlate => 5,
-                Item::Belt => 6,
-                Item::CopperPlate => 7,
-                Item::Arm => 8,
-                Item::Chest => 9,
-                Item::Drill => 10,
-            };
-            let order_b = match b {
-                Item::IronOre => 0,
-                Item::Copper => 1,
-                Item::Stone => 2,
-                Item::Coal => 3,
-                Item::Furnace => 4,
-                Item::IronPlate => 5,
-                Item::Belt => 6,
-                Item::CopperPlate => 7,
-                Item::Arm => 8,
-                Item::Chest => 9,
-                Item::Drill => 10,
-            };
-            order_a.cmp(&order_b)
-        });
-        
-        available
+        Item::all().filter(|item| self.count(*item) > 0).collect()
     }
 
+    // Remove up to count items, emptying the last stacks first.
+    // Returns true if all of them were removed.
     pub fn remove_items(&mut self, item_type: Item, count: u32) -> bool {
-        let mut removed = 0;
-        self.items.retain(|item| {
-            if std::mem::discriminant(item) == std::mem::discriminant(&item_type) && removed < count {
-                removed += 1;
-                false
-            } else {
-                true
+        let mut remaining = count;
+        for slot in self.slots.iter_mut().rev() {
+            if remaining == 0 {
+                break;
             }
-        });
-        removed == count
+            if let Some(stack) = slot {
+                if stack.item == item_type {
+                    let removed = stack.count.min(remaining);
+                    stack.count -= removed;
+                    remaining -= removed;
+                    if stack.count == 0 {
+                        *slot = None;
+                    }
+                }
+            }
+        }
+        self.counts[item_type as usize] -= count - remaining;
+        remaining == 0
     }
 
     // Clean up zero-count items from inventory
@@ -131,3 +182,36 @@ impl Inventory {
     }
 }
 
+// Native-only helpers (signatures wasm-bindgen can't export)
+impl Inventory {
+    // Whether all of these items fit at once (items may repeat)
+    pub fn can_add_all(&self, items: &[(Item, u32)]) -> bool {
+        let mut wanted = vec![0; ITEM_DEFS.len()];
+        for (item, count) in items {
+            wanted[*item as usize] += count;
+        }
+
+        let mut slots_needed = 0;
+        for item in Item::all() {
+            let count = wanted[item as usize];
+            if count == 0 {
+                continue;
+            }
+            let stack_size = item.stack_size();
+            let partial_room: u32 = self
+                .slots
+                .iter()
+                .flatten()
+                .filter(|stack| stack.item == item)
+                .map(|stack| stack_size.saturating_sub(stack.count))
+                .sum();
+            slots_needed += count.saturating_sub(partial_room).div_ceil(stack_size) as usize;
+        }
+        slots_needed <= self.capacity() - self.used_slots()
+    }
+
+    // Items in slot order (None for empty slots)
+    pub fn slots(&self) -> &[Option<ItemStack>] {
+        &self.slots
+    }
+}
@@ -0,0 +1,214 @@
+use std::collections::HashSet;
+use crate::action::Action;
+use crate::types::Direction;
+use crate::splitter::SplitterSide;
+use crate::game_state::GameState;
+use crate::handlers::get_target_position;
+
+// Keys held down during a tick, named like the browser's KeyboardEvent.key ("w", " ", "delete", ...)
+#[derive(Clone, Default)]
+pub struct KeyState {
+    pressed: HashSet<String>,
+}
+
+impl KeyState {
+    pub fn new() -> KeyState {
+        KeyState {
+            pressed: HashSet::new(),
+        }
+    }
+
+    pub fn press(&mut self, key: &str) {
+        self.pressed.insert(key.to_string());
+    }
+
+    pub fn release(&mut self, key: &str) {
+        self.pressed.remove(key);
+    }
+
+    pub fn is_pressed(&self, key: &str) -> bool {
+        self.pressed.contains(key)
+    }
+}
+
+// Translate the browser keymap into actions, targeting the cursor or the tile in front of the player
+pub fn actions_from_keys(game_state: &GameState, keys: &KeyState) -> Vec<Action> {
+    let mut actions = Vec::new();
+
+    // Movement (WASD)
+    if keys.is_pressed("w") {
+        actions.push(Action::Move(Direction::North));
+    }
+    if keys.is_pressed("s") {
+        actions.push(Action::Move(Direction::South));
+    }
+    if keys.is_pressed("a") {
+        actions.push(Action::Move(Direction::West));
+    }
+    if keys.is_pressed("d") {
+        actions.push(Action::Move(Direction::East));
+    }
+
+    // Mining (M key)
+    if keys.is_pressed("m") {
+        actions.push(Action::Mine);
+    }
+
+    // Crafting (F furnace, B belt, P arm, C chest, T drill, U underground belt, L splitter)
+    let craft_keys = [
+        ("f", "furnace"),
+        ("b", "belt"),
+        ("p", "arm"),
+        ("c", "chest"),
+        ("t", "drill"),
+        ("u", "underground_belt"),
+        ("l", "splitter"),
+    ];
+    for (key, recipe) in craft_keys {
+        if keys.is_pressed(key) {
+            actions.push(Action::Craft { recipe: recipe.to_string(), count: 1 });
+        }
+    }
+
+    // Cancel the most recently queued craft (X key)
+    if keys.is_pressed("x") {
+        let queued = game_state.crafting_queue().len();
+        if queued > 0 {
+            actions.push(Action::CancelCraft { index: queued - 1 });
+        }
+    }
+
+    let (target_x, target_y) = get_target_position(game_state);
+
+    // Placement/interaction (Space key)
+    // Add the selected item to a container under the target, otherwise place the selected item
+    if keys.is_pressed(" ") {
+        actions.push(Action::UseSelected { x: target_x, y: target_y });
+    }
+
+    // Inventory selection cycling ([ and ] keys)
+    if keys.is_pressed("[") {
+        actions.push(Action::SelectPrevious);
+    }
+    if keys.is_pressed("]") {
+        actions.push(Action::SelectNext);
+    }
+
+    // Pickup (h key) - picks up items, or harvests from furnace if no items
+    if keys.is_pressed("h") {
+        actions.push(Action::Pickup { x: target_x, y: target_y });
+    }
+
+    // Drop (j key)
+    if keys.is_pressed("j") {
+        actions.push(Action::DropSelected { x: target_x, y: target_y });
+    }
+
+    // Rotation (r key) - the object under the target, or the next placement if nothing there rotates
+    if keys.is_pressed("r") {
+        let rotatable = game_state
+            .map()
+            .get_placeable_at(target_x, target_y)
+            .is_some_and(|placeable_type| placeable_type.is_rotatable());
+        if rotatable {
+            actions.push(Action::Rotate { x: target_x, y: target_y });
+        } else {
+            actions.push(Action::RotatePlacement);
+        }
+    }
+
+    // Splitter settings for the splitter under the target: I cycles the input priority,
+    // O the output priority, G filters on the selected item (or clears the filter)
+    if let Some(splitter) = game_state.get_splitter_data(target_x, target_y) {
+        let mut input_priority = splitter.input_priority();
+        let mut output_priority = splitter.output_priority();
+        let mut filter = splitter.filter();
+        if keys.is_pressed("i") {
+            input_priority = SplitterSide::cycle(input_priority);
+        }
+        if keys.is_pressed("o") {
+            output_priority = SplitterSide::cycle(output_priority);
+        }
+        if keys.is_pressed("g") {
+            let selected = game_state.get_selected_item();
+            filter = if filter == selected { None } else { selected };
+        }
+        if keys.is_pressed("i") || keys.is_pressed("o") || keys.is_pressed("g") {
+            actions.push(Action::ConfigureSplitter { x: target_x, y: target_y, input_priority, output_priority, filter });
+        }
+    }
+
+    // Pickup placeable (Delete key)
+    if keys.is_pressed("delete") {
+        actions.push(Action::Deconstruct { x: target_x, y: target_y });
+    }
+
+    actions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{Item, PlaceableType};
+    use crate::events::{GameEvent, ActionError};
+
+    #[test]
+    fn held_keys_become_actions_on_the_target_tile() {
+        let game_state = GameState::new();
+        let mut keys = KeyState::new();
+        for key in ["w", "d", "b", "h"] {
+            keys.press(key);
+        }
+        keys.release("w");
+
+        let (x, y) = get_target_position(&game_state);
+        assert_eq!(actions_from_keys(&game_state, &keys), vec![
+            Action::Move(Direction::East),
+            Action::Craft { recipe: "belt".to_string(), count: 1 },
+            Action::Pickup { x, y },
+        ]);
+    }
+
+    #[test]
+    fn space_places_the_selected_item_under_the_cursor() {
+        let mut game_state = GameState::new();
+        while game_state.get_selected_item() != Some(Item::Belt) {
+            game_state.step(&[Action::SelectNext]);
+        }
+        let (x, y) = (game_state.player_x() + 3, game_state.player_y() + 3);
+        let mut keys = KeyState::new();
+        keys.press(" ");
+        game_state.next_step(&keys, Some(x), Some(y));
+
+        let placed = game_state.entity_at(x, y).unwrap();
+        assert_eq!(placed.placeable_type(), PlaceableType::Belt);
+        assert_eq!(placed.direction(), game_state.placement_direction());
+    }
+
+    #[test]
+    fn using_nothing_fails_on_the_tick_it_is_applied_and_is_recorded() {
+        let mut game_state = GameState::new();
+        for item in Item::all() {
+            let count = game_state.player().inventory().count(item);
+            game_state.player_mut().inventory_mut().remove_items(item, count);
+        }
+        game_state.validate_selection();
+        game_state.start_recording();
+        game_state.drain_events();
+
+        let mut keys = KeyState::new();
+        keys.press(" ");
+        keys.press("j");
+        game_state.next_step(&keys, None, None);
+
+        let failures: Vec<u64> = game_state
+            .drain_events()
+            .into_iter()
+            .filter(|record| record.event == GameEvent::ActionFailed(ActionError::NoItemSelected))
+            .map(|record| record.tick)
+            .collect();
+        assert_eq!(failures, vec![game_state.current_tick(); 2]);
+        let frames = game_state.recording().unwrap().frames();
+        assert!(matches!(frames[0].actions[..], [Action::UseSelected { .. }, Action::DropSelected { .. }]));
+    }
+}
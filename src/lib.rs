@@ -3,35 +3,64 @@ mod types;
 mod inventory;
 mod player;
 mod map;
+mod grid;
+mod entity;
 mod furnace;
 mod arm;
 mod chest;
 mod drill;
+mod fuel;
+mod belt;
+mod underground_belt;
+mod splitter;
 mod crafting;
+mod stats;
+mod events;
 mod handlers;
 mod map_generation;
 mod game_state;
+mod save;
+mod replay;
+mod action;
+mod keymap;
+#[cfg(feature = "wasm")]
+mod wasm;
 
-// Re-export public types for wasm-bindgen
-pub use types::{Item, Direction, PlaceableType, ResourceType};
-pub use inventory::Inventory;
+// Re-export public types for wasm-bindgen and native users
+pub use types::{Item, ItemDef, ItemCategory, ITEM_DEFS, Direction, PlaceableType, ResourceType, item_name, item_id};
+pub use inventory::{Inventory, ItemStack, INVENTORY_SLOTS};
 pub use player::Player;
 pub use map::{Map, Resource, WaterPatch, PlaceableObject, IronOre, DroppedItem};
+pub use entity::{Entity, EntityId, EntityState, EntitySnapshot, MachineStatus, TickContext, machine_status_name};
 pub use furnace::FurnaceData;
 pub use arm::ArmData;
 pub use chest::ChestData;
 pub use drill::DrillData;
-pub use game_state::GameState;
+pub use fuel::FuelStore;
+pub use underground_belt::{UndergroundBeltData, UndergroundRole, UNDERGROUND_MAX_LENGTH};
+pub use splitter::{SplitterData, SplitterSide};
+pub use belt::{BeltItem, BeltLane, BeltShape, BELT_LENGTH, BELT_ITEM_SPACING, DEFAULT_BELT_SPEED};
+pub use game_state::{GameState, PlacementPreview};
+pub use save::{SaveError, SAVE_FORMAT_VERSION};
+pub use replay::{Replay, REPLAY_FORMAT_VERSION};
+pub use map_generation::{GenerationConfig, ResourcePatchConfig, seed_from_str};
+pub use action::{Action, InputFrame};
+pub use keymap::KeyState;
+pub use crafting::{CraftingRecipe, CraftError, CraftJob, RecipeInfo, RECIPES};
+pub use stats::{ProductionStats, StatsWindow, ItemStats};
+pub use events::{GameEvent, EventRecord, PlacementError, ItemLocation, ActionError};
+pub use game_state::EventListener;
 
 // Console message struct (kept for backward compatibility if needed)
+#[cfg(feature = "wasm")]
 use wasm_bindgen::prelude::*;
 
-#[wasm_bindgen]
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
 pub struct ConsoleMessage {
     text: String,
 }
 
-#[wasm_bindgen]
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
 impl ConsoleMessage {
     pub fn text(&self) -> String {
         self.text.clone()
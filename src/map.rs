@@ -1,43 +1,52 @@
+#[cfg(feature = "wasm")]
 use wasm_bindgen::prelude::*;
+use serde::{Serialize, Deserialize};
 use std::collections::HashMap;
 use crate::types::{Item, PlaceableType, ResourceType, Direction};
 use crate::furnace::FurnaceData;
 use crate::arm::ArmData;
 use crate::chest::ChestData;
 use crate::drill::DrillData;
-
-// Placeable object struct
-#[wasm_bindgen]
-#[derive(Clone)]
+use crate::splitter::SplitterData;
+use crate::underground_belt::{UndergroundBeltData, UndergroundRole, UNDERGROUND_MAX_LENGTH};
+use crate::entity::{Entity, EntityId, EntityState, EntitySnapshot};
+use crate::grid::Grid;
+use crate::events::PlacementError;
+use crate::belt::{BeltLane, BeltShape, BELT_LENGTH, BELT_ITEM_SPACING, DEFAULT_BELT_SPEED};
+
+// Placeable object struct - one entry in the Map's entity store
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct PlaceableObject {
+    id: EntityId,
     x: u32,
     y: u32,
-    placeable_type: PlaceableType,
     direction: Direction, // Direction for belts (and potentially other rotatable objects)
+    state: EntityState,   // Type-specific data (furnace contents, arm timer, ...)
 }
 
-#[wasm_bindgen]
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
 impl PlaceableObject {
-    #[wasm_bindgen(getter)]
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
     pub fn x(&self) -> u32 {
         self.x
     }
 
-    #[wasm_bindgen(getter)]
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
     pub fn y(&self) -> u32 {
         self.y
     }
 
-    #[wasm_bindgen(getter)]
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
     pub fn placeable_type(&self) -> PlaceableType {
-        self.placeable_type
+        self.state.placeable_type()
     }
 
     pub fn placeable_type_value(&self) -> u32 {
-        self.placeable_type.value()
+        self.placeable_type().value()
     }
 
-    #[wasm_bindgen(getter)]
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
     pub fn direction(&self) -> Direction {
         self.direction
     }
@@ -49,35 +58,56 @@ impl PlaceableObject {
     pub fn set_direction(&mut self, direction: Direction) {
         self.direction = direction;
     }
+
+    // The entity id as a plain number (what GameState::entity takes)
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn id(&self) -> u32 {
+        self.id.0
+    }
+}
+
+// Native-only helpers (signatures wasm-bindgen can't export)
+impl PlaceableObject {
+    pub fn entity_id(&self) -> EntityId {
+        self.id
+    }
+
+    pub fn state(&self) -> &EntityState {
+        &self.state
+    }
+
+    pub(crate) fn state_mut(&mut self) -> &mut EntityState {
+        &mut self.state
+    }
 }
 
 // Resource structs (for resources that don't disappear)
-#[wasm_bindgen]
-#[derive(Clone)]
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Resource {
     x: u32,
     y: u32,
     resource_type: ResourceType,
 }
 
-#[wasm_bindgen]
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
 impl Resource {
-    #[wasm_bindgen(constructor)]
+    #[cfg_attr(feature = "wasm", wasm_bindgen(constructor))]
     pub fn new(x: u32, y: u32, resource_type: ResourceType) -> Resource {
         Resource { x, y, resource_type }
     }
 
-    #[wasm_bindgen(getter)]
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
     pub fn x(&self) -> u32 {
         self.x
     }
 
-    #[wasm_bindgen(getter)]
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
     pub fn y(&self) -> u32 {
         self.y
     }
 
-    #[wasm_bindgen(getter)]
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
     pub fn resource_type(&self) -> ResourceType {
         self.resource_type
     }
@@ -88,8 +118,8 @@ impl Resource {
 }
 
 // Water patch struct
-#[wasm_bindgen]
-#[derive(Clone)]
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct WaterPatch {
     x: u32,
     y: u32,
@@ -97,58 +127,72 @@ pub struct WaterPatch {
     height: u32,
 }
 
-#[wasm_bindgen]
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
 impl WaterPatch {
-    #[wasm_bindgen(constructor)]
+    #[cfg_attr(feature = "wasm", wasm_bindgen(constructor))]
     pub fn new(x: u32, y: u32, width: u32, height: u32) -> WaterPatch {
         WaterPatch { x, y, width, height }
     }
 
-    #[wasm_bindgen(getter)]
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
     pub fn x(&self) -> u32 {
         self.x
     }
 
-    #[wasm_bindgen(getter)]
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
     pub fn y(&self) -> u32 {
         self.y
     }
 
-    #[wasm_bindgen(getter)]
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
     pub fn width(&self) -> u32 {
         self.width
     }
 
-    #[wasm_bindgen(getter)]
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
     pub fn height(&self) -> u32 {
         self.height
     }
 }
 
+// What is on one tile, kept in sync with the Map's lists so position lookups are O(1)
+#[derive(Clone, Default)]
+struct Tile {
+    water: bool,
+    resource: Option<ResourceType>,
+    placeable: Option<usize>,    // Index into entities
+    dropped_item: Option<usize>, // Lowest index into dropped_items on this tile
+}
+
 // Map struct
-#[wasm_bindgen]
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Serialize, Deserialize)]
 pub struct Map {
     width: u32,
     height: u32,
     water_patches: Vec<WaterPatch>,
     resources: Vec<Resource>,
-    placeable_objects: Vec<PlaceableObject>,
-    furnace_data: HashMap<(u32, u32), FurnaceData>,
-    arm_data: HashMap<(u32, u32), ArmData>,
-    chest_data: HashMap<(u32, u32), ChestData>,
-    drill_data: HashMap<(u32, u32), DrillData>,
+    entities: Vec<PlaceableObject>, // Placed objects, in placement order (also the tick order)
+    next_entity_id: u32,
     dropped_items: Vec<DroppedItem>,
-    belt_items: HashMap<(u32, u32), DroppedItem>, // Items currently on belts (only 1 per belt)
+    #[serde(with = "crate::save::position_map")]
+    belt_items: HashMap<(u32, u32), Vec<DroppedItem>>, // Items on each belt tile, front (highest progress) first
+    belt_speed: u32, // Belt progress per tick (see belt::BELT_LENGTH)
+    // Derived from the lists above - rebuilt by rebuild_tile_index() after loading
+    #[serde(skip)]
+    tiles: Grid<Tile>,
+    #[serde(skip)]
+    entity_index: HashMap<EntityId, usize>, // Entity id -> index into entities
 }
 
-#[wasm_bindgen]
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
 impl Map {
-    #[wasm_bindgen(getter)]
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
     pub fn width(&self) -> u32 {
         self.width
     }
 
-    #[wasm_bindgen(getter)]
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
     pub fn height(&self) -> u32 {
         self.height
     }
@@ -162,7 +206,7 @@ impl Map {
     }
 
     pub fn placeable_objects(&self) -> Vec<PlaceableObject> {
-        self.placeable_objects.clone()
+        self.entities.clone()
     }
 
     pub fn dropped_items(&self) -> Vec<DroppedItem> {
@@ -170,116 +214,68 @@ impl Map {
     }
 
     pub fn get_resource_at(&self, x: u32, y: u32) -> Option<ResourceType> {
-        for resource in &self.resources {
-            if resource.x == x && resource.y == y {
-                return Some(resource.resource_type);
-            }
-        }
-        None
+        self.tiles.get(x, y).and_then(|tile| tile.resource)
     }
 
     pub fn get_placeable_at(&self, x: u32, y: u32) -> Option<PlaceableType> {
-        for obj in &self.placeable_objects {
-            if obj.x == x && obj.y == y {
-                return Some(obj.placeable_type);
-            }
-        }
-        None
+        self.get_placeable_object_at(x, y).map(|obj| obj.placeable_type())
     }
 
     pub(crate) fn get_placeable_object_at(&self, x: u32, y: u32) -> Option<&PlaceableObject> {
-        self.placeable_objects.iter().find(|obj| obj.x == x && obj.y == y)
+        let index = self.tiles.get(x, y)?.placeable?;
+        self.entities.get(index)
     }
 
     pub(crate) fn get_placeable_object_at_mut(&mut self, x: u32, y: u32) -> Option<&mut PlaceableObject> {
-        self.placeable_objects.iter_mut().find(|obj| obj.x == x && obj.y == y)
+        let index = self.tiles.get(x, y)?.placeable?;
+        self.entities.get_mut(index)
     }
 
     pub fn get_furnace_data(&self, x: u32, y: u32) -> Option<FurnaceData> {
-        self.furnace_data.get(&(x, y)).cloned()
+        match self.get_placeable_object_at(x, y)?.state() {
+            EntityState::Furnace(data) => Some(data.clone()),
+            _ => None,
+        }
     }
 
     pub fn get_dropped_item_index_at(&self, x: u32, y: u32) -> Option<usize> {
-        self.dropped_items.iter().position(|item| item.x() == x && item.y() == y)
-    }
-
-    pub fn set_furnace_data(&mut self, x: u32, y: u32, data: FurnaceData) {
-        self.furnace_data.insert((x, y), data);
+        self.tiles.get(x, y).and_then(|tile| tile.dropped_item)
     }
 
     pub fn get_arm_data(&self, x: u32, y: u32) -> Option<ArmData> {
-        self.arm_data.get(&(x, y)).cloned()
-    }
-
-    pub fn set_arm_data(&mut self, x: u32, y: u32, data: ArmData) {
-        self.arm_data.insert((x, y), data);
+        match self.get_placeable_object_at(x, y)?.state() {
+            EntityState::Arm(data) => Some(data.clone()),
+            _ => None,
+        }
     }
 
     pub fn get_chest_data(&self, x: u32, y: u32) -> Option<ChestData> {
-        self.chest_data.get(&(x, y)).cloned()
-    }
-
-    pub fn set_chest_data(&mut self, x: u32, y: u32, data: ChestData) {
-        self.chest_data.insert((x, y), data);
+        match self.get_placeable_object_at(x, y)?.state() {
+            EntityState::Chest(data) => Some(data.clone()),
+            _ => None,
+        }
     }
 
     pub fn get_drill_data(&self, x: u32, y: u32) -> Option<DrillData> {
-        self.drill_data.get(&(x, y)).cloned()
+        match self.get_placeable_object_at(x, y)?.state() {
+            EntityState::Drill(data) => Some(data.clone()),
+            _ => None,
+        }
     }
 
-    pub fn set_drill_data(&mut self, x: u32, y: u32, data: DrillData) {
-        self.drill_data.insert((x, y), data);
+    pub fn get_underground_belt_data(&self, x: u32, y: u32) -> Option<UndergroundBeltData> {
+        self.underground_belt_at(x, y).cloned()
     }
 
-    pub fn add_placeable(&mut self, x: u32, y: u32, placeable_type: PlaceableType) {
-        // Check if position is valid (not on water, not occupied)
-        if self.would_collide_with_water(x, y) {
-            return;
-        }
-        if self.get_placeable_at(x, y).is_some() {
-            return;
-        }
-        // Drills can be placed on resources, other items cannot
-        if !matches!(placeable_type, PlaceableType::Drill) {
-            if self.get_resource_at(x, y).is_some() {
-                return;
-            }
-        }
-        self.placeable_objects.push(PlaceableObject {
-            x,
-            y,
-            placeable_type,
-            direction: Direction::East, // Default direction for belts
-        });
-        // Initialize furnace data if it's a furnace
-        if matches!(placeable_type, PlaceableType::Furnace) {
-            self.furnace_data.insert((x, y), FurnaceData::new());
-        }
-        // Initialize arm data if it's an arm
-        if matches!(placeable_type, PlaceableType::Arm) {
-            self.arm_data.insert((x, y), ArmData::new());
-        }
-        // Initialize chest data if it's a chest
-        if matches!(placeable_type, PlaceableType::Chest) {
-            self.chest_data.insert((x, y), ChestData::new());
-        }
-        // Initialize drill data if it's a drill
-        if matches!(placeable_type, PlaceableType::Drill) {
-            self.drill_data.insert((x, y), DrillData::new());
+    pub fn get_splitter_data(&self, x: u32, y: u32) -> Option<SplitterData> {
+        match self.get_placeable_object_at(x, y)?.state() {
+            EntityState::Splitter(data) => Some(data.clone()),
+            _ => None,
         }
     }
 
     pub fn would_collide_with_water(&self, x: u32, y: u32) -> bool {
-        for patch in &self.water_patches {
-            if x < patch.x + patch.width
-                && x + 1 > patch.x
-                && y < patch.y + patch.height
-                && y + 1 > patch.y
-            {
-                return true;
-            }
-        }
-        false
+        self.tiles.get(x, y).is_some_and(|tile| tile.water)
     }
 
     pub fn would_collide_with_placeable(&self, x: u32, y: u32) -> bool {
@@ -298,105 +294,480 @@ impl Map {
             height,
             water_patches: Vec::new(),
             resources: Vec::new(),
-            placeable_objects: Vec::new(),
-            furnace_data: HashMap::new(),
-            arm_data: HashMap::new(),
-            chest_data: HashMap::new(),
-            drill_data: HashMap::new(),
+            entities: Vec::new(),
+            next_entity_id: 0,
             dropped_items: Vec::new(),
             belt_items: HashMap::new(),
+            belt_speed: DEFAULT_BELT_SPEED,
+            tiles: Grid::new(width, height),
+            entity_index: HashMap::new(),
+        }
+    }
+
+    // Rebuild the per-tile index from the lists (after loading a save)
+    pub(crate) fn rebuild_tile_index(&mut self) {
+        self.tiles = Grid::new(self.width, self.height);
+        for index in 0..self.water_patches.len() {
+            self.index_water_patch(index);
+        }
+        for index in 0..self.resources.len() {
+            self.index_resource(index);
+        }
+        self.entity_index.clear();
+        for index in 0..self.entities.len() {
+            for (x, y) in self.object_tiles(&self.entities[index]) {
+                if let Some(tile) = self.tiles.get_mut(x, y) {
+                    tile.placeable = Some(index);
+                }
+            }
+            self.entity_index.insert(self.entities[index].id, index);
+        }
+        for (index, item) in self.dropped_items.iter().enumerate() {
+            if let Some(tile) = self.tiles.get_mut(item.x, item.y) {
+                tile.dropped_item.get_or_insert(index);
+            }
+        }
+    }
+
+    fn index_water_patch(&mut self, index: usize) {
+        let patch = &self.water_patches[index];
+        for y in patch.y..patch.y + patch.height {
+            for x in patch.x..patch.x + patch.width {
+                if let Some(tile) = self.tiles.get_mut(x, y) {
+                    tile.water = true;
+                }
+            }
+        }
+    }
+
+    fn index_resource(&mut self, index: usize) {
+        let resource = &self.resources[index];
+        if let Some(tile) = self.tiles.get_mut(resource.x, resource.y) {
+            // The first resource added to a tile is the one that gets mined
+            tile.resource.get_or_insert(resource.resource_type);
         }
     }
 
     pub fn add_water_patch(&mut self, patch: WaterPatch) {
         self.water_patches.push(patch);
+        self.index_water_patch(self.water_patches.len() - 1);
     }
 
     pub fn add_resource(&mut self, resource: Resource) {
         self.resources.push(resource);
+        self.index_resource(self.resources.len() - 1);
     }
 
     pub fn add_dropped_item(&mut self, item: DroppedItem) {
+        if let Some(tile) = self.tiles.get_mut(item.x, item.y) {
+            tile.dropped_item.get_or_insert(self.dropped_items.len());
+        }
         self.dropped_items.push(item);
     }
 
     pub fn remove_dropped_item(&mut self, index: usize) {
-        if index < self.dropped_items.len() {
-            self.dropped_items.remove(index);
+        if index >= self.dropped_items.len() {
+            return;
         }
+
+        // The tile falls back to the next dropped item on it, if any
+        let (x, y) = (self.dropped_items[index].x, self.dropped_items[index].y);
+        let next_on_tile = self.dropped_items[index + 1..]
+            .iter()
+            .position(|item| item.x == x && item.y == y)
+            .map(|offset| index + 1 + offset);
+        if let Some(tile) = self.tiles.get_mut(x, y) {
+            if tile.dropped_item == Some(index) {
+                tile.dropped_item = next_on_tile;
+            }
+        }
+
+        self.dropped_items.remove(index);
+
+        // Everything after the removed item moved down one index
+        for (moved, item) in self.dropped_items.iter().enumerate().skip(index) {
+            if let Some(tile) = self.tiles.get_mut(item.x, item.y) {
+                if tile.dropped_item == Some(moved + 1) {
+                    tile.dropped_item = Some(moved);
+                }
+            }
+        }
+    }
+
+    // Borrowed views of the object lists for the tick handlers (the exported getters clone)
+    pub(crate) fn placeable_objects_ref(&self) -> &[PlaceableObject] {
+        &self.entities
+    }
+
+    pub(crate) fn placeable_objects_mut(&mut self) -> &mut [PlaceableObject] {
+        &mut self.entities
     }
 
-    // Internal method (not exposed to wasm)
+    pub(crate) fn dropped_items_ref(&self) -> &[DroppedItem] {
+        &self.dropped_items
+    }
+
+    // Internal method (not exposed to wasm) - for quantity changes only, moving the item would bypass the tile index
     pub(crate) fn get_dropped_item_mut(&mut self, index: usize) -> Option<&mut DroppedItem> {
         self.dropped_items.get_mut(index)
     }
 
-    pub(crate) fn has_belt_item(&self, x: u32, y: u32) -> bool {
-        self.belt_items.contains_key(&(x, y))
+    pub fn belt_speed(&self) -> u32 {
+        self.belt_speed
+    }
+
+    pub fn set_belt_speed(&mut self, speed: u32) {
+        self.belt_speed = speed;
+    }
+
+    // Items on a belt tile, front first
+    pub(crate) fn belt_items_at(&self, x: u32, y: u32) -> &[DroppedItem] {
+        self.belt_items.get(&(x, y)).map_or(&[], Vec::as_slice)
+    }
+
+    // The tile next to (x, y) in a direction (None off the map)
+    pub(crate) fn neighbour(&self, x: u32, y: u32, direction: Direction) -> Option<(u32, u32)> {
+        let (x, y) = match direction {
+            Direction::North => (x, y.checked_sub(1)?),
+            Direction::South => (x, y + 1),
+            Direction::East => (x + 1, y),
+            Direction::West => (x.checked_sub(1)?, y),
+        };
+        (x < self.width && y < self.height).then_some((x, y))
+    }
+
+    // Direction of the belt (or underground belt) on a tile (None if there's no belt)
+    pub(crate) fn belt_direction_at(&self, x: u32, y: u32) -> Option<Direction> {
+        self.get_placeable_object_at(x, y)
+            .filter(|obj| obj.placeable_type().is_belt())
+            .map(|obj| obj.direction())
     }
 
-    pub(crate) fn set_belt_item(&mut self, x: u32, y: u32, item: DroppedItem) {
-        self.belt_items.insert((x, y), item);
+    pub(crate) fn underground_belt_at(&self, x: u32, y: u32) -> Option<&UndergroundBeltData> {
+        match self.get_placeable_object_at(x, y)?.state() {
+            EntityState::UndergroundBelt(data) => Some(data),
+            _ => None,
+        }
+    }
+
+    fn underground_belt_at_mut(&mut self, x: u32, y: u32) -> Option<&mut UndergroundBeltData> {
+        match self.get_placeable_object_at_mut(x, y)?.state_mut() {
+            EntityState::UndergroundBelt(data) => Some(data),
+            _ => None,
+        }
+    }
+
+    // The splitter covering a tile (either half)
+    pub(crate) fn splitter_at_mut(&mut self, x: u32, y: u32) -> Option<&mut SplitterData> {
+        match self.get_placeable_object_at_mut(x, y)?.state_mut() {
+            EntityState::Splitter(data) => Some(data),
+            _ => None,
+        }
+    }
+
+    // Whether the belt on the tile in front of (x, y), looking `toward` it, points back at (x, y).
+    // Underground entries send their items down the tunnel instead.
+    fn belt_feeds_from(&self, x: u32, y: u32, toward: Direction) -> bool {
+        let Some((from_x, from_y)) = self.neighbour(x, y, toward) else {
+            return false;
+        };
+        let is_entry = self.underground_belt_at(from_x, from_y).is_some_and(|data| data.role() == UndergroundRole::Entry);
+        !is_entry && self.belt_direction_at(from_x, from_y) == Some(toward.opposite())
+    }
+
+    // Shape of the belt on a tile, worked out from the belts feeding into it (None if there's no belt).
+    // Underground belts and splitters are always straight.
+    pub(crate) fn belt_shape(&self, x: u32, y: u32) -> Option<BeltShape> {
+        let direction = self.belt_direction_at(x, y)?;
+        if self.get_placeable_at(x, y) != Some(PlaceableType::Belt) || self.belt_feeds_from(x, y, direction.opposite()) {
+            return Some(BeltShape::Straight);
+        }
+        let right = direction.rotated_clockwise();
+        let shape = match (self.belt_feeds_from(x, y, right.opposite()), self.belt_feeds_from(x, y, right)) {
+            (true, false) => BeltShape::TurnLeft,
+            (false, true) => BeltShape::TurnRight,
+            _ => BeltShape::Straight,
+        };
+        Some(shape)
+    }
+
+    // Whether an item could sit at this progress in a lane without crowding its neighbours
+    pub(crate) fn belt_has_room(&self, x: u32, y: u32, lane: BeltLane, progress: u32) -> bool {
+        self.belt_items_at(x, y)
+            .iter()
+            .filter(|item| item.lane == lane)
+            .all(|item| item.progress.abs_diff(progress) >= BELT_ITEM_SPACING)
+    }
+
+    // Put an item onto a belt lane at the given progress, returns false if there's no room
+    pub(crate) fn insert_onto_belt(&mut self, x: u32, y: u32, mut item: DroppedItem, lane: BeltLane, progress: u32) -> bool {
+        if !self.belt_has_room(x, y, lane, progress) {
+            return false;
+        }
+        item.set_position(x, y);
+        item.lane = lane;
+        item.progress = progress;
+        let items = self.belt_items.entry((x, y)).or_default();
+        let index = items.iter().position(|other| other.progress < progress).unwrap_or(items.len());
+        items.insert(index, item);
+        true
+    }
+
+    // The front item (of either lane) on a belt tile - not one already down an underground belt's tunnel
+    pub(crate) fn front_belt_item(&self, x: u32, y: u32) -> Option<&DroppedItem> {
+        self.belt_items_at(x, y).iter().find(|item| item.progress <= BELT_LENGTH)
+    }
+
+    // Take the front item off a belt tile
+    pub(crate) fn take_belt_item(&mut self, x: u32, y: u32) -> Option<DroppedItem> {
+        let items = self.belt_items.get_mut(&(x, y))?;
+        let index = items.iter().position(|item| item.progress <= BELT_LENGTH)?;
+        let item = items.remove(index);
+        if items.is_empty() {
+            self.belt_items.remove(&(x, y));
+        }
+        Some(item)
+    }
+
+    // Take every item off a belt tile (for the belt tick to move them)
+    pub(crate) fn take_belt_items(&mut self, x: u32, y: u32) -> Vec<DroppedItem> {
+        self.belt_items.remove(&(x, y)).unwrap_or_default()
+    }
+
+    // Put back a belt tile's items, which must be ordered front first
+    pub(crate) fn set_belt_items(&mut self, x: u32, y: u32, items: Vec<DroppedItem>) {
+        if items.is_empty() {
+            self.belt_items.remove(&(x, y));
+        } else {
+            self.belt_items.insert((x, y), items);
+        }
+    }
+
+    pub(crate) fn get_entity(&self, id: EntityId) -> Option<&PlaceableObject> {
+        self.entity_index.get(&id).map(|index| &self.entities[*index])
     }
 
-    pub fn get_belt_item(&self, x: u32, y: u32) -> Option<DroppedItem> {
-        self.belt_items.get(&(x, y)).cloned()
+    // Snapshot of an entity, including the items a belt is carrying
+    pub(crate) fn snapshot(&self, obj: &PlaceableObject) -> EntitySnapshot {
+        let mut contents = obj.state.contents();
+        for (x, y) in self.object_tiles(obj) {
+            for belt_item in self.belt_items_at(x, y) {
+                contents.push((belt_item.item, belt_item.quantity));
+            }
+        }
+        EntitySnapshot::new(obj.id, obj.x, obj.y, obj.direction, contents, obj.state.clone())
     }
 
-    pub(crate) fn remove_belt_item(&mut self, x: u32, y: u32) {
-        self.belt_items.remove(&(x, y));
+    // Whether the entity at a position would take one of this item right now
+    pub(crate) fn accepts_item_at(&self, x: u32, y: u32, item: Item) -> bool {
+        self.get_placeable_object_at(x, y).is_some_and(|obj| obj.state.accepts_item(item))
+    }
+
+    // Insert one item into the entity at a position, returns false if it doesn't accept it
+    pub(crate) fn insert_item_at(&mut self, x: u32, y: u32, item: Item) -> bool {
+        match self.get_placeable_object_at_mut(x, y) {
+            Some(obj) if obj.state.accepts_item(item) => {
+                obj.state.insert_item(item);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    // Take one item out of the entity at a position
+    pub(crate) fn take_item_at(&mut self, x: u32, y: u32) -> Option<Item> {
+        self.get_placeable_object_at_mut(x, y)?.state.take_item()
     }
 
     pub(crate) fn remove_placeable(&mut self, x: u32, y: u32) {
-        // Remove from placeable_objects
-        self.placeable_objects.retain(|obj| !(obj.x == x && obj.y == y));
-        // Remove associated data
-        self.furnace_data.remove(&(x, y));
-        self.arm_data.remove(&(x, y));
-        self.chest_data.remove(&(x, y));
-        self.drill_data.remove(&(x, y));
-        // Remove belt items if it was a belt
-        self.belt_items.remove(&(x, y));
+        // The other end of an underground belt goes back to being an unpaired entry
+        if let Some(partner) = self.underground_belt_at(x, y).and_then(|data| data.partner()) {
+            if let Some(data) = self.underground_belt_at_mut(partner.0, partner.1) {
+                data.unpair();
+            }
+        }
+        // Remove from the entity store, along with anything on the belts it covered
+        let Some(index) = self.tiles.get(x, y).and_then(|tile| tile.placeable) else {
+            self.belt_items.remove(&(x, y));
+            return;
+        };
+        let removed = self.entities.remove(index);
+        self.entity_index.remove(&removed.id);
+        for (x, y) in self.object_tiles(&removed) {
+            if let Some(tile) = self.tiles.get_mut(x, y) {
+                tile.placeable = None;
+            }
+            self.belt_items.remove(&(x, y));
+        }
+        // Everything after the removed object moved down one index
+        for moved in index..self.entities.len() {
+            for (x, y) in self.object_tiles(&self.entities[moved]) {
+                if let Some(tile) = self.tiles.get_mut(x, y) {
+                    tile.placeable = Some(moved);
+                }
+            }
+            self.entity_index.insert(self.entities[moved].id, moved);
+        }
+    }
+
+    // Every tile a placed object covers
+    pub(crate) fn object_tiles(&self, obj: &PlaceableObject) -> Vec<(u32, u32)> {
+        self.footprint(obj.x, obj.y, obj.placeable_type(), obj.direction).unwrap_or_default()
+    }
+}
+
+// Native-only helpers (signatures wasm-bindgen can't export)
+impl Map {
+    // Tiles an object placed at (x, y) would cover (None if some are off the map).
+    // Splitters also take the tile to their right.
+    pub fn footprint(&self, x: u32, y: u32, placeable_type: PlaceableType, direction: Direction) -> Option<Vec<(u32, u32)>> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+        match placeable_type {
+            PlaceableType::Splitter => Some(vec![(x, y), self.neighbour(x, y, direction.rotated_clockwise())?]),
+            _ => Some(vec![(x, y)]),
+        }
+    }
+
+    // The single source of truth for where objects may go
+    pub fn can_place(&self, x: u32, y: u32, placeable_type: PlaceableType, direction: Direction) -> Result<(), PlacementError> {
+        let footprint = self.footprint(x, y, placeable_type, direction).ok_or(PlacementError::OutOfBounds)?;
+        for (x, y) in footprint {
+            let tile = self.tiles.get(x, y).ok_or(PlacementError::OutOfBounds)?;
+            if tile.water {
+                return Err(PlacementError::Water);
+            }
+            if tile.placeable.is_some() {
+                return Err(PlacementError::Occupied);
+            }
+            // Drills can be placed on resources, other items cannot
+            if !matches!(placeable_type, PlaceableType::Drill) && tile.resource.is_some() {
+                return Err(PlacementError::OnResource);
+            }
+        }
+        Ok(())
+    }
+
+    // Place a new entity, returning its id
+    pub fn add_placeable(&mut self, x: u32, y: u32, placeable_type: PlaceableType, direction: Direction) -> Result<EntityId, PlacementError> {
+        self.can_place(x, y, placeable_type, direction)?;
+        for (x, y) in self.footprint(x, y, placeable_type, direction).unwrap_or_default() {
+            if let Some(tile) = self.tiles.get_mut(x, y) {
+                tile.placeable = Some(self.entities.len());
+            }
+        }
+        // Ids are never reused, so they stay valid across removals and reloads
+        let id = EntityId(self.next_entity_id);
+        self.next_entity_id += 1;
+        self.entity_index.insert(id, self.entities.len());
+        self.entities.push(PlaceableObject {
+            id,
+            x,
+            y,
+            direction,
+            state: EntityState::new(placeable_type),
+        });
+        if placeable_type == PlaceableType::UndergroundBelt {
+            self.pair_underground_belt(x, y, direction);
+        }
+        Ok(id)
+    }
+
+    // Pair a new underground belt with the nearest unpaired underground belt facing the same way:
+    // one behind it makes the new belt its exit, otherwise one ahead of it makes the new belt its entry
+    fn pair_underground_belt(&mut self, x: u32, y: u32, direction: Direction) {
+        if let Some(entry) = self.unpaired_underground_towards(x, y, direction, direction.opposite()) {
+            self.pair_underground_ends(entry, (x, y));
+        } else if let Some(exit) = self.unpaired_underground_towards(x, y, direction, direction) {
+            self.pair_underground_ends((x, y), exit);
+        }
+    }
+
+    // The nearest underground belt facing `direction` within reach of (x, y) when looking towards `search`,
+    // if it's unpaired. Anything else, including paired underground belts facing another way, can be tunnelled under.
+    fn unpaired_underground_towards(&self, x: u32, y: u32, direction: Direction, search: Direction) -> Option<(u32, u32)> {
+        let (mut tile_x, mut tile_y) = (x, y);
+        for _ in 0..=UNDERGROUND_MAX_LENGTH {
+            (tile_x, tile_y) = self.neighbour(tile_x, tile_y, search)?;
+            let facing_same_way = self.get_placeable_object_at(tile_x, tile_y).is_some_and(|obj| obj.direction() == direction);
+            match self.underground_belt_at(tile_x, tile_y) {
+                Some(data) if facing_same_way => return (!data.is_paired()).then_some((tile_x, tile_y)),
+                _ => {}
+            }
+        }
+        None
+    }
+
+    fn pair_underground_ends(&mut self, entry: (u32, u32), exit: (u32, u32)) {
+        if let Some(data) = self.underground_belt_at_mut(entry.0, entry.1) {
+            data.pair(UndergroundRole::Entry, exit);
+        }
+        if let Some(data) = self.underground_belt_at_mut(exit.0, exit.1) {
+            data.pair(UndergroundRole::Exit, entry);
+        }
     }
 }
 
 // Dropped item struct - items that can be picked up from the ground
-#[wasm_bindgen]
-#[derive(Clone)]
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct DroppedItem {
     x: u32,
     y: u32,
     item: Item,
     quantity: u32,
+    #[serde(default)]
+    progress: u32, // Position along the belt tile it is on (unused on the ground)
+    #[serde(default)]
+    lane: BeltLane, // Belt lane it rides on (unused on the ground)
 }
 
-#[wasm_bindgen]
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
 impl DroppedItem {
-    #[wasm_bindgen(constructor)]
+    #[cfg_attr(feature = "wasm", wasm_bindgen(constructor))]
     pub fn new(x: u32, y: u32, item: Item, quantity: u32) -> DroppedItem {
-        DroppedItem { x, y, item, quantity }
+        DroppedItem { x, y, item, quantity, progress: 0, lane: BeltLane::default() }
     }
 
-    #[wasm_bindgen(getter)]
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
     pub fn x(&self) -> u32 {
         self.x
     }
 
-    #[wasm_bindgen(getter)]
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
     pub fn y(&self) -> u32 {
         self.y
     }
 
-    #[wasm_bindgen(getter)]
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
     pub fn item(&self) -> Item {
-        self.item.clone()
+        self.item
     }
 
-    #[wasm_bindgen(getter)]
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
     pub fn quantity(&self) -> u32 {
         self.quantity
     }
 
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn progress(&self) -> u32 {
+        self.progress
+    }
+
+    pub fn set_progress(&mut self, progress: u32) {
+        self.progress = progress;
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn lane(&self) -> BeltLane {
+        self.lane
+    }
+
+    pub fn set_lane(&mut self, lane: BeltLane) {
+        self.lane = lane;
+    }
+
     pub fn set_position(&mut self, x: u32, y: u32) {
         self.x = x;
         self.y = y;
@@ -417,28 +788,81 @@ impl DroppedItem {
 }
 
 // Keep IronOre for backward compatibility (deprecated, use Resource instead)
-#[wasm_bindgen]
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
 #[derive(Clone)]
 pub struct IronOre {
     x: u32,
     y: u32,
 }
 
-#[wasm_bindgen]
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
 impl IronOre {
-    #[wasm_bindgen(constructor)]
+    #[cfg_attr(feature = "wasm", wasm_bindgen(constructor))]
     pub fn new(x: u32, y: u32) -> IronOre {
         IronOre { x, y }
     }
 
-    #[wasm_bindgen(getter)]
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
     pub fn x(&self) -> u32 {
         self.x
     }
 
-    #[wasm_bindgen(getter)]
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
     pub fn y(&self) -> u32 {
         self.y
     }
 }
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn placement_errors_name_what_is_in_the_way() {
+        let mut map = Map::new(6, 4);
+        map.add_water_patch(WaterPatch::new(0, 0, 2, 2));
+        map.add_resource(Resource::new(4, 0, ResourceType::IronOre));
+        map.add_placeable(3, 3, PlaceableType::Chest, Direction::North).unwrap();
+
+        let error = |x, y, placeable_type| map.can_place(x, y, placeable_type, Direction::North).err();
+        assert_eq!(error(6, 0, PlaceableType::Belt), Some(PlacementError::OutOfBounds));
+        assert_eq!(error(1, 1, PlaceableType::Belt), Some(PlacementError::Water));
+        assert_eq!(error(4, 0, PlaceableType::Belt), Some(PlacementError::OnResource));
+        assert_eq!(error(3, 3, PlaceableType::Belt), Some(PlacementError::Occupied));
+        assert_eq!(error(4, 0, PlaceableType::Drill), None);
+        // A splitter facing north also covers the tile to its east
+        assert_eq!(error(5, 2, PlaceableType::Splitter), Some(PlacementError::OutOfBounds));
+        assert_eq!(error(2, 3, PlaceableType::Splitter), Some(PlacementError::Occupied));
+
+        assert_eq!(map.add_placeable(1, 1, PlaceableType::Belt, Direction::North), Err(PlacementError::Water));
+        assert_eq!(map.placeable_objects_ref().len(), 1);
+    }
+
+    #[test]
+    fn underground_belts_pair_whichever_end_is_placed_first() {
+        let mut map = Map::new(12, 3);
+        let role = |map: &Map, x| map.underground_belt_at(x, 1).map(|data| (data.role(), data.partner()));
+
+        // Entry first, then the exit
+        map.add_placeable(0, 1, PlaceableType::UndergroundBelt, Direction::East).unwrap();
+        map.add_placeable(3, 1, PlaceableType::UndergroundBelt, Direction::East).unwrap();
+        assert_eq!(role(&map, 0), Some((UndergroundRole::Entry, Some((3, 1)))));
+        assert_eq!(role(&map, 3), Some((UndergroundRole::Exit, Some((0, 1)))));
+
+        // Exit first, then the entry
+        map.add_placeable(9, 1, PlaceableType::UndergroundBelt, Direction::East).unwrap();
+        map.add_placeable(6, 1, PlaceableType::UndergroundBelt, Direction::East).unwrap();
+        assert_eq!(role(&map, 6), Some((UndergroundRole::Entry, Some((9, 1)))));
+        assert_eq!(role(&map, 9), Some((UndergroundRole::Exit, Some((6, 1)))));
+
+        // Too far apart, or facing the other way, they stay unpaired
+        map.add_placeable(0, 0, PlaceableType::UndergroundBelt, Direction::East).unwrap();
+        map.add_placeable(6, 0, PlaceableType::UndergroundBelt, Direction::East).unwrap();
+        map.add_placeable(0, 2, PlaceableType::UndergroundBelt, Direction::East).unwrap();
+        map.add_placeable(3, 2, PlaceableType::UndergroundBelt, Direction::West).unwrap();
+        for (x, y) in [(0, 0), (6, 0), (0, 2), (3, 2)] {
+            assert!(!map.underground_belt_at(x, y).unwrap().is_paired(), "{:?}", (x, y));
+        }
+    }
+}
@@ -1,3 +1,4 @@
+use serde::{Serialize, Deserialize};
 use std::collections::HashSet;
 use crate::map::{Map, WaterPatch, Resource};
 use crate::types::ResourceType;
@@ -8,9 +9,7 @@ pub struct SimpleRng {
 }
 
 impl SimpleRng {
-    pub fn new() -> Self {
-        // Use current time as seed (approximated)
-        let seed = js_sys::Date::now() as u64;
+    pub fn new(seed: u64) -> Self {
         SimpleRng { seed }
     }
 
@@ -19,39 +18,155 @@ impl SimpleRng {
         self.seed = self.seed.wrapping_mul(1103515245).wrapping_add(12345);
         self.seed
     }
+
+    // Random value in the inclusive range (min, max)
+    pub fn range(&mut self, (min, max): (u32, u32)) -> u32 {
+        if max <= min {
+            return min;
+        }
+        min + (self.next() % ((max - min) as u64 + 1)) as u32
+    }
+}
+
+// Turn a shared seed string into a numeric seed.
+// Numeric strings map to themselves so a displayed seed can be typed back in.
+pub fn seed_from_str(seed: &str) -> u64 {
+    let seed = seed.trim();
+    if let Ok(value) = seed.parse::<u64>() {
+        return value;
+    }
+    // FNV-1a
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in seed.bytes() {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    hash
+}
+
+// Largest map side and patch count generation will use, so a bad config can't exhaust memory or time
+pub const MAX_MAP_SIDE: u32 = 1024;
+pub const MAX_PATCH_COUNT: u32 = 1000;
+
+// Patch settings for one resource type
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ResourcePatchConfig {
+    pub resource_type: ResourceType,
+    pub patch_count: (u32, u32), // Inclusive range of patches on the map
+    pub patch_size: (u32, u32),  // Inclusive range of patch width and height in tiles
+}
+
+// Everything that determines a generated map - the same config always produces the same map
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct GenerationConfig {
+    pub seed: u64,
+    pub width: u32,
+    pub height: u32,
+    pub water_density: f64, // Rough fraction of the map covered by water
+    pub water_patch_size: (u32, u32),
+    pub resources: Vec<ResourcePatchConfig>,
+}
+
+impl GenerationConfig {
+    // Default 160x120 world with the given seed
+    pub fn new(seed: u64) -> GenerationConfig {
+        let resource = |resource_type| ResourcePatchConfig {
+            resource_type,
+            patch_count: (5, 10),
+            patch_size: (1, 4),
+        };
+        GenerationConfig {
+            seed,
+            width: 160,
+            height: 120,
+            water_density: 0.007,
+            water_patch_size: (1, 5),
+            resources: vec![
+                resource(ResourceType::IronOre),
+                resource(ResourceType::Copper),
+                resource(ResourceType::Stone),
+                resource(ResourceType::Coal),
+            ],
+        }
+    }
+
+    pub fn from_seed_str(seed: &str) -> GenerationConfig {
+        GenerationConfig::new(seed_from_str(seed))
+    }
+
+    // The config generate_map actually uses: sides and patch counts capped, size ranges at least 1
+    // and in order, and a water density between 0 and 1 (0 if it isn't a number)
+    pub fn clamped(&self) -> GenerationConfig {
+        let size_range = |(min, max): (u32, u32)| {
+            let min = min.clamp(1, MAX_MAP_SIDE);
+            (min, max.clamp(min, MAX_MAP_SIDE))
+        };
+        let count_range = |(min, max): (u32, u32)| {
+            let min = min.min(MAX_PATCH_COUNT);
+            (min, max.clamp(min, MAX_PATCH_COUNT))
+        };
+        GenerationConfig {
+            seed: self.seed,
+            width: self.width.min(MAX_MAP_SIDE),
+            height: self.height.min(MAX_MAP_SIDE),
+            water_density: if self.water_density.is_finite() { self.water_density.clamp(0.0, 1.0) } else { 0.0 },
+            water_patch_size: size_range(self.water_patch_size),
+            resources: self
+                .resources
+                .iter()
+                .map(|resource| ResourcePatchConfig {
+                    resource_type: resource.resource_type,
+                    patch_count: count_range(resource.patch_count),
+                    patch_size: size_range(resource.patch_size),
+                })
+                .collect(),
+        }
+    }
+
+    // Number of water patches needed to roughly reach the configured density
+    fn water_patch_count(&self) -> usize {
+        let (min, max) = self.water_patch_size;
+        let mean_side = (min as f64 + max as f64) / 2.0;
+        let tiles = self.width as f64 * self.height as f64;
+        ((self.water_density * tiles / (mean_side * mean_side)).round() as usize).min(MAX_PATCH_COUNT as usize)
+    }
 }
 
-// Generate map function
-pub fn generate_map() -> Map {
-    // Map size in tiles (160x120 tiles - 4x bigger than 40x30)
-    let map_width: u32 = 160;
-    let map_height: u32 = 120;
-    
-    let mut rng = SimpleRng::new();
+// Generate map function - the same config always produces the same map
+pub fn generate_map(config: &GenerationConfig) -> Map {
+    let config = &config.clamped();
+    let map_width = config.width;
+    let map_height = config.height;
+
+    let mut rng = SimpleRng::new(config.seed);
     let mut used_positions = HashSet::new();
-    
+
     let mut map = Map::new(map_width, map_height);
-    
+    if map_width == 0 || map_height == 0 {
+        return map;
+    }
+
     // Generate water patches procedurally
-    let num_patches = 10 + (rng.next() % 11) as usize; // 10-20 water patches
-    
+    let water_size = config.water_patch_size;
+    let num_patches = config.water_patch_count();
+
     for _ in 0..num_patches {
         let mut attempts = 0;
         loop {
             let x = (rng.next() % map_width as u64) as u32;
             let y = (rng.next() % map_height as u64) as u32;
-            let width = 1 + (rng.next() % 5) as u32; // 1x1 to 5x5 tiles
-            let height = 1 + (rng.next() % 5) as u32;
-            
-            // Make sure water patch fits within map bounds
-            if x + width > map_width || y + height > map_height {
+            let width = rng.range(water_size);
+            let height = rng.range(water_size);
+
+            // Make sure water patch fits within map bounds (in u64 so huge sizes can't wrap)
+            if x as u64 + width as u64 > map_width as u64 || y as u64 + height as u64 > map_height as u64 {
                 attempts += 1;
                 if attempts > 50 {
                     break;
                 }
                 continue;
             }
-            
+
             // Check if position overlaps significantly
             let mut overlaps = false;
             for used in &used_positions {
@@ -61,7 +176,7 @@ pub fn generate_map() -> Map {
                     break;
                 }
             }
-            
+
             if !overlaps || attempts > 50 {
                 map.add_water_patch(WaterPatch::new(x, y, width, height));
                 used_positions.insert((x, y));
@@ -70,36 +185,30 @@ pub fn generate_map() -> Map {
             attempts += 1;
         }
     }
-    
-    // Generate resources (iron ore, copper, stone, coal) as patches, not on water
-    let resource_types = [
-        ResourceType::IronOre,
-        ResourceType::Copper,
-        ResourceType::Stone,
-        ResourceType::Coal,
-    ];
-    
-    // Generate 5-10 patches of each resource type
-    for resource_type in resource_types.iter() {
-        let num_patches = 5 + (rng.next() % 6) as usize; // 5-10 patches of each type
-        
+
+    // Generate resources as patches, not on water
+    for resource_config in &config.resources {
+        let resource_type = resource_config.resource_type;
+        let patch_size = resource_config.patch_size;
+        let num_patches = rng.range(resource_config.patch_count) as usize;
+
         for _ in 0..num_patches {
             let mut attempts = 0;
             loop {
                 let x = (rng.next() % map_width as u64) as u32;
                 let y = (rng.next() % map_height as u64) as u32;
-                let width = 1 + (rng.next() % 4) as u32; // 1x1 to 4x4 tiles
-                let height = 1 + (rng.next() % 4) as u32;
-                
-                // Make sure resource patch fits within map bounds
-                if x + width > map_width || y + height > map_height {
+                let width = rng.range(patch_size);
+                let height = rng.range(patch_size);
+
+                // Make sure resource patch fits within map bounds (in u64 so huge sizes can't wrap)
+                if x as u64 + width as u64 > map_width as u64 || y as u64 + height as u64 > map_height as u64 {
                     attempts += 1;
                     if attempts > 50 {
                         break;
                     }
                     continue;
                 }
-                
+
                 // Check if patch overlaps with water
                 let mut overlaps_water = false;
                 for patch in map.water_patches() {
@@ -112,7 +221,7 @@ pub fn generate_map() -> Map {
                         break;
                     }
                 }
-                
+
                 // Check if patch overlaps significantly with existing resources
                 let mut overlaps_resource = false;
                 for used in &used_positions {
@@ -122,7 +231,7 @@ pub fn generate_map() -> Map {
                         break;
                     }
                 }
-                
+
                 if !overlaps_water && (!overlaps_resource || attempts > 50) {
                     // Add resources for each tile in the patch
                     for patch_x in x..(x + width) {
@@ -138,14 +247,14 @@ pub fn generate_map() -> Map {
                                 }
                             }
                             if !on_water {
-                                map.add_resource(Resource::new(patch_x, patch_y, *resource_type));
+                                map.add_resource(Resource::new(patch_x, patch_y, resource_type));
                             }
                         }
                     }
                     used_positions.insert((x, y));
                     break;
                 }
-                
+
                 attempts += 1;
                 if attempts > 50 {
                     break;
@@ -153,7 +262,89 @@ pub fn generate_map() -> Map {
             }
         }
     }
-    
+
     map
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game_state::GameState;
+    use crate::action::Action;
+    use crate::types::{Direction, Item};
+
+    #[test]
+    fn tiny_maps_survive_every_action() {
+        for (width, height) in [(0, 0), (1, 1), (0, 5), (5, 0)] {
+            let config = GenerationConfig { width, height, ..GenerationConfig::new(1) };
+            let mut game_state = GameState::with_config(&config);
+            for direction in [Direction::North, Direction::South, Direction::East, Direction::West] {
+                game_state.step(&[Action::Move(direction)]);
+                game_state.step(&[
+                    Action::Mine,
+                    Action::Place { item: Item::Belt, x: 0, y: 0, direction },
+                    Action::PlaceLine { item: Item::Belt, from_x: 0, from_y: 0, to_x: 3, to_y: 3 },
+                    Action::Drop { item: Item::IronOre, x: 0, y: 0 },
+                    Action::Pickup { x: 0, y: 0 },
+                    Action::Rotate { x: 0, y: 0 },
+                    Action::Deconstruct { x: 0, y: 0 },
+                ]);
+                for _ in 0..10 {
+                    game_state.step(&[]);
+                }
+            }
+            assert_eq!((game_state.map_width(), game_state.map_height()), (width, height));
+        }
+    }
+
+    #[test]
+    fn the_same_config_generates_the_same_map() {
+        for seed in [0, 1, 0x5EED, u64::MAX] {
+            let config = GenerationConfig::new(seed);
+            assert_eq!(GameState::with_config(&config).save(), GameState::with_config(&config).save());
+        }
+        assert_eq!(GenerationConfig::from_seed_str("factory"), GenerationConfig::new(seed_from_str("factory")));
+
+        let resource_tiles = |seed| {
+            let map = generate_map(&GenerationConfig::new(seed));
+            map.resources().iter().map(|resource| (resource.x(), resource.y())).collect::<Vec<_>>()
+        };
+        assert_ne!(resource_tiles(1), resource_tiles(2));
+    }
+
+    #[test]
+    fn extreme_configs_are_clamped_instead_of_overflowing() {
+        let mut rng = SimpleRng::new(7);
+        for _ in 0..100 {
+            rng.range((0, u32::MAX));
+            assert!(rng.range((u32::MAX - 1, u32::MAX)) >= u32::MAX - 1);
+        }
+
+        let wild_resources = |patch_count, patch_size| {
+            GenerationConfig::new(1)
+                .resources
+                .into_iter()
+                .map(|resource| ResourcePatchConfig { patch_count, patch_size, ..resource })
+                .collect::<Vec<_>>()
+        };
+        for water_density in [f64::NAN, f64::INFINITY, f64::NEG_INFINITY, -1.0, 1e300] {
+            for (patch_count, patch_size) in [((0, u32::MAX), (0, u32::MAX)), ((u32::MAX, 0), (u32::MAX, u32::MAX))] {
+                let config = GenerationConfig {
+                    width: 40,
+                    height: u32::MAX,
+                    water_density,
+                    water_patch_size: (0, u32::MAX),
+                    resources: wild_resources(patch_count, patch_size),
+                    ..GenerationConfig::new(1)
+                };
+                let clamped = config.clamped();
+                assert!((0.0..=1.0).contains(&clamped.water_density));
+                assert!(clamped.water_patch_count() <= MAX_PATCH_COUNT as usize);
+
+                let map = generate_map(&config);
+                assert_eq!((map.width(), map.height()), (40, MAX_MAP_SIDE));
+                assert!(map.water_patches().len() <= MAX_PATCH_COUNT as usize);
+            }
+        }
+    }
+}
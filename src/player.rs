@@ -1,34 +1,42 @@
+#[cfg(feature = "wasm")]
 use wasm_bindgen::prelude::*;
+use std::collections::VecDeque;
+use serde::{Serialize, Deserialize};
 use crate::types::{Item, Direction};
 use crate::inventory::Inventory;
+use crate::crafting::CraftJob;
 
 // Player struct
-#[wasm_bindgen]
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Serialize, Deserialize)]
 pub struct Player {
     x: u32,
     y: u32,
     direction: Direction,
     inventory: Inventory,
+    #[serde(default)]
+    crafting_queue: VecDeque<CraftJob>,
 }
 
-#[wasm_bindgen]
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
 impl Player {
-    #[wasm_bindgen(constructor)]
+    #[cfg_attr(feature = "wasm", wasm_bindgen(constructor))]
     pub fn new(x: u32, y: u32) -> Player {
         Player {
             x,
             y,
             direction: Direction::South,
             inventory: Inventory::new(),
+            crafting_queue: VecDeque::new(),
         }
     }
 
-    #[wasm_bindgen(getter)]
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
     pub fn x(&self) -> u32 {
         self.x
     }
 
-    #[wasm_bindgen(getter)]
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
     pub fn y(&self) -> u32 {
         self.y
     }
@@ -38,19 +46,16 @@ impl Player {
         self.y = y;
     }
 
-    pub fn add_to_inventory(&mut self, item: Item) {
-        self.inventory.add_item(item);
-    }
-
-    pub fn get_iron_ore_count(&self) -> u32 {
-        self.inventory.count_iron_ore()
+    // Returns false if the inventory is full
+    pub fn add_to_inventory(&mut self, item: Item) -> bool {
+        self.inventory.add_item(item)
     }
 
     pub fn set_direction(&mut self, direction: Direction) {
         self.direction = direction;
     }
 
-    #[wasm_bindgen(getter)]
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
     pub fn direction(&self) -> Direction {
         self.direction
     }
@@ -67,5 +72,13 @@ impl Player {
     pub(crate) fn inventory_mut(&mut self) -> &mut Inventory {
         &mut self.inventory
     }
+
+    pub(crate) fn crafting_queue(&self) -> &VecDeque<CraftJob> {
+        &self.crafting_queue
+    }
+
+    pub(crate) fn crafting_queue_mut(&mut self) -> &mut VecDeque<CraftJob> {
+        &mut self.crafting_queue
+    }
 }
 
@@ -0,0 +1,214 @@
+use serde::{Serialize, Deserialize};
+use crate::action::InputFrame;
+use crate::game_state::GameState;
+use crate::map_generation::GenerationConfig;
+use crate::save::SaveError;
+
+// Current replay format version - bump it whenever Action or InputFrame change shape
+pub const REPLAY_FORMAT_VERSION: u32 = 8;
+
+// Binary replays start with this magic, followed by the version (u32 little endian) and a MessagePack payload
+const REPLAY_MAGIC: &[u8; 4] = b"FRPL";
+
+// Recorded inputs of a session, one frame per tick, plus what is needed to rebuild the starting world
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Replay {
+    config: GenerationConfig,
+    // Binary save of the starting state (None = fresh world from config)
+    initial_save: Option<Vec<u8>>,
+    frames: Vec<InputFrame>,
+}
+
+impl Replay {
+    pub(crate) fn new(config: GenerationConfig, initial_save: Option<Vec<u8>>) -> Replay {
+        Replay {
+            config,
+            initial_save,
+            frames: Vec::new(),
+        }
+    }
+
+    pub(crate) fn push_frame(&mut self, frame: InputFrame) {
+        self.frames.push(frame);
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.config.seed
+    }
+
+    pub fn config(&self) -> &GenerationConfig {
+        &self.config
+    }
+
+    pub fn frames(&self) -> &[InputFrame] {
+        &self.frames
+    }
+
+    // Number of recorded ticks
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    // The game as it was when recording started
+    pub fn initial_state(&self) -> Result<GameState, SaveError> {
+        match &self.initial_save {
+            Some(bytes) => GameState::load(bytes),
+            None => Ok(GameState::with_config(&self.config)),
+        }
+    }
+
+    // Rebuild the starting state and play every recorded tick
+    pub fn play(&self) -> Result<GameState, SaveError> {
+        let mut game_state = self.initial_state()?;
+        for frame in &self.frames {
+            game_state.step_frame(frame);
+        }
+        Ok(game_state)
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = REPLAY_MAGIC.to_vec();
+        bytes.extend_from_slice(&REPLAY_FORMAT_VERSION.to_le_bytes());
+        let payload = rmp_serde::to_vec_named(self).expect("replays are always serializable");
+        bytes.extend_from_slice(&payload);
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Replay, SaveError> {
+        if bytes.len() < 8 || &bytes[0..4] != REPLAY_MAGIC {
+            return Err(SaveError::InvalidHeader);
+        }
+        let version = u32::from_le_bytes([bytes[4], bytes[5], bytes[6], bytes[7]]);
+        if version != REPLAY_FORMAT_VERSION {
+            return Err(SaveError::UnsupportedVersion(version));
+        }
+        rmp_serde::from_slice(&bytes[8..]).map_err(|e| SaveError::Corrupt(e.to_string()))
+    }
+
+    // Human-readable variant of to_bytes(), handy for regression test fixtures
+    pub fn to_json(&self) -> String {
+        let envelope = serde_json::json!({
+            "version": REPLAY_FORMAT_VERSION,
+            "replay": self,
+        });
+        serde_json::to_string_pretty(&envelope).expect("replays are always serializable")
+    }
+
+    pub fn from_json(json: &str) -> Result<Replay, SaveError> {
+        let mut envelope: serde_json::Value = serde_json::from_str(json).map_err(|e| SaveError::Corrupt(e.to_string()))?;
+        let version = envelope
+            .get("version")
+            .and_then(serde_json::Value::as_u64)
+            .ok_or(SaveError::InvalidHeader)? as u32;
+        if version != REPLAY_FORMAT_VERSION {
+            return Err(SaveError::UnsupportedVersion(version));
+        }
+        let replay = envelope.get_mut("replay").map(serde_json::Value::take).ok_or(SaveError::InvalidHeader)?;
+        serde_json::from_value(replay).map_err(|e| SaveError::Corrupt(e.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::action::Action;
+    use crate::keymap::KeyState;
+    use crate::types::{Item, PlaceableType};
+
+    // A belt line next to the player with iron ore dropped on it, then ticks for it to move
+    fn belt_session(game_state: &GameState) -> Vec<Vec<Action>> {
+        let (x, y) = (game_state.player_x() + 1, game_state.player_y() + 2);
+        let mut session = vec![
+            vec![Action::PlaceLine { item: Item::Belt, from_x: x, from_y: y, to_x: x + 5, to_y: y }],
+            vec![Action::Drop { item: Item::IronOre, x, y }],
+        ];
+        session.extend((0..20).map(|_| Vec::new()));
+        session
+    }
+
+    #[test]
+    fn changes_made_before_recording_are_played_back() {
+        let mut game_state = GameState::new();
+        game_state.step(&[Action::SetBeltSpeed(20)]);
+        game_state.start_recording();
+        for actions in belt_session(&game_state) {
+            game_state.step(&actions);
+        }
+        let replay = game_state.stop_recording().unwrap();
+
+        let played = replay.play().unwrap();
+        assert_eq!(played.belt_speed(), 20);
+        assert_eq!(played.save(), game_state.save());
+    }
+
+    #[test]
+    fn belt_speed_changes_are_recorded() {
+        let mut game_state = GameState::new();
+        game_state.start_recording();
+        game_state.set_belt_speed(20);
+        assert_ne!(game_state.belt_speed(), 20);
+        game_state.next_step(&KeyState::new(), None, None);
+        let replay = game_state.stop_recording().unwrap();
+
+        assert_eq!(game_state.belt_speed(), 20);
+        assert_eq!(replay.frames()[0].actions, vec![Action::SetBeltSpeed(20)]);
+        assert_eq!(replay.play().unwrap().belt_speed(), 20);
+    }
+
+    #[test]
+    fn queued_lines_are_recorded() {
+        let mut game_state = GameState::new();
+        let (x, y) = (game_state.player_x() + 1, game_state.player_y() + 2);
+        game_state.start_recording();
+        game_state.queue_place_line(Item::Belt, x, y, x + 3, y);
+        game_state.next_step(&KeyState::new(), None, None);
+        let replay = game_state.stop_recording().unwrap();
+
+        assert_eq!(game_state.entities_of_type(PlaceableType::Belt).len(), 4);
+        assert_eq!(replay.play().unwrap().save(), game_state.save());
+    }
+
+    fn recorded_session() -> (GameState, Replay) {
+        let mut game_state = GameState::new();
+        game_state.start_recording();
+        for actions in belt_session(&game_state) {
+            game_state.step(&actions);
+        }
+        // Key input goes through next_step, cursor included
+        let mut keys = KeyState::new();
+        keys.press("d");
+        for _ in 0..10 {
+            game_state.next_step(&keys, Some(3), Some(4));
+        }
+        let replay = game_state.stop_recording().unwrap();
+        (game_state, replay)
+    }
+
+    #[test]
+    fn replays_round_trip_and_play_back_the_game() {
+        let (game_state, replay) = recorded_session();
+        assert_eq!(replay.len(), 32);
+        assert_eq!(Replay::from_bytes(&replay.to_bytes()).unwrap(), replay);
+        assert_eq!(Replay::from_json(&replay.to_json()).unwrap(), replay);
+
+        let played = Replay::from_bytes(&replay.to_bytes()).unwrap().play().unwrap();
+        assert_eq!(played.save(), game_state.save());
+        assert_eq!(played.current_tick(), game_state.current_tick());
+    }
+
+    #[test]
+    fn replays_from_other_versions_are_rejected() {
+        let (_, replay) = recorded_session();
+        let mut bytes = replay.to_bytes();
+        bytes[4..8].copy_from_slice(&(REPLAY_FORMAT_VERSION - 1).to_le_bytes());
+        assert!(matches!(Replay::from_bytes(&bytes), Err(SaveError::UnsupportedVersion(v)) if v == REPLAY_FORMAT_VERSION - 1));
+
+        let json = replay.to_json().replacen(&format!("\"version\": {}", REPLAY_FORMAT_VERSION), "\"version\": 1", 1);
+        assert!(matches!(Replay::from_json(&json), Err(SaveError::UnsupportedVersion(1))));
+        assert!(matches!(Replay::from_bytes(b"FSAV"), Err(SaveError::InvalidHeader)));
+    }
+}
@@ -0,0 +1,489 @@
+use std::fmt;
+use serde_json::Value;
+use crate::game_state::GameState;
+use crate::inventory::INVENTORY_SLOTS;
+use crate::types::Item;
+use crate::furnace::FurnaceData;
+use crate::arm::ArmData;
+use crate::chest::ChestData;
+use crate::drill::DrillData;
+use crate::fuel::FuelStore;
+use crate::belt::{BELT_INSERT_PROGRESS, DEFAULT_BELT_SPEED};
+
+// Current save format version - bump it and add a migration when the saved layout changes
+pub const SAVE_FORMAT_VERSION: u32 = 7;
+
+// Binary saves start with this magic, followed by the version (u32 little endian) and a MessagePack payload
+const BINARY_MAGIC: &[u8; 4] = b"FSAV";
+
+// Migration from version N to N + 1 lives at index N - 1.
+// Migrations work on the untyped value tree, so they can rename fields or rewrite items
+// that no longer exist before the current types see the data.
+type Migration = fn(Value) -> Result<Value, SaveError>;
+const MIGRATIONS: &[Migration] = &[migrate_v1_inventory_slots, migrate_v2_entity_store, migrate_v3_belt_lists, migrate_v4_fuel_stores, migrate_v5_furnace_slots, migrate_v6_production_stats];
+
+// v1 -> v2: the player inventory went from one list entry per item to slots of stacks.
+// Stacks are laid out in registry order; extra slots are added if an old save held more than fits.
+fn migrate_v1_inventory_slots(mut value: Value) -> Result<Value, SaveError> {
+    let inventory = value
+        .pointer_mut("/player/inventory")
+        .ok_or_else(|| SaveError::Corrupt("missing player inventory".to_string()))?;
+    let items: Vec<Item> = serde_json::from_value(inventory.get("items").cloned().unwrap_or_default())
+        .map_err(|e| SaveError::Corrupt(e.to_string()))?;
+
+    let mut slots = Vec::new();
+    for item in Item::all() {
+        let mut count = items.iter().filter(|held| **held == item).count() as u32;
+        while count > 0 {
+            let stack = count.min(item.stack_size());
+            slots.push(serde_json::json!({ "item": item, "count": stack }));
+            count -= stack;
+        }
+    }
+    slots.resize(slots.len().max(INVENTORY_SLOTS), Value::Null);
+
+    *inventory = serde_json::json!({ "slots": slots });
+    Ok(value)
+}
+
+// v2 -> v3: placeable_objects plus the per-type data lists keyed by position became one entity store.
+// Entities get ids in their old placement order; objects missing their data get fresh state.
+fn migrate_v2_entity_store(mut value: Value) -> Result<Value, SaveError> {
+    let map = value
+        .get_mut("map")
+        .and_then(Value::as_object_mut)
+        .ok_or_else(|| SaveError::Corrupt("missing map".to_string()))?;
+
+    let mut take_data = |key: &str| -> Vec<(Value, Value)> {
+        match map.remove(key) {
+            Some(Value::Array(entries)) => entries
+                .into_iter()
+                .filter_map(|entry| match entry {
+                    Value::Array(mut pair) if pair.len() == 2 => {
+                        let data = pair.pop()?;
+                        Some((pair.pop()?, data))
+                    }
+                    _ => None,
+                })
+                .collect(),
+            _ => Vec::new(),
+        }
+    };
+    let furnaces = take_data("furnace_data");
+    let arms = take_data("arm_data");
+    let chests = take_data("chest_data");
+    let drills = take_data("drill_data");
+    let objects = match map.remove("placeable_objects") {
+        Some(Value::Array(objects)) => objects,
+        _ => Vec::new(),
+    };
+
+    // The saved data for the object at a position, or fresh state if there is none
+    fn data_at<T: serde::Serialize>(list: &[(Value, Value)], position: &Value, fresh: T) -> Value {
+        list.iter()
+            .find(|(at, _)| at == position)
+            .map(|(_, data)| data.clone())
+            .unwrap_or_else(|| serde_json::to_value(fresh).expect("entity data is always serializable"))
+    }
+
+    let mut entities = Vec::new();
+    for (id, object) in objects.into_iter().enumerate() {
+        let Value::Object(mut object) = object else {
+            return Err(SaveError::Corrupt("placeable object is not a map".to_string()));
+        };
+        let position = serde_json::json!([object.get("x"), object.get("y")]);
+        let state = match object.remove("placeable_type").as_ref().and_then(Value::as_str) {
+            Some("Furnace") => serde_json::json!({ "Furnace": data_at(&furnaces, &position, FurnaceData::new()) }),
+            Some("Belt") => Value::from("Belt"),
+            Some("Arm") => serde_json::json!({ "Arm": data_at(&arms, &position, ArmData::new()) }),
+            Some("Chest") => serde_json::json!({ "Chest": data_at(&chests, &position, ChestData::new()) }),
+            Some("Drill") => serde_json::json!({ "Drill": data_at(&drills, &position, DrillData::new()) }),
+            other => return Err(SaveError::Corrupt(format!("unknown placeable type {:?}", other))),
+        };
+        object.insert("id".to_string(), Value::from(id));
+        object.insert("state".to_string(), state);
+        entities.push(Value::Object(object));
+    }
+
+    map.insert("next_entity_id".to_string(), Value::from(entities.len()));
+    map.insert("entities".to_string(), Value::Array(entities));
+    Ok(value)
+}
+
+// v3 -> v4: belt tiles went from one item to a list of items with positions along the tile.
+// Old belt items are placed in the middle of their tile; belts move at the default speed.
+fn migrate_v3_belt_lists(mut value: Value) -> Result<Value, SaveError> {
+    let map = value
+        .get_mut("map")
+        .and_then(Value::as_object_mut)
+        .ok_or_else(|| SaveError::Corrupt("missing map".to_string()))?;
+
+    if let Some(Value::Array(entries)) = map.get_mut("belt_items") {
+        for entry in entries.iter_mut() {
+            let Some(Value::Object(mut item)) = entry.get_mut(1).map(Value::take) else {
+                return Err(SaveError::Corrupt("belt item is not a map".to_string()));
+            };
+            item.insert("progress".to_string(), Value::from(BELT_INSERT_PROGRESS));
+            entry[1] = Value::Array(vec![Value::Object(item)]);
+        }
+    }
+    map.insert("belt_speed".to_string(), Value::from(DEFAULT_BELT_SPEED));
+    Ok(value)
+}
+
+// v4 -> v5: furnaces and drills went from a coal count to a fuel store that takes any fuel.
+// Entities migrated from v2 already have fresh fuel stores, so only coal counts are rewritten.
+fn migrate_v4_fuel_stores(mut value: Value) -> Result<Value, SaveError> {
+    let entities = match value.pointer_mut("/map/entities") {
+        Some(Value::Array(entities)) => entities,
+        _ => return Ok(value),
+    };
+    for entity in entities.iter_mut() {
+        for machine in ["Furnace", "Drill"] {
+            let Some(Value::Object(data)) = entity.pointer_mut(&format!("/state/{}", machine)) else {
+                continue;
+            };
+            if let Some(coal_count) = data.remove("coal_count") {
+                let coal_count = coal_count.as_u64().ok_or_else(|| SaveError::Corrupt("coal count is not a number".to_string()))?;
+                let fuel = FuelStore::coal(coal_count as u32);
+                data.insert("fuel".to_string(), serde_json::to_value(fuel).expect("fuel stores are always serializable"));
+            }
+        }
+    }
+    Ok(value)
+}
+
+// v5 -> v6: furnaces went from fixed iron and copper counts to input and output lists keyed by item.
+// Entities migrated from v2 already have the new layout, so only furnaces with the old counts are rewritten.
+fn migrate_v5_furnace_slots(mut value: Value) -> Result<Value, SaveError> {
+    let entities = match value.pointer_mut("/map/entities") {
+        Some(Value::Array(entities)) => entities,
+        _ => return Ok(value),
+    };
+    for entity in entities.iter_mut() {
+        let Some(Value::Object(data)) = entity.pointer_mut("/state/Furnace") else {
+            continue;
+        };
+        if !data.contains_key("processing_type") {
+            continue;
+        }
+        let mut slots = |fields: [(&str, Item); 2]| -> Result<Value, SaveError> {
+            let mut slots = Vec::new();
+            for (field, item) in fields {
+                let count = data.remove(field).unwrap_or_else(|| Value::from(0));
+                let count = count.as_u64().ok_or_else(|| SaveError::Corrupt(format!("{} is not a number", field)))?;
+                if count > 0 {
+                    slots.push(serde_json::json!([item, count]));
+                }
+            }
+            Ok(Value::Array(slots))
+        };
+        let inputs = slots([("iron_ore_count", Item::IronOre), ("copper_count", Item::Copper)])?;
+        let outputs = slots([("iron_plate_count", Item::IronPlate), ("copper_plate_count", Item::CopperPlate)])?;
+        let smelting = match data.remove("processing_type").as_ref().and_then(Value::as_str) {
+            Some("IronPlate") => serde_json::json!(Item::IronOre),
+            Some("CopperPlate") => serde_json::json!(Item::Copper),
+            _ => Value::Null,
+        };
+        data.insert("inputs".to_string(), inputs);
+        data.insert("outputs".to_string(), outputs);
+        data.insert("smelting".to_string(), smelting);
+    }
+    Ok(value)
+}
+
+// v6 -> v7: production statistics are saved too. Older saves didn't keep them, so they start empty
+// at the saved tick.
+fn migrate_v6_production_stats(mut value: Value) -> Result<Value, SaveError> {
+    let game = value
+        .as_object_mut()
+        .ok_or_else(|| SaveError::Corrupt("game is not a map".to_string()))?;
+    let current_tick = game.get("current_tick").cloned().unwrap_or_else(|| Value::from(0));
+    game.insert("production".to_string(), serde_json::json!({ "current_tick": current_tick, "items": [] }));
+    Ok(value)
+}
+
+#[derive(Debug)]
+pub enum SaveError {
+    // Not a save file (binary magic or JSON envelope missing)
+    InvalidHeader,
+    // Saved by a newer version of the game
+    UnsupportedVersion(u32),
+    // The payload could not be decoded
+    Corrupt(String),
+}
+
+impl fmt::Display for SaveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SaveError::InvalidHeader => write!(f, "not a save file"),
+            SaveError::UnsupportedVersion(version) => write!(
+                f,
+                "save format version {} is newer than supported version {}",
+                version, SAVE_FORMAT_VERSION
+            ),
+            SaveError::Corrupt(message) => write!(f, "corrupt save: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for SaveError {}
+
+// Encode the game as FSAV + version + MessagePack (field names included so new fields can be defaulted)
+pub fn save_binary(game_state: &GameState) -> Vec<u8> {
+    let mut bytes = BINARY_MAGIC.to_vec();
+    bytes.extend_from_slice(&SAVE_FORMAT_VERSION.to_le_bytes());
+    let payload = rmp_serde::to_vec_named(game_state).expect("game state is always serializable");
+    bytes.extend_from_slice(&payload);
+    bytes
+}
+
+pub fn load_binary(bytes: &[u8]) -> Result<GameState, SaveError> {
+    if bytes.len() < 8 || &bytes[0..4] != BINARY_MAGIC {
+        return Err(SaveError::InvalidHeader);
+    }
+    let version = u32::from_le_bytes([bytes[4], bytes[5], bytes[6], bytes[7]]);
+    let value: Value = rmp_serde::from_slice(&bytes[8..]).map_err(|e| SaveError::Corrupt(e.to_string()))?;
+    decode(version, value)
+}
+
+// Encode the game as { "version": N, "game": { ... } }
+pub fn save_json(game_state: &GameState) -> String {
+    let game = serde_json::to_value(game_state).expect("game state is always serializable");
+    let envelope = serde_json::json!({
+        "version": SAVE_FORMAT_VERSION,
+        "game": game,
+    });
+    serde_json::to_string_pretty(&envelope).expect("json values are always serializable")
+}
+
+pub fn load_json(json: &str) -> Result<GameState, SaveError> {
+    let envelope: Value = serde_json::from_str(json).map_err(|e| SaveError::Corrupt(e.to_string()))?;
+    let version = envelope
+        .get("version")
+        .and_then(Value::as_u64)
+        .ok_or(SaveError::InvalidHeader)? as u32;
+    let game = envelope.get("game").cloned().ok_or(SaveError::InvalidHeader)?;
+    decode(version, game)
+}
+
+// Bring an older payload up to the current version, then decode it
+fn decode(version: u32, mut value: Value) -> Result<GameState, SaveError> {
+    if version == 0 {
+        return Err(SaveError::InvalidHeader);
+    }
+    if version > SAVE_FORMAT_VERSION {
+        return Err(SaveError::UnsupportedVersion(version));
+    }
+    for migration in MIGRATIONS.iter().skip((version - 1) as usize) {
+        value = migration(value)?;
+    }
+    let mut game_state: GameState = serde_json::from_value(value).map_err(|e| SaveError::Corrupt(e.to_string()))?;
+    game_state.map_mut().rebuild_tile_index();
+    Ok(game_state)
+}
+
+// Serde helper for HashMaps keyed by tile position.
+// JSON only allows string keys, so the map is stored as a list of entries sorted by position.
+pub(crate) mod position_map {
+    use std::collections::HashMap;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<T: Serialize, S: Serializer>(map: &HashMap<(u32, u32), T>, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut entries: Vec<(&(u32, u32), &T)> = map.iter().collect();
+        entries.sort_by_key(|(position, _)| **position);
+        serializer.collect_seq(entries)
+    }
+
+    pub fn deserialize<'de, T: Deserialize<'de>, D: Deserializer<'de>>(deserializer: D) -> Result<HashMap<(u32, u32), T>, D::Error> {
+        let entries: Vec<((u32, u32), T)> = Vec::deserialize(deserializer)?;
+        Ok(entries.into_iter().collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::action::Action;
+    use crate::types::Direction;
+    use crate::stats::StatsWindow;
+
+    #[test]
+    fn v4_coal_counts_become_fuel_stores() {
+        let mut game_state = GameState::new();
+        let (x, y) = (game_state.player_x() + 1, game_state.player_y() + 2);
+        game_state.step(&[Action::Place { item: Item::Furnace, x, y, direction: Direction::North }]);
+        for _ in 0..3 {
+            game_state.step(&[Action::Insert { item: Item::Coal, x, y }]);
+        }
+
+        // Write the furnace the way v4 saves stored it
+        let mut envelope: Value = serde_json::from_str(&game_state.save_json()).unwrap();
+        envelope["version"] = Value::from(4);
+        let furnace = envelope
+            .pointer_mut("/game/map/entities")
+            .and_then(Value::as_array_mut)
+            .and_then(|entities| entities.iter_mut().find_map(|entity| entity.pointer_mut("/state/Furnace")))
+            .and_then(Value::as_object_mut)
+            .unwrap();
+        furnace.remove("fuel");
+        furnace.insert("coal_count".to_string(), Value::from(3));
+
+        let loaded = load_json(&envelope.to_string()).unwrap();
+        let fuel = loaded.get_furnace_data(x, y).unwrap().fuel();
+        assert_eq!((fuel.item(), fuel.count()), (Some(Item::Coal), 3));
+        assert_eq!(loaded.save(), game_state.save());
+    }
+
+    #[test]
+    fn v5_iron_and_copper_counts_become_furnace_slots() {
+        let value = serde_json::json!({ "map": { "entities": [{ "id": 0, "state": { "Furnace": {
+            "fuel": FuelStore::coal(2),
+            "iron_ore_count": 3,
+            "iron_plate_count": 0,
+            "copper_count": 1,
+            "copper_plate_count": 4,
+            "processing_ticks_remaining": 20,
+            "processing_type": "IronPlate",
+        } } }] } });
+
+        let migrated = migrate_v5_furnace_slots(value).unwrap();
+        let furnace: FurnaceData = serde_json::from_value(migrated["map"]["entities"][0]["state"]["Furnace"].clone()).unwrap();
+        assert_eq!(furnace.input_items(), vec![Item::IronOre, Item::Copper]);
+        assert_eq!((furnace.input_count(Item::IronOre), furnace.input_count(Item::Copper)), (3, 1));
+        assert_eq!(furnace.output_items(), vec![Item::CopperPlate]);
+        assert_eq!(furnace.output_count(Item::CopperPlate), 4);
+        assert_eq!((furnace.processing_ticks_remaining(), furnace.smelt_ticks()), (20, 60));
+
+        // Furnaces already in the new layout are left alone
+        let fresh = serde_json::json!({ "map": { "entities": [{ "state": { "Furnace": FurnaceData::new() } }] } });
+        assert_eq!(migrate_v5_furnace_slots(fresh.clone()).unwrap(), fresh);
+    }
+
+    #[test]
+    fn v6_saves_start_with_empty_production_stats() {
+        let game_state = busy_game();
+        assert!(game_state.production_stats(Item::Belt, StatsWindow::Hour).produced() > 0);
+
+        let mut envelope: Value = serde_json::from_str(&game_state.save_json()).unwrap();
+        envelope["version"] = Value::from(6);
+        envelope["game"].as_object_mut().unwrap().remove("production");
+
+        let loaded = load_json(&envelope.to_string()).unwrap();
+        assert_eq!(loaded.production_stats(Item::Belt, StatsWindow::Hour).produced(), 0);
+        assert_eq!(loaded.current_tick(), game_state.current_tick());
+    }
+
+    // A game with a bit of everything in it: belts carrying items, a working furnace, a crafting queue
+    fn busy_game() -> GameState {
+        let mut game_state = GameState::new();
+        let (x, y) = (game_state.player_x() + 1, game_state.player_y() + 2);
+        game_state.step(&[
+            Action::PlaceLine { item: Item::Belt, from_x: x, from_y: y, to_x: x + 4, to_y: y },
+            Action::Place { item: Item::Furnace, x, y: y + 2, direction: Direction::North },
+            Action::Place { item: Item::Splitter, x: x + 2, y: y + 3, direction: Direction::East },
+            Action::Craft { recipe: "belt".to_string(), count: 3 },
+        ]);
+        game_state.step(&[Action::Insert { item: Item::Coal, x, y: y + 2 }, Action::Insert { item: Item::IronOre, x, y: y + 2 }]);
+        for _ in 0..3 {
+            game_state.step(&[Action::Drop { item: Item::IronOre, x, y }]);
+        }
+        for _ in 0..40 {
+            game_state.step(&[]);
+        }
+        game_state
+    }
+
+    #[test]
+    fn saves_round_trip_and_keep_running_the_same() {
+        let mut game_state = busy_game();
+        let mut from_binary = load_binary(&save_binary(&game_state)).unwrap();
+        let mut from_json = load_json(&save_json(&game_state)).unwrap();
+        assert_eq!(save_json(&from_binary), save_json(&game_state));
+        assert_eq!(save_binary(&from_json), save_binary(&game_state));
+        let belts_made = |game_state: &GameState| game_state.production_stats(Item::Belt, StatsWindow::Hour).produced();
+        assert!(belts_made(&game_state) > 0);
+        assert_eq!(belts_made(&from_binary), belts_made(&game_state));
+
+        for _ in 0..200 {
+            game_state.step(&[]);
+            from_binary.step(&[]);
+            from_json.step(&[]);
+        }
+        assert_eq!(from_binary.save(), game_state.save());
+        assert_eq!(from_json.save(), game_state.save());
+    }
+
+    #[test]
+    fn unreadable_saves_are_rejected() {
+        let mut newer = save_binary(&GameState::new());
+        newer[4..8].copy_from_slice(&(SAVE_FORMAT_VERSION + 1).to_le_bytes());
+        assert!(matches!(load_binary(&newer), Err(SaveError::UnsupportedVersion(v)) if v == SAVE_FORMAT_VERSION + 1));
+        assert!(matches!(load_binary(b"FSA"), Err(SaveError::InvalidHeader)));
+        assert!(matches!(load_binary(b"NOPE\x01\0\0\0"), Err(SaveError::InvalidHeader)));
+        assert!(matches!(load_binary(&newer[..8]), Err(SaveError::Corrupt(_))));
+        assert!(matches!(load_json("{\"game\": {}}"), Err(SaveError::InvalidHeader)));
+        assert!(matches!(load_json("not json"), Err(SaveError::Corrupt(_))));
+    }
+
+    #[test]
+    fn v1_inventory_lists_become_stacked_slots() {
+        let mut items = vec![Item::Coal; 3];
+        items.extend(vec![Item::IronOre; Item::IronOre.stack_size() as usize + 5]);
+        let value = serde_json::json!({ "player": { "inventory": { "items": items } } });
+
+        let migrated = migrate_v1_inventory_slots(value).unwrap();
+        let slots = migrated.pointer("/player/inventory/slots").and_then(Value::as_array).unwrap();
+        assert_eq!(slots.len(), INVENTORY_SLOTS);
+        let stacks: Vec<(Item, u32)> = slots
+            .iter()
+            .filter(|slot| !slot.is_null())
+            .map(|slot| (serde_json::from_value(slot["item"].clone()).unwrap(), slot["count"].as_u64().unwrap() as u32))
+            .collect();
+        assert_eq!(stacks, vec![(Item::IronOre, Item::IronOre.stack_size()), (Item::IronOre, 5), (Item::Coal, 3)]);
+
+        assert!(matches!(migrate_v1_inventory_slots(serde_json::json!({})), Err(SaveError::Corrupt(_))));
+    }
+
+    #[test]
+    fn v2_per_type_lists_become_one_entity_store() {
+        let furnace = serde_json::to_value(FurnaceData::new()).unwrap();
+        let value = serde_json::json!({ "map": {
+            "placeable_objects": [
+                { "x": 1, "y": 2, "direction": "North", "placeable_type": "Furnace" },
+                { "x": 3, "y": 4, "direction": "East", "placeable_type": "Belt" },
+                { "x": 5, "y": 6, "direction": "East", "placeable_type": "Chest" },
+            ],
+            "furnace_data": [[[1, 2], furnace]],
+        } });
+
+        let migrated = migrate_v2_entity_store(value).unwrap();
+        let map = &migrated["map"];
+        assert_eq!(map["next_entity_id"], 3);
+        assert!(map.get("placeable_objects").is_none() && map.get("furnace_data").is_none());
+        let entities = map["entities"].as_array().unwrap();
+        assert_eq!(entities.iter().map(|entity| entity["id"].as_u64().unwrap()).collect::<Vec<_>>(), vec![0, 1, 2]);
+        assert_eq!(entities[0]["state"]["Furnace"], furnace);
+        assert_eq!(entities[1]["state"], "Belt");
+        // The chest had no data list entry, so it starts empty
+        assert_eq!(entities[2]["state"]["Chest"], serde_json::to_value(ChestData::new()).unwrap());
+
+        let unknown = serde_json::json!({ "map": { "placeable_objects": [{ "x": 0, "y": 0, "placeable_type": "Rocket" }] } });
+        assert!(matches!(migrate_v2_entity_store(unknown), Err(SaveError::Corrupt(_))));
+    }
+
+    #[test]
+    fn v3_belt_items_become_lists_in_the_middle_of_the_tile() {
+        let value = serde_json::json!({ "map": {
+            "belt_items": [[[1, 2], { "x": 1, "y": 2, "item": "IronOre", "quantity": 1 }]],
+        } });
+
+        let migrated = migrate_v3_belt_lists(value).unwrap();
+        assert_eq!(migrated["map"]["belt_speed"], DEFAULT_BELT_SPEED);
+        let tile = &migrated["map"]["belt_items"][0];
+        assert_eq!(tile[0], serde_json::json!([1, 2]));
+        assert_eq!(tile[1].as_array().unwrap().len(), 1);
+        assert_eq!(tile[1][0]["item"], "IronOre");
+        assert_eq!(tile[1][0]["progress"], BELT_INSERT_PROGRESS);
+    }
+}
@@ -0,0 +1,109 @@
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+use serde::{Serialize, Deserialize};
+use crate::types::Item;
+
+// One half of a splitter, looking along it. The left half is the tile it was placed on,
+// the right half the tile to its right.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SplitterSide {
+    #[default]
+    Left,
+    Right,
+}
+
+impl SplitterSide {
+    pub const ALL: [SplitterSide; 2] = [SplitterSide::Left, SplitterSide::Right];
+
+    pub fn other(&self) -> SplitterSide {
+        match self {
+            SplitterSide::Left => SplitterSide::Right,
+            SplitterSide::Right => SplitterSide::Left,
+        }
+    }
+
+    // Next setting when cycling a priority: none -> left -> right -> none
+    pub fn cycle(priority: Option<SplitterSide>) -> Option<SplitterSide> {
+        match priority {
+            None => Some(SplitterSide::Left),
+            Some(SplitterSide::Left) => Some(SplitterSide::Right),
+            Some(SplitterSide::Right) => None,
+        }
+    }
+}
+
+// Splitter data - settings plus which side is next when alternating.
+// Without settings a splitter takes items from both inputs in turn, and each input sends its items to both outputs in turn.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct SplitterData {
+    input_priority: Option<SplitterSide>,  // Input served first when both have items waiting
+    output_priority: Option<SplitterSide>, // Output filled first, the other only gets the overflow
+    filter: Option<Item>, // Only this item goes to the priority output (left without one), everything else to the other
+    next_input: SplitterSide,
+    next_output: [SplitterSide; 2], // For each input
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl SplitterData {
+    #[cfg_attr(feature = "wasm", wasm_bindgen(constructor))]
+    pub fn new() -> SplitterData {
+        SplitterData::default()
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn input_priority(&self) -> Option<SplitterSide> {
+        self.input_priority
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn output_priority(&self) -> Option<SplitterSide> {
+        self.output_priority
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn filter(&self) -> Option<Item> {
+        self.filter
+    }
+}
+
+impl SplitterData {
+    pub(crate) fn configure(&mut self, input_priority: Option<SplitterSide>, output_priority: Option<SplitterSide>, filter: Option<Item>) {
+        self.input_priority = input_priority;
+        self.output_priority = output_priority;
+        self.filter = filter;
+    }
+
+    // Inputs in the order they get to send items this tick (alternating each tick without a priority)
+    pub(crate) fn input_order(&mut self) -> [SplitterSide; 2] {
+        let first = match self.input_priority {
+            Some(priority) => priority,
+            None => {
+                let first = self.next_input;
+                self.next_input = first.other();
+                first
+            }
+        };
+        [first, first.other()]
+    }
+
+    // Outputs an item from an input may go to, best first.
+    // A filtered splitter has one place for each item and waits for it to have room.
+    pub(crate) fn output_order(&self, input: SplitterSide, item: Item) -> Vec<SplitterSide> {
+        let next_output = self.next_output[input as usize];
+        match (self.filter, self.output_priority) {
+            (Some(filter), priority) => {
+                let filter_side = priority.unwrap_or_default();
+                vec![if item == filter { filter_side } else { filter_side.other() }]
+            }
+            (None, Some(priority)) => vec![priority, priority.other()],
+            (None, None) => vec![next_output, next_output.other()],
+        }
+    }
+
+    // An item from an input went out on this side - the input's next item tries the other side first
+    pub(crate) fn item_sent(&mut self, input: SplitterSide, output: SplitterSide) {
+        self.next_output[input as usize] = output.other();
+    }
+}
@@ -0,0 +1,218 @@
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+use serde::{Serialize, Deserialize};
+use crate::types::{Item, ITEM_DEFS};
+
+// Every window is split into this many buckets, so totals roll over in 1/60th steps
+const BUCKETS: usize = 60;
+
+// Rolling time windows for production statistics (at 60 ticks per second)
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StatsWindow {
+    Second,
+    Minute,
+    TenMinutes,
+    Hour,
+}
+
+impl StatsWindow {
+    const ALL: [StatsWindow; 4] = [StatsWindow::Second, StatsWindow::Minute, StatsWindow::TenMinutes, StatsWindow::Hour];
+
+    pub fn ticks(&self) -> u64 {
+        match self {
+            StatsWindow::Second => 60,
+            StatsWindow::Minute => 60 * 60,
+            StatsWindow::TenMinutes => 10 * 60 * 60,
+            StatsWindow::Hour => 60 * 60 * 60,
+        }
+    }
+
+    fn bucket_ticks(&self) -> u64 {
+        self.ticks() / BUCKETS as u64
+    }
+}
+
+// Which way an item moved
+#[derive(Clone, Copy)]
+enum Flow {
+    Produced,  // Crafted, smelted, drilled or mined
+    Consumed,  // Used up as an ingredient or fuel
+    Stored,    // Put into a chest
+    Retrieved, // Taken out of a chest
+}
+
+const FLOWS: usize = 4;
+
+// Bucketed counts of one item: [flow][window][bucket]
+#[derive(Clone)]
+struct ItemCounters {
+    buckets: [[[u32; BUCKETS]; StatsWindow::ALL.len()]; FLOWS],
+}
+
+impl Default for ItemCounters {
+    fn default() -> Self {
+        ItemCounters {
+            buckets: [[[0; BUCKETS]; StatsWindow::ALL.len()]; FLOWS],
+        }
+    }
+}
+
+// Per-item production counters over rolling windows, saved with the game
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(into = "SavedStats", try_from = "SavedStats")]
+pub struct ProductionStats {
+    current_tick: u64,
+    items: Vec<ItemCounters>, // Indexed by registry order
+}
+
+impl Default for ProductionStats {
+    fn default() -> Self {
+        ProductionStats {
+            current_tick: 0,
+            items: vec![ItemCounters::default(); ITEM_DEFS.len()],
+        }
+    }
+}
+
+// Saved form of the stats: each item's buckets flattened in [flow][window][bucket] order.
+// Only items that moved are listed, keyed by item so registry changes can't shift counts between items.
+#[derive(Serialize, Deserialize)]
+struct SavedStats {
+    current_tick: u64,
+    items: Vec<(Item, Vec<u32>)>,
+}
+
+impl From<ProductionStats> for SavedStats {
+    fn from(stats: ProductionStats) -> SavedStats {
+        let items = Item::all()
+            .zip(stats.items.iter())
+            .map(|(item, counters)| (item, counters.buckets.iter().flatten().flatten().copied().collect::<Vec<u32>>()))
+            .filter(|(_, buckets)| buckets.iter().any(|count| *count > 0))
+            .collect();
+        SavedStats { current_tick: stats.current_tick, items }
+    }
+}
+
+impl TryFrom<SavedStats> for ProductionStats {
+    type Error = String;
+
+    fn try_from(saved: SavedStats) -> Result<ProductionStats, String> {
+        let mut stats = ProductionStats { current_tick: saved.current_tick, ..ProductionStats::default() };
+        for (item, buckets) in saved.items {
+            if buckets.len() != FLOWS * StatsWindow::ALL.len() * BUCKETS {
+                return Err(format!("{} stats buckets saved for {:?}", buckets.len(), item));
+            }
+            let slots = stats.items[item as usize].buckets.iter_mut().flatten().flatten();
+            for (slot, count) in slots.zip(buckets) {
+                *slot = count;
+            }
+        }
+        Ok(stats)
+    }
+}
+
+impl ProductionStats {
+    // Move to a new tick, clearing buckets that fell out of their window
+    pub fn advance_to(&mut self, tick: u64) {
+        for (window_index, window) in StatsWindow::ALL.iter().enumerate() {
+            let last = self.current_tick / window.bucket_ticks();
+            let next = tick / window.bucket_ticks();
+            // Buckets between the two ticks are reused - clear them (at most one full lap)
+            for bucket in (last + 1..=next).take(BUCKETS) {
+                let index = bucket as usize % BUCKETS;
+                for counters in self.items.iter_mut() {
+                    for flow in counters.buckets.iter_mut() {
+                        flow[window_index][index] = 0;
+                    }
+                }
+            }
+        }
+        self.current_tick = tick;
+    }
+
+    fn record(&mut self, flow: Flow, item: Item, count: u32) {
+        let counters = &mut self.items[item as usize].buckets[flow as usize];
+        for (window_index, window) in StatsWindow::ALL.iter().enumerate() {
+            let index = (self.current_tick / window.bucket_ticks()) as usize % BUCKETS;
+            counters[window_index][index] += count;
+        }
+    }
+
+    pub fn produced(&mut self, item: Item, count: u32) {
+        self.record(Flow::Produced, item, count);
+    }
+
+    pub fn consumed(&mut self, item: Item, count: u32) {
+        self.record(Flow::Consumed, item, count);
+    }
+
+    pub fn stored(&mut self, item: Item, count: u32) {
+        self.record(Flow::Stored, item, count);
+    }
+
+    pub fn retrieved(&mut self, item: Item, count: u32) {
+        self.record(Flow::Retrieved, item, count);
+    }
+
+    fn total(&self, flow: Flow, item: Item, window: StatsWindow) -> u32 {
+        self.items[item as usize].buckets[flow as usize][window as usize].iter().sum()
+    }
+
+    // Totals for one item over a window (the current bucket included)
+    pub fn item_stats(&self, item: Item, window: StatsWindow) -> ItemStats {
+        ItemStats {
+            item,
+            window_ticks: window.ticks(),
+            produced: self.total(Flow::Produced, item, window),
+            consumed: self.total(Flow::Consumed, item, window),
+            stored: self.total(Flow::Stored, item, window),
+            retrieved: self.total(Flow::Retrieved, item, window),
+        }
+    }
+}
+
+// How much of an item moved during a window
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy, Debug)]
+pub struct ItemStats {
+    item: Item,
+    window_ticks: u64,
+    produced: u32,
+    consumed: u32,
+    stored: u32,
+    retrieved: u32,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl ItemStats {
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn item(&self) -> Item {
+        self.item
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn window_ticks(&self) -> u64 {
+        self.window_ticks
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn produced(&self) -> u32 {
+        self.produced
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn consumed(&self) -> u32 {
+        self.consumed
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn stored(&self) -> u32 {
+        self.stored
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn retrieved(&self) -> u32 {
+        self.retrieved
+    }
+}
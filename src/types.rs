@@ -1,28 +1,139 @@
+#[cfg(feature = "wasm")]
 use wasm_bindgen::prelude::*;
+use serde::{Serialize, Deserialize};
 
-// Item enum for inventory
-#[wasm_bindgen]
-#[derive(Clone)]
-pub enum Item {
-    IronOre,
-    Copper,
-    Stone,
-    Coal,
-    Furnace,
-    IronPlate,
-    Belt,
-    CopperPlate,
-    Arm,
-    Chest,
-    Drill,
+// Item category, for grouping items in UIs and tools (machines go by fuel value and their own inputs)
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ItemCategory {
+    Resource,     // Mined from the map
+    Intermediate, // Smelted or crafted, used in recipes
+    Placeable,    // Can be built on the map
+}
+
+// Static data for one item
+#[derive(Debug)]
+pub struct ItemDef {
+    pub item: Item,
+    pub id: &'static str, // Stable identifier for saves, replays and tools
+    pub name: &'static str,
+    pub stack_size: u32,
+    pub fuel_value: u32, // Machine cycles one unit of this item powers (0 = not a fuel)
+    pub category: ItemCategory,
+    pub places: Option<PlaceableType>,
+}
+
+// Generates the Item enum and its registry from one table, so they can't get out of sync
+macro_rules! define_items {
+    ($($variant:ident {
+        id: $id:literal,
+        name: $name:literal,
+        stack_size: $stack_size:literal,
+        fuel_value: $fuel_value:literal,
+        category: $category:ident,
+        places: $places:expr $(,)?
+    }),* $(,)?) => {
+        // Item enum for inventory
+        #[cfg_attr(feature = "wasm", wasm_bindgen)]
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+        pub enum Item {
+            $($variant,)*
+        }
+
+        // Item registry, in inventory display order
+        pub const ITEM_DEFS: &[ItemDef] = &[
+            $(ItemDef {
+                item: Item::$variant,
+                id: $id,
+                name: $name,
+                stack_size: $stack_size,
+                fuel_value: $fuel_value,
+                category: ItemCategory::$category,
+                places: $places,
+            },)*
+        ];
+    };
+}
+
+// Every item in the game - add new items here
+define_items! {
+    IronOre { id: "iron_ore", name: "Iron Ore", stack_size: 50, fuel_value: 0, category: Resource, places: None },
+    Copper { id: "copper", name: "Copper", stack_size: 50, fuel_value: 0, category: Resource, places: None },
+    Stone { id: "stone", name: "Stone", stack_size: 50, fuel_value: 0, category: Resource, places: None },
+    Coal { id: "coal", name: "Coal", stack_size: 50, fuel_value: 1, category: Resource, places: None },
+    Furnace { id: "furnace", name: "Furnace", stack_size: 50, fuel_value: 0, category: Placeable, places: Some(PlaceableType::Furnace) },
+    IronPlate { id: "iron_plate", name: "Iron Plate", stack_size: 100, fuel_value: 0, category: Intermediate, places: None },
+    Belt { id: "belt", name: "Belt", stack_size: 100, fuel_value: 0, category: Placeable, places: Some(PlaceableType::Belt) },
+    CopperPlate { id: "copper_plate", name: "Copper Plate", stack_size: 100, fuel_value: 0, category: Intermediate, places: None },
+    IronGear { id: "iron_gear", name: "Iron Gear", stack_size: 100, fuel_value: 0, category: Intermediate, places: None },
+    Arm { id: "arm", name: "Arm", stack_size: 50, fuel_value: 0, category: Placeable, places: Some(PlaceableType::Arm) },
+    Chest { id: "chest", name: "Chest", stack_size: 50, fuel_value: 0, category: Placeable, places: Some(PlaceableType::Chest) },
+    Drill { id: "drill", name: "Drill", stack_size: 50, fuel_value: 0, category: Placeable, places: Some(PlaceableType::Drill) },
+    UndergroundBelt { id: "underground_belt", name: "Underground Belt", stack_size: 50, fuel_value: 0, category: Placeable, places: Some(PlaceableType::UndergroundBelt) },
+    Splitter { id: "splitter", name: "Splitter", stack_size: 50, fuel_value: 0, category: Placeable, places: Some(PlaceableType::Splitter) },
+}
+
+impl Item {
+    pub fn def(&self) -> &'static ItemDef {
+        &ITEM_DEFS[*self as usize]
+    }
+
+    // All items in registry order
+    pub fn all() -> impl Iterator<Item = Item> {
+        ITEM_DEFS.iter().map(|def| def.item)
+    }
+
+    pub fn from_id(id: &str) -> Option<Item> {
+        ITEM_DEFS.iter().find(|def| def.id == id).map(|def| def.item)
+    }
+
+    pub fn id(&self) -> &'static str {
+        self.def().id
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.def().name
+    }
+
+    pub fn stack_size(&self) -> u32 {
+        self.def().stack_size
+    }
+
+    pub fn fuel_value(&self) -> u32 {
+        self.def().fuel_value
+    }
+
+    pub fn is_fuel(&self) -> bool {
+        self.def().fuel_value > 0
+    }
+
+    pub fn category(&self) -> ItemCategory {
+        self.def().category
+    }
+
+    // The placeable this item builds, if any
+    pub fn placeable_type(&self) -> Option<PlaceableType> {
+        self.def().places
+    }
+}
+
+// Item lookups for JavaScript (wasm-bindgen can't export methods on enums)
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn item_name(item: Item) -> String {
+    item.name().to_string()
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn item_id(item: Item) -> String {
+    item.id().to_string()
 }
 
 // Direction enum
-#[wasm_bindgen]
-#[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
 pub enum Direction {
     North,
     South,
+    #[default]
     East,
     West,
 }
@@ -36,17 +147,38 @@ impl Direction {
             Direction::West => 3,
         }
     }
+
+    // North -> East -> South -> West -> North
+    pub fn rotated_clockwise(&self) -> Direction {
+        match self {
+            Direction::North => Direction::East,
+            Direction::East => Direction::South,
+            Direction::South => Direction::West,
+            Direction::West => Direction::North,
+        }
+    }
+
+    pub fn opposite(&self) -> Direction {
+        match self {
+            Direction::North => Direction::South,
+            Direction::South => Direction::North,
+            Direction::East => Direction::West,
+            Direction::West => Direction::East,
+        }
+    }
 }
 
 // Placeable object enum
-#[wasm_bindgen]
-#[derive(Clone, Copy)]
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub enum PlaceableType {
     Furnace,
     Belt,
     Arm,
     Chest,
     Drill,
+    UndergroundBelt,
+    Splitter,
 }
 
 impl PlaceableType {
@@ -57,13 +189,35 @@ impl PlaceableType {
             PlaceableType::Arm => 2,
             PlaceableType::Chest => 3,
             PlaceableType::Drill => 4,
+            PlaceableType::UndergroundBelt => 5,
+            PlaceableType::Splitter => 6,
         }
     }
+
+    // The item that builds this placeable
+    pub fn item(&self) -> Item {
+        ITEM_DEFS
+            .iter()
+            .find(|def| def.places == Some(*self))
+            .map(|def| def.item)
+            .expect("every placeable has an item in the registry")
+    }
+
+    // Whether 'r' rotates it once placed.
+    // Underground belts keep the direction they were paired along, splitters the two tiles they cover.
+    pub fn is_rotatable(&self) -> bool {
+        matches!(self, PlaceableType::Belt | PlaceableType::Arm | PlaceableType::Drill)
+    }
+
+    // Whether it carries items along in Map::belt_items
+    pub fn is_belt(&self) -> bool {
+        matches!(self, PlaceableType::Belt | PlaceableType::UndergroundBelt | PlaceableType::Splitter)
+    }
 }
 
 // Resource type enum
-#[wasm_bindgen]
-#[derive(Clone, Copy)]
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub enum ResourceType {
     IronOre,
     Copper,
@@ -80,23 +234,14 @@ impl ResourceType {
             ResourceType::Coal => 3,
         }
     }
-}
 
-impl Item {
-    pub fn name(&self) -> &'static str {
+    // The item mining this resource yields
+    pub fn item(&self) -> Item {
         match self {
-            Item::IronOre => "Iron Ore",
-            Item::Copper => "Copper",
-            Item::Stone => "Stone",
-            Item::Coal => "Coal",
-            Item::Furnace => "Furnace",
-            Item::IronPlate => "Iron Plate",
-            Item::Belt => "Belt",
-            Item::CopperPlate => "Copper Plate",
-            Item::Arm => "Arm",
-            Item::Chest => "Chest",
-            Item::Drill => "Drill",
+            ResourceType::IronOre => Item::IronOre,
+            ResourceType::Copper => Item::Copper,
+            ResourceType::Stone => Item::Stone,
+            ResourceType::Coal => Item::Coal,
         }
     }
 }
-
@@ -0,0 +1,71 @@
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+use serde::{Serialize, Deserialize};
+
+// Most tiles an underground belt can pass under between its entry and exit
+pub const UNDERGROUND_MAX_LENGTH: u32 = 4;
+
+// Which end of a tunnel an underground belt is
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum UndergroundRole {
+    #[default]
+    Entry, // Takes items in from behind and sends them down the tunnel
+    Exit,  // Brings items up out of the tunnel and sends them on ahead
+}
+
+// Underground belt data - which end it is and where the other end is.
+// A new underground belt pairs with the nearest unpaired one facing the same way within
+// UNDERGROUND_MAX_LENGTH tiles: it becomes the exit of one behind it, or the entry of one ahead of it.
+// Unpaired underground belts are entries.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct UndergroundBeltData {
+    role: UndergroundRole,
+    partner: Option<(u32, u32)>, // Position of the other end
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl UndergroundBeltData {
+    #[cfg_attr(feature = "wasm", wasm_bindgen(constructor))]
+    pub fn new() -> UndergroundBeltData {
+        UndergroundBeltData::default()
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn role(&self) -> UndergroundRole {
+        self.role
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn is_paired(&self) -> bool {
+        self.partner.is_some()
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn partner_x(&self) -> Option<u32> {
+        self.partner.map(|(x, _)| x)
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn partner_y(&self) -> Option<u32> {
+        self.partner.map(|(_, y)| y)
+    }
+}
+
+impl UndergroundBeltData {
+    pub fn partner(&self) -> Option<(u32, u32)> {
+        self.partner
+    }
+
+    pub(crate) fn pair(&mut self, role: UndergroundRole, partner: (u32, u32)) {
+        self.role = role;
+        self.partner = Some(partner);
+    }
+
+    // Back to an unpaired entry (when the other end is picked up)
+    pub(crate) fn unpair(&mut self) {
+        self.role = UndergroundRole::Entry;
+        self.partner = None;
+    }
+}
@@ -0,0 +1,100 @@
+// Browser bindings that need js_sys; everything else is exported through cfg_attr in its own module
+use wasm_bindgen::prelude::*;
+use std::cell::RefCell;
+use crate::game_state::GameState;
+use crate::keymap::KeyState;
+
+thread_local! {
+    // JS event callbacks. JS values can't leave their thread, so listeners only hold an index into this.
+    static JS_LISTENERS: RefCell<Vec<js_sys::Function>> = const { RefCell::new(Vec::new()) };
+}
+
+// Convert the `{ key: pressed }` object built by main.ts into a KeyState
+fn key_state_from_js(keys: &js_sys::Object) -> KeyState {
+    let mut key_state = KeyState::new();
+    for entry in js_sys::Object::entries(keys).iter() {
+        let pair = js_sys::Array::from(&entry);
+        if pair.get(1).as_bool().unwrap_or(false) {
+            if let Some(key) = pair.get(0).as_string() {
+                key_state.press(&key);
+            }
+        }
+    }
+    key_state
+}
+
+#[wasm_bindgen]
+impl GameState {
+    // Browser games get a fresh world each page load
+    #[wasm_bindgen(constructor)]
+    pub fn new_js() -> GameState {
+        GameState::with_seed(js_sys::Date::now() as u64)
+    }
+
+    #[wasm_bindgen(js_name = save)]
+    pub fn save_js(&self) -> Vec<u8> {
+        self.save()
+    }
+
+    #[wasm_bindgen(js_name = load)]
+    pub fn load_js(bytes: &[u8]) -> Result<GameState, JsError> {
+        GameState::load(bytes).map_err(|e| JsError::new(&e.to_string()))
+    }
+
+    #[wasm_bindgen(js_name = save_json)]
+    pub fn save_json_js(&self) -> String {
+        self.save_json()
+    }
+
+    #[wasm_bindgen(js_name = load_json)]
+    pub fn load_json_js(json: &str) -> Result<GameState, JsError> {
+        GameState::load_json(json).map_err(|e| JsError::new(&e.to_string()))
+    }
+
+    #[wasm_bindgen(js_name = start_recording)]
+    pub fn start_recording_js(&mut self) {
+        self.start_recording();
+    }
+
+    // Stop recording and return the replay file (undefined if nothing was being recorded)
+    #[wasm_bindgen(js_name = stop_recording)]
+    pub fn stop_recording_js(&mut self) -> Option<Vec<u8>> {
+        self.stop_recording().map(|replay| replay.to_bytes())
+    }
+
+    // Rebuild a game from a replay file and play it to the end
+    #[wasm_bindgen(js_name = play_replay)]
+    pub fn play_replay_js(bytes: &[u8]) -> Result<GameState, JsError> {
+        crate::replay::Replay::from_bytes(bytes)
+            .and_then(|replay| replay.play())
+            .map_err(|e| JsError::new(&e.to_string()))
+    }
+
+    // Events since the last call, as a JSON array of { tick, event } records
+    #[wasm_bindgen(js_name = drain_events)]
+    pub fn drain_events_js(&mut self) -> String {
+        serde_json::to_string(&self.drain_events()).expect("events are always serializable")
+    }
+
+    // Call callback with each event (as a JSON record) as it happens
+    #[wasm_bindgen(js_name = subscribe)]
+    pub fn subscribe_js(&mut self, callback: js_sys::Function) {
+        let index = JS_LISTENERS.with(|listeners| {
+            let mut listeners = listeners.borrow_mut();
+            listeners.push(callback);
+            listeners.len() - 1
+        });
+        self.subscribe(Box::new(move |record| {
+            let json = serde_json::to_string(record).expect("events are always serializable");
+            // Cloned out so the callback can subscribe again without a double borrow
+            if let Some(callback) = JS_LISTENERS.with(|listeners| listeners.borrow().get(index).cloned()) {
+                let _ = callback.call1(&JsValue::NULL, &JsValue::from_str(&json));
+            }
+        }));
+    }
+
+    #[wasm_bindgen(js_name = next_step)]
+    pub fn next_step_js(&mut self, keys: &js_sys::Object, cursor_x: Option<u32>, cursor_y: Option<u32>) {
+        self.next_step(&key_state_from_js(keys), cursor_x, cursor_y);
+    }
+}